@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ErrorCode;
+
+/// Limits on the structure of JSON documents received from untrusted parties
+///
+/// Deeply nested or oversized documents can exhaust the stack or memory of the deserializer, so
+/// every external JSON input is checked against these limits before it is deserialized.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonLimits {
+    /// Maximum nesting depth of arrays and objects
+    pub max_depth: usize,
+    /// Maximum length of a string in bytes, as encoded in the document
+    pub max_string_length: usize,
+    /// Maximum number of elements of an array
+    pub max_array_length: usize,
+}
+
+impl Default for JsonLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 32,
+            max_string_length: 1 << 20,
+            // Proofs encode the revealed bytes of the transcript as arrays of numbers
+            max_array_length: 1 << 20,
+        }
+    }
+}
+
+/// Error returned when a JSON document exceeds one of the [JsonLimits]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum JsonLimitError {
+    /// Arrays and objects are nested deeper than the limit
+    TooDeep(usize),
+    /// A string is longer than the limit
+    StringTooLong(usize),
+    /// An array has more elements than the limit
+    ArrayTooLong(usize),
+}
+
+impl JsonLimitError {
+    /// Returns the machine-readable code of the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::TooDeep(_) => ErrorCode::JsonTooDeep,
+            Self::StringTooLong(_) => ErrorCode::JsonStringTooLong,
+            Self::ArrayTooLong(_) => ErrorCode::JsonArrayTooLong,
+        }
+    }
+
+    /// Returns the limit which was exceeded
+    pub fn limit(&self) -> usize {
+        match self {
+            Self::TooDeep(limit) | Self::StringTooLong(limit) | Self::ArrayTooLong(limit) => *limit,
+        }
+    }
+}
+
+impl std::fmt::Display for JsonLimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooDeep(limit) => write!(f, "JSON is nested deeper than {limit} levels"),
+            Self::StringTooLong(limit) => write!(f, "JSON string is longer than {limit} bytes"),
+            Self::ArrayTooLong(limit) => write!(f, "JSON array has more than {limit} elements"),
+        }
+    }
+}
+
+impl std::error::Error for JsonLimitError {}
+
+impl JsonLimits {
+    /// Checks that a JSON document does not exceed the limits
+    ///
+    /// The document is scanned without being parsed, using memory proportional to its depth, so
+    /// it can be deserialized safely afterwards. Malformed documents are left for the deserializer
+    /// to reject.
+    pub fn check(&self, json: &[u8]) -> Result<(), JsonLimitError> {
+        // Number of elements of each open container, or `None` for objects
+        let mut containers: Vec<Option<usize>> = Vec::new();
+        let mut string_length = None;
+        let mut escaped = false;
+
+        for &byte in json {
+            if let Some(length) = string_length.as_mut() {
+                if escaped {
+                    escaped = false;
+                } else if byte == b'\\' {
+                    escaped = true;
+                } else if byte == b'"' {
+                    string_length = None;
+                    continue;
+                }
+
+                *length += 1;
+                if *length > self.max_string_length {
+                    return Err(JsonLimitError::StringTooLong(self.max_string_length));
+                }
+                continue;
+            }
+
+            if byte.is_ascii_whitespace() {
+                continue;
+            }
+
+            // The first value of an array
+            if let Some(Some(elements @ 0)) = containers.last_mut() {
+                if byte != b']' {
+                    *elements = 1;
+                    if *elements > self.max_array_length {
+                        return Err(JsonLimitError::ArrayTooLong(self.max_array_length));
+                    }
+                }
+            }
+
+            match byte {
+                b'[' | b'{' => {
+                    if containers.len() >= self.max_depth {
+                        return Err(JsonLimitError::TooDeep(self.max_depth));
+                    }
+                    containers.push((byte == b'[').then_some(0));
+                }
+                b']' | b'}' => {
+                    containers.pop();
+                }
+                b',' => {
+                    if let Some(Some(elements)) = containers.last_mut() {
+                        *elements += 1;
+                        if *elements > self.max_array_length {
+                            return Err(JsonLimitError::ArrayTooLong(self.max_array_length));
+                        }
+                    }
+                }
+                b'"' => string_length = Some(0),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const LIMITS: JsonLimits = JsonLimits {
+        max_depth: 2,
+        max_string_length: 4,
+        max_array_length: 3,
+    };
+
+    #[test]
+    fn test_within_limits() {
+        assert_eq!(LIMITS.check(br#"{"a": [1, 2, 3], "bcd": "e\"f"}"#), Ok(()));
+        assert_eq!(LIMITS.check(br#"[[], [1, 2], "[{,"]"#), Ok(()));
+        assert_eq!(LIMITS.check(b"[ ]"), Ok(()));
+    }
+
+    #[test]
+    fn test_exceeds_limits() {
+        assert_eq!(
+            LIMITS.check(br#"{"a": [{"b": 1}]}"#),
+            Err(JsonLimitError::TooDeep(2))
+        );
+        assert_eq!(
+            LIMITS.check(br#"{"a": "bcdef"}"#),
+            Err(JsonLimitError::StringTooLong(4))
+        );
+        assert_eq!(
+            LIMITS.check(br#"{"a": "b\"cd"}"#),
+            Err(JsonLimitError::StringTooLong(4))
+        );
+        assert_eq!(
+            LIMITS.check(br#"{"abcde": 1}"#),
+            Err(JsonLimitError::StringTooLong(4))
+        );
+        assert_eq!(
+            LIMITS.check(b"[1, [], {}, 4]"),
+            Err(JsonLimitError::ArrayTooLong(3))
+        );
+        assert_eq!(
+            LIMITS.check(br#"[[1, 2, 3, 4]]"#),
+            Err(JsonLimitError::ArrayTooLong(3))
+        );
+    }
+}
@@ -0,0 +1,579 @@
+//! Types of the notary server HTTP API, shared by the server and its clients.
+
+#![deny(missing_docs, unreachable_pub, unused_must_use)]
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+mod json_limits;
+
+use serde::{Deserialize, Serialize};
+
+pub use json_limits::{JsonLimitError, JsonLimits};
+pub use tlsn_units::ByteCount;
+
+/// Response object of the /info API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfoResponse {
+    /// Current version of notary-server
+    pub version: String,
+    /// Public key of the default notary signing key
+    pub public_key: String,
+    /// Public keys of all notary signing keys, one per supported signature algorithm
+    #[serde(default)]
+    pub public_keys: Vec<PublicKeyInfo>,
+    /// Current git commit hash of notary-server
+    pub git_commit_hash: String,
+    /// Current git commit timestamp of notary-server
+    pub git_commit_timestamp: String,
+}
+
+/// Response object of the /session API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotarizationSessionResponse {
+    /// Unique session id that is generated by notary and shared to prover
+    pub session_id: String,
+    /// Algorithm of the signature over the session header, selected by the notary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_algorithm: Option<SignatureAlgorithm>,
+}
+
+/// Request object of the /session API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotarizationSessionRequest {
+    /// Type of client that the prover is using
+    pub client_type: ClientType,
+    /// Maximum data that can be sent by the prover
+    pub max_sent_data: Option<ByteCount>,
+    /// Maximum data that can be received by the prover
+    pub max_recv_data: Option<ByteCount>,
+    /// Algorithm of the signature over the session header, which defaults to the algorithm of the
+    /// default notary signing key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_algorithm: Option<SignatureAlgorithm>,
+}
+
+/// Request object of the /estimate API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotarizationEstimateRequest {
+    /// Maximum data that can be sent by the prover
+    pub max_sent_data: Option<ByteCount>,
+    /// Maximum data that can be received by the prover
+    pub max_recv_data: Option<ByteCount>,
+}
+
+/// Response object of the /estimate API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotarizationEstimateResponse {
+    /// Maximum data that can be sent by the prover, after applying defaults
+    pub max_sent_data: ByteCount,
+    /// Maximum data that can be received by the prover, after applying defaults
+    pub max_recv_data: ByteCount,
+    /// Estimated number of oblivious transfers performed during the session
+    pub ot_count: usize,
+    /// Estimate of the bytes exchanged between prover and notary during MPC, scaled from the
+    /// bytes observed in past notarizations. Not set until a notarization has completed
+    #[serde(default)]
+    pub estimated_bandwidth: Option<ByteCount>,
+    /// Expected milliseconds until a session started now completes, assuming it waits for the
+    /// active and pending sessions and takes the average duration of past notarizations. Not set
+    /// until a notarization has completed
+    #[serde(default)]
+    pub expected_wall_time_ms: Option<u64>,
+    /// Number of sessions that have been initialized but not yet upgraded to a notarization, the
+    /// same as [LoadResponse::queued_sessions]
+    pub pending_sessions: usize,
+    /// Global limit for maximum transcript size in bytes
+    pub max_transcript_size: ByteCount,
+}
+
+/// Response object of the /load API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadResponse {
+    /// Number of notarizations currently running
+    pub active_sessions: usize,
+    /// Number of sessions that have been initialized but not yet upgraded to a notarization
+    pub queued_sessions: usize,
+    /// Estimated seconds of work to finish the active and queued sessions, based on the average
+    /// duration of past notarizations
+    pub backlog_seconds: u64,
+    /// Whether new sessions are currently accepted
+    pub accepting: bool,
+    /// Skew of the server clock in milliseconds relative to its time sources, positive if the time
+    /// sources are ahead. Not set if the clock has not been checked
+    #[serde(default)]
+    pub clock_skew_ms: Option<i64>,
+    /// Statistics of the WebSocket proxy. Not set if the proxy is not enabled
+    #[serde(default)]
+    pub proxy: Option<ProxyStats>,
+    /// Number of notarizations aborted for exceeding a per-session limit
+    #[serde(default)]
+    pub aborted_sessions: AbortedSessionStats,
+}
+
+/// Number of notarizations aborted for exceeding each per-session limit, counted since the server
+/// started
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbortedSessionStats {
+    /// Number of notarizations which exceeded the maximum session duration
+    pub timed_out: u64,
+    /// Number of notarizations which exceeded the CPU time budget
+    pub cpu_time_exceeded: u64,
+    /// Number of notarizations which exceeded the memory budget
+    pub memory_exceeded: u64,
+}
+
+/// Statistics of the WebSocket proxy, counted since the server started
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyStats {
+    /// Number of proxied connections currently open
+    pub open_connections: usize,
+    /// Number of connections accepted by the proxy
+    pub accepted_connections: u64,
+    /// Number of connections rejected because the target is not allowed or the proxy is at
+    /// capacity
+    pub rejected_connections: u64,
+    /// Number of accepted connections which failed to reach the target or ended with an error
+    pub failed_connections: u64,
+    /// Number of bytes forwarded from provers to target servers
+    pub bytes_sent: u64,
+    /// Number of bytes forwarded from target servers to provers
+    pub bytes_received: u64,
+}
+
+/// Response object of the /inspect-proof API
+///
+/// The proof is only parsed, not verified, so the summary must not be trusted. It does not
+/// identify the notary key either: the signature does not commit to a key id, so the key can
+/// only be told by verifying the signature against the keys listed by /info
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectProofResponse {
+    /// Version of the protocol whose proof format was used to parse the proof
+    pub protocol_version: u16,
+    /// Name of the server the prover claims to have connected to
+    pub server_name: String,
+    /// Time of the TLS session, in seconds since the UNIX epoch
+    pub time: u64,
+    /// Whether the session header is signed by a notary
+    pub signed: bool,
+    /// Summary of the data sent by the prover
+    pub sent: TranscriptSummary,
+    /// Summary of the data received by the prover
+    pub received: TranscriptSummary,
+}
+
+/// Summary of one direction of a transcript in a proof
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptSummary {
+    /// Total length of the transcript in bytes
+    pub length: usize,
+    /// Ranges of the transcript which are revealed by the proof
+    pub revealed_ranges: Vec<ByteRange>,
+    /// Number of bytes revealed by the proof
+    pub revealed_bytes: usize,
+    /// Number of bytes redacted from the proof
+    pub redacted_bytes: usize,
+}
+
+/// A range of bytes in a transcript
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ByteRange {
+    /// Start of the range, inclusive
+    pub start: usize,
+    /// End of the range, exclusive
+    pub end: usize,
+}
+
+/// Request query of the /notarize API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotarizationRequestQuery {
+    /// Session id that is returned from /session API
+    pub session_id: String,
+}
+
+/// Types of client that the prover is using
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ClientType {
+    /// Client that has access to the transport layer
+    Tcp,
+    /// Client that cannot directly access transport layer, e.g. browser extension
+    Websocket,
+}
+
+/// Algorithm of the notary signature over the session header
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum SignatureAlgorithm {
+    /// ECDSA over the NIST P-256 curve
+    P256,
+    /// ECDSA over the secp256k1 curve
+    K256,
+}
+
+/// Public key of a notary signing key
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyInfo {
+    /// Algorithm of the signatures made with the key
+    pub algorithm: SignatureAlgorithm,
+    /// Public key in PEM format
+    pub public_key: String,
+}
+
+/// Machine-readable code of an error returned by the notary server
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// The request is malformed or exceeds the limits of the notary server
+    BadRequest,
+    /// The API key is missing or invalid
+    Unauthorized,
+    /// The notary server does not accept new sessions at the moment
+    Unavailable,
+    /// The notarization exceeded the maximum session duration
+    Timeout,
+    /// The notarization exceeded its CPU time or memory budget, or the request to a public route
+    /// its processing time budget
+    ResourceLimit,
+    /// Too many requests were sent to a public route
+    RateLimited,
+    /// The request body exceeds the size limit of a public route
+    PayloadTooLarge,
+    /// A JSON body is nested deeper than the limit
+    JsonTooDeep,
+    /// A JSON body contains a string which is longer than the limit
+    JsonStringTooLong,
+    /// A JSON body contains an array which has more elements than the limit
+    JsonArrayTooLong,
+    /// An unexpected error occurred in the notary server
+    Internal,
+}
+
+impl ErrorCode {
+    /// Returns the URI identifying the type of the problem, which is stable across releases
+    pub fn type_uri(&self) -> &'static str {
+        match self {
+            Self::BadRequest => "urn:tlsn:notary:error:bad_request",
+            Self::Unauthorized => "urn:tlsn:notary:error:unauthorized",
+            Self::Unavailable => "urn:tlsn:notary:error:unavailable",
+            Self::Timeout => "urn:tlsn:notary:error:timeout",
+            Self::ResourceLimit => "urn:tlsn:notary:error:resource_limit",
+            Self::RateLimited => "urn:tlsn:notary:error:rate_limited",
+            Self::PayloadTooLarge => "urn:tlsn:notary:error:payload_too_large",
+            Self::JsonTooDeep => "urn:tlsn:notary:error:json_too_deep",
+            Self::JsonStringTooLong => "urn:tlsn:notary:error:json_string_too_long",
+            Self::JsonArrayTooLong => "urn:tlsn:notary:error:json_array_too_long",
+            Self::Internal => "urn:tlsn:notary:error:internal",
+        }
+    }
+
+    /// Returns a short summary of the type of the problem, which is the same for every occurrence
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::BadRequest => "Invalid request",
+            Self::Unauthorized => "Unauthorized request",
+            Self::Unavailable => "Notary is unavailable",
+            Self::Timeout => "Notarization timed out",
+            Self::ResourceLimit => "Resource limit exceeded",
+            Self::RateLimited => "Too many requests",
+            Self::PayloadTooLarge => "Request body is too large",
+            Self::JsonTooDeep => "JSON is nested too deeply",
+            Self::JsonStringTooLong => "JSON string is too long",
+            Self::JsonArrayTooLong => "JSON array is too long",
+            Self::Internal => "Internal error",
+        }
+    }
+}
+
+/// Content type of the error responses of all APIs
+pub const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Body of the error responses of all APIs, following the problem details format of RFC 7807
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorResponse {
+    /// URI identifying the type of the problem, see [ErrorCode::type_uri]
+    #[serde(rename = "type", default = "default_type_uri")]
+    pub type_uri: String,
+    /// Short summary of the type of the problem, see [ErrorCode::title]
+    #[serde(default)]
+    pub title: String,
+    /// HTTP status code of the response
+    #[serde(default)]
+    pub status: u16,
+    /// Human-readable description of this occurrence of the problem
+    // Notary servers before the problem details format named this field message
+    #[serde(alias = "message")]
+    pub detail: String,
+    /// Machine-readable code of the error
+    pub code: ErrorCode,
+    /// Number of seconds after which the request may succeed when retried, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+    /// The limit which was exceeded by the request, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+impl ErrorResponse {
+    /// Creates the problem details of an error with the given code
+    pub fn new(code: ErrorCode, status: u16, detail: impl Into<String>) -> Self {
+        Self {
+            type_uri: code.type_uri().to_string(),
+            title: code.title().to_string(),
+            status,
+            detail: detail.into(),
+            code,
+            retry_after: None,
+            limit: None,
+        }
+    }
+}
+
+fn default_type_uri() -> String {
+    // The type of problems which have no further semantics than their status, see RFC 7807
+    "about:blank".to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+    use std::fmt::Debug;
+
+    use super::*;
+
+    /// Checks that the value is serialized to the expected JSON and deserialized back
+    fn assert_round_trip<T: Serialize + DeserializeOwned + Debug>(
+        value: T,
+        expected: serde_json::Value,
+    ) {
+        let serialized = serde_json::to_value(&value).unwrap();
+        assert_eq!(serialized, expected);
+
+        let deserialized: T = serde_json::from_value(serialized).unwrap();
+        assert_eq!(format!("{deserialized:?}"), format!("{value:?}"));
+    }
+
+    #[test]
+    fn test_session_round_trip() {
+        assert_round_trip(
+            NotarizationSessionRequest {
+                client_type: ClientType::Websocket,
+                max_sent_data: Some(ByteCount::new(4096)),
+                max_recv_data: None,
+                signature_algorithm: None,
+            },
+            json!({ "clientType": "Websocket", "maxSentData": 4096, "maxRecvData": null }),
+        );
+        assert_round_trip(
+            NotarizationSessionRequest {
+                client_type: ClientType::Tcp,
+                max_sent_data: None,
+                max_recv_data: None,
+                signature_algorithm: Some(SignatureAlgorithm::K256),
+            },
+            json!({
+                "clientType": "Tcp",
+                "maxSentData": null,
+                "maxRecvData": null,
+                "signatureAlgorithm": "k256",
+            }),
+        );
+        assert_round_trip(
+            NotarizationSessionResponse {
+                session_id: "id".to_string(),
+                signature_algorithm: Some(SignatureAlgorithm::P256),
+            },
+            json!({ "sessionId": "id", "signatureAlgorithm": "p256" }),
+        );
+        assert_round_trip(
+            NotarizationRequestQuery {
+                session_id: "id".to_string(),
+            },
+            json!({ "sessionId": "id" }),
+        );
+    }
+
+    #[test]
+    fn test_info_round_trip() {
+        assert_round_trip(
+            InfoResponse {
+                version: "0.1.0".to_string(),
+                public_key: "key".to_string(),
+                public_keys: vec![PublicKeyInfo {
+                    algorithm: SignatureAlgorithm::P256,
+                    public_key: "key".to_string(),
+                }],
+                git_commit_hash: "hash".to_string(),
+                git_commit_timestamp: "timestamp".to_string(),
+            },
+            json!({
+                "version": "0.1.0",
+                "publicKey": "key",
+                "publicKeys": [{ "algorithm": "p256", "publicKey": "key" }],
+                "gitCommitHash": "hash",
+                "gitCommitTimestamp": "timestamp",
+            }),
+        );
+    }
+
+    #[test]
+    fn test_estimate_and_load_round_trip() {
+        assert_round_trip(
+            NotarizationEstimateRequest {
+                max_sent_data: None,
+                max_recv_data: Some(ByteCount::new(16384)),
+            },
+            json!({ "maxSentData": null, "maxRecvData": 16384 }),
+        );
+        assert_round_trip(
+            NotarizationEstimateResponse {
+                max_sent_data: ByteCount::new(1),
+                max_recv_data: ByteCount::new(2),
+                ot_count: 3,
+                estimated_bandwidth: Some(ByteCount::new(4)),
+                expected_wall_time_ms: Some(5),
+                pending_sessions: 6,
+                max_transcript_size: ByteCount::new(7),
+            },
+            json!({
+                "maxSentData": 1,
+                "maxRecvData": 2,
+                "otCount": 3,
+                "estimatedBandwidth": 4,
+                "expectedWallTimeMs": 5,
+                "pendingSessions": 6,
+                "maxTranscriptSize": 7,
+            }),
+        );
+        assert_round_trip(
+            LoadResponse {
+                active_sessions: 1,
+                queued_sessions: 2,
+                backlog_seconds: 3,
+                accepting: true,
+                clock_skew_ms: Some(-4),
+                proxy: Some(ProxyStats {
+                    open_connections: 5,
+                    accepted_connections: 6,
+                    rejected_connections: 7,
+                    failed_connections: 8,
+                    bytes_sent: 9,
+                    bytes_received: 10,
+                }),
+                aborted_sessions: AbortedSessionStats {
+                    timed_out: 11,
+                    cpu_time_exceeded: 12,
+                    memory_exceeded: 13,
+                },
+            },
+            json!({
+                "activeSessions": 1,
+                "queuedSessions": 2,
+                "backlogSeconds": 3,
+                "accepting": true,
+                "clockSkewMs": -4,
+                "proxy": {
+                    "openConnections": 5,
+                    "acceptedConnections": 6,
+                    "rejectedConnections": 7,
+                    "failedConnections": 8,
+                    "bytesSent": 9,
+                    "bytesReceived": 10,
+                },
+                "abortedSessions": {
+                    "timedOut": 11,
+                    "cpuTimeExceeded": 12,
+                    "memoryExceeded": 13,
+                },
+            }),
+        );
+    }
+
+    #[test]
+    fn test_inspect_round_trip() {
+        let summary = TranscriptSummary {
+            length: 10,
+            revealed_ranges: vec![ByteRange { start: 2, end: 5 }],
+            revealed_bytes: 3,
+            redacted_bytes: 7,
+        };
+        assert_round_trip(
+            InspectProofResponse {
+                protocol_version: 1,
+                server_name: "example.com".to_string(),
+                time: 1,
+                signed: true,
+                sent: summary.clone(),
+                received: summary,
+            },
+            json!({
+                "protocolVersion": 1,
+                "serverName": "example.com",
+                "time": 1,
+                "signed": true,
+                "sent": {
+                    "length": 10,
+                    "revealedRanges": [{ "start": 2, "end": 5 }],
+                    "revealedBytes": 3,
+                    "redactedBytes": 7,
+                },
+                "received": {
+                    "length": 10,
+                    "revealedRanges": [{ "start": 2, "end": 5 }],
+                    "revealedBytes": 3,
+                    "redactedBytes": 7,
+                },
+            }),
+        );
+    }
+
+    #[test]
+    fn test_error_round_trip() {
+        assert_round_trip(
+            ErrorResponse::new(ErrorCode::BadRequest, 400, "detail"),
+            json!({
+                "type": "urn:tlsn:notary:error:bad_request",
+                "title": "Invalid request",
+                "status": 400,
+                "detail": "detail",
+                "code": "bad_request",
+            }),
+        );
+        assert_round_trip(
+            ErrorResponse {
+                retry_after: Some(30),
+                limit: Some(60),
+                ..ErrorResponse::new(ErrorCode::RateLimited, 429, "detail")
+            },
+            json!({
+                "type": "urn:tlsn:notary:error:rate_limited",
+                "title": "Too many requests",
+                "status": 429,
+                "detail": "detail",
+                "code": "rate_limited",
+                "retryAfter": 30,
+                "limit": 60,
+            }),
+        );
+
+        // Error responses of notary servers before the problem details format
+        let legacy: ErrorResponse =
+            serde_json::from_value(json!({ "code": "bad_request", "message": "message" })).unwrap();
+        assert_eq!(legacy.type_uri, "about:blank");
+        assert_eq!(legacy.detail, "message");
+    }
+}
@@ -0,0 +1,414 @@
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use hyper::{
+    body::to_bytes,
+    client::conn::{handshake, Parts, SendRequest},
+    header, Body, Method, Request, Response, StatusCode,
+};
+use notary_api_types::{
+    ByteCount, ClientType, ErrorResponse, InfoResponse, InspectProofResponse, JsonLimits,
+    LoadResponse, NotarizationEstimateRequest, NotarizationEstimateResponse,
+    NotarizationSessionRequest, NotarizationSessionResponse, SignatureAlgorithm,
+};
+use rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::TcpStream,
+};
+use tokio_rustls::{client::TlsStream, TlsConnector};
+#[cfg(feature = "tracing")]
+use tracing::{debug, warn};
+
+use crate::ClientError;
+
+/// A client for the notary server HTTP API.
+#[derive(Debug, Clone, derive_builder::Builder)]
+pub struct NotaryClient {
+    /// Host of the notary server.
+    #[builder(setter(into))]
+    host: String,
+    /// Port of the notary server.
+    port: u16,
+    /// Whether to connect to the notary server using TLS.
+    #[builder(default = "true")]
+    tls: bool,
+    /// Name used to verify the certificate of the notary server, defaults to the host.
+    #[builder(setter(into, strip_option), default)]
+    tls_server_name: Option<String>,
+    /// Root certificates used to verify the notary server, defaults to the Mozilla root
+    /// certificates.
+    #[builder(setter(strip_option), default)]
+    root_cert_store: Option<RootCertStore>,
+    /// API key sent in the Authorization header, if the notary server requires one.
+    #[builder(setter(into, strip_option), default)]
+    api_key: Option<String>,
+    /// Maximum number of times a failed request is retried.
+    #[builder(default = "3")]
+    max_retries: usize,
+    /// Delay before the first retry, which is doubled after each retry.
+    #[builder(default = "Duration::from_millis(500)")]
+    retry_backoff: Duration,
+    /// Maximum number of idle connections kept open for reuse by later requests.
+    #[builder(default = "4")]
+    max_idle_connections: usize,
+    /// Limits checked before deserializing the JSON responses of the notary server.
+    #[builder(default)]
+    json_limits: JsonLimits,
+    /// Algorithm of the notary signature requested for the sessions, defaults to the algorithm of
+    /// the default key of the notary server.
+    #[builder(setter(strip_option), default)]
+    signature_algorithm: Option<SignatureAlgorithm>,
+    /// Idle connections to the notary server, shared between clones of the client.
+    #[builder(setter(skip))]
+    idle_connections: Arc<Mutex<Vec<SendRequest<Body>>>>,
+}
+
+impl NotaryClient {
+    /// Creates a new builder for `NotaryClient`.
+    pub fn builder() -> NotaryClientBuilder {
+        NotaryClientBuilder::default()
+    }
+
+    /// Opens up to `connections` connections to the notary server ahead of time, so that later
+    /// requests do not have to wait for the TCP and TLS handshakes.
+    ///
+    /// The number of connections kept open is capped by the maximum number of idle connections.
+    pub async fn warm_up(&self, connections: usize) -> Result<(), ClientError> {
+        for _ in 0..connections.min(self.max_idle_connections) {
+            let request_sender = self.retry(|| self.open_request_sender()).await?;
+            self.release(request_sender);
+        }
+        Ok(())
+    }
+
+    /// Returns general information about the notary server.
+    pub async fn info(&self) -> Result<InfoResponse, ClientError> {
+        self.retry(|| self.send_json(Method::GET, "/info", None::<&()>))
+            .await
+    }
+
+    /// Returns the current load of the notary server.
+    pub async fn load(&self) -> Result<LoadResponse, ClientError> {
+        self.retry(|| self.send_json(Method::GET, "/load", None::<&()>))
+            .await
+    }
+
+    /// Returns the notary server's estimate of the cost of a notarization.
+    pub async fn estimate(
+        &self,
+        request: &NotarizationEstimateRequest,
+    ) -> Result<NotarizationEstimateResponse, ClientError> {
+        self.retry(|| self.send_json(Method::POST, "/estimate", Some(request)))
+            .await
+    }
+
+    /// Returns a summary of a serialized `TlsProof`, which is parsed but not verified by the
+    /// notary server.
+    pub async fn inspect_proof(
+        &self,
+        proof: &impl Serialize,
+    ) -> Result<InspectProofResponse, ClientError> {
+        self.retry(|| self.send_json(Method::POST, "/inspect-proof", Some(proof)))
+            .await
+    }
+
+    /// Configures a notarization session, returning the id of the session.
+    pub async fn create_session(
+        &self,
+        request: &NotarizationSessionRequest,
+    ) -> Result<NotarizationSessionResponse, ClientError> {
+        self.retry(|| self.send_json(Method::POST, "/session", Some(request)))
+            .await
+    }
+
+    /// Upgrades a new connection to the notary server into a TCP connection for the notarization
+    /// of the given session.
+    ///
+    /// This is not retried, as the notary server only accepts one upgrade per session.
+    pub async fn upgrade_tcp(&self, session_id: &str) -> Result<NotaryConnection, ClientError> {
+        let (mut request_sender, connection) = handshake(self.connect().await?).await?;
+        let connection_task = tokio::spawn(connection.without_shutdown());
+
+        let request = self
+            .request_builder(Method::GET, &format!("/notarize?sessionId={session_id}"))
+            .header(header::CONNECTION, "Upgrade")
+            .header(header::UPGRADE, "TCP")
+            .body(Body::empty())?;
+
+        let response = request_sender.send_request(request).await?;
+        if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+            return Err(response_error(response, &self.json_limits).await);
+        }
+
+        // Claim back the socket once the HTTP exchange is done
+        let Parts { io, .. } = connection_task
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))??;
+
+        #[cfg(feature = "tracing")]
+        debug!(session_id, "Upgraded connection to the notary server");
+
+        Ok(io)
+    }
+
+    /// Configures a notarization session for a TCP client and returns the upgraded connection,
+    /// which can be passed to the prover, together with the id of the session.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_sent_data` - Maximum data that can be sent by the prover.
+    /// * `max_recv_data` - Maximum data that can be received by the prover.
+    pub async fn request_notarization(
+        &self,
+        max_sent_data: Option<ByteCount>,
+        max_recv_data: Option<ByteCount>,
+    ) -> Result<(NotaryConnection, String), ClientError> {
+        let NotarizationSessionResponse { session_id, .. } = self
+            .create_session(&NotarizationSessionRequest {
+                client_type: ClientType::Tcp,
+                max_sent_data,
+                max_recv_data,
+                signature_algorithm: self.signature_algorithm,
+            })
+            .await?;
+
+        let connection = self.upgrade_tcp(&session_id).await?;
+
+        Ok((connection, session_id))
+    }
+
+    /// Returns the URL a WebSocket client connects to for the notarization of the given session.
+    pub fn websocket_url(&self, session_id: &str) -> String {
+        let scheme = if self.tls { "wss" } else { "ws" };
+        format!(
+            "{scheme}://{}:{}/notarize?sessionId={session_id}",
+            self.host, self.port
+        )
+    }
+
+    /// Calls `f` until it succeeds, it returns an error which is not retryable or the maximum
+    /// number of retries is reached.
+    async fn retry<T, F, Fut>(&self, mut f: F) -> Result<T, ClientError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        let mut backoff = self.retry_backoff;
+        let mut retries = 0;
+        loop {
+            match f().await {
+                Err(err) if err.is_retryable() && retries < self.max_retries => {
+                    #[cfg(feature = "tracing")]
+                    warn!("Request to notary server failed, retrying in {backoff:?}: {err}");
+
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sends a request with an optional JSON body on an idle or new connection and parses the JSON
+    /// response.
+    async fn send_json<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&impl Serialize>,
+    ) -> Result<T, ClientError> {
+        let mut request = self.request_builder(method, path);
+        let body = match body {
+            Some(body) => {
+                // Need to specify application/json for the notary server to parse it as json
+                request = request.header(header::CONTENT_TYPE, "application/json");
+                Body::from(serde_json::to_vec(body)?)
+            }
+            None => Body::empty(),
+        };
+        let request = request.body(body)?;
+
+        let mut request_sender = self.request_sender().await?;
+        let response = request_sender.send_request(request).await?;
+        if response.status() != StatusCode::OK {
+            return Err(response_error(response, &self.json_limits).await);
+        }
+        let body = to_bytes(response.into_body()).await?;
+
+        // The connection can only be reused once the whole response has been read
+        self.release(request_sender);
+
+        self.json_limits.check(&body)?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Returns an idle connection to the notary server which is still open, or opens a new one.
+    async fn request_sender(&self) -> Result<SendRequest<Body>, ClientError> {
+        loop {
+            let idle = self.idle_connections.lock().unwrap().pop();
+            let Some(mut request_sender) = idle else {
+                break;
+            };
+            // Connections closed by the notary server while idle are dropped
+            if request_sender.ready().await.is_ok() {
+                return Ok(request_sender);
+            }
+        }
+        self.open_request_sender().await
+    }
+
+    /// Opens a new keep-alive connection to the notary server.
+    async fn open_request_sender(&self) -> Result<SendRequest<Body>, ClientError> {
+        let (request_sender, connection) = handshake(self.connect().await?).await?;
+        tokio::spawn(async move {
+            if let Err(_err) = connection.await {
+                #[cfg(feature = "tracing")]
+                debug!("Connection to the notary server failed: {_err}");
+            }
+        });
+        Ok(request_sender)
+    }
+
+    /// Returns a connection to the idle connections, unless there are too many already.
+    fn release(&self, request_sender: SendRequest<Body>) {
+        let mut idle_connections = self.idle_connections.lock().unwrap();
+        if idle_connections.len() < self.max_idle_connections {
+            idle_connections.push(request_sender);
+        }
+    }
+
+    fn request_builder(&self, method: Method, path: &str) -> hyper::http::request::Builder {
+        let scheme = if self.tls { "https" } else { "http" };
+        let mut builder = Request::builder()
+            .uri(format!("{scheme}://{}:{}{path}", self.host, self.port))
+            .method(method)
+            .header(header::HOST, &self.host);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.header(header::AUTHORIZATION, api_key);
+        }
+        builder
+    }
+
+    /// Opens a new connection to the notary server.
+    async fn connect(&self) -> Result<NotaryConnection, ClientError> {
+        let socket = TcpStream::connect((self.host.as_str(), self.port)).await?;
+        if !self.tls {
+            return Ok(NotaryConnection::Tcp(socket));
+        }
+
+        let root_store = self
+            .root_cert_store
+            .clone()
+            .unwrap_or_else(default_root_store);
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+
+        let name = self.tls_server_name.as_deref().unwrap_or(&self.host);
+        let server_name =
+            ServerName::try_from(name).map_err(|_| ClientError::ServerName(name.to_string()))?;
+
+        let socket = TlsConnector::from(Arc::new(config))
+            .connect(server_name, socket)
+            .await?;
+
+        Ok(NotaryConnection::Tls(Box::new(socket)))
+    }
+}
+
+/// Converts an unsuccessful response into an error.
+///
+/// Bodies which exceed the JSON limits are not parsed and are returned as the message instead.
+async fn response_error(response: Response<Body>, json_limits: &JsonLimits) -> ClientError {
+    let status = response.status();
+    match to_bytes(response.into_body()).await {
+        Ok(body) => match json_limits
+            .check(&body)
+            .ok()
+            .and_then(|_| serde_json::from_slice::<ErrorResponse>(&body).ok())
+        {
+            Some(ErrorResponse { code, detail, .. }) => ClientError::Response {
+                status,
+                code: Some(code),
+                message: detail,
+            },
+            None => ClientError::Response {
+                status,
+                code: None,
+                message: String::from_utf8_lossy(&body).into_owned(),
+            },
+        },
+        Err(err) => err.into(),
+    }
+}
+
+fn default_root_store() -> RootCertStore {
+    let mut root_store = RootCertStore::empty();
+    root_store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            ta.subject,
+            ta.spki,
+            ta.name_constraints,
+        )
+    }));
+    root_store
+}
+
+/// A connection to the notary server, with or without TLS.
+#[derive(Debug)]
+pub enum NotaryConnection {
+    /// A plain TCP connection.
+    Tcp(TcpStream),
+    /// A TLS connection.
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl AsyncRead for NotaryConnection {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(socket) => Pin::new(socket).poll_read(cx, buf),
+            Self::Tls(socket) => Pin::new(socket.as_mut()).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for NotaryConnection {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(socket) => Pin::new(socket).poll_write(cx, buf),
+            Self::Tls(socket) => Pin::new(socket.as_mut()).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(socket) => Pin::new(socket).poll_flush(cx),
+            Self::Tls(socket) => Pin::new(socket.as_mut()).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(socket) => Pin::new(socket).poll_shutdown(cx),
+            Self::Tls(socket) => Pin::new(socket.as_mut()).poll_shutdown(cx),
+        }
+    }
+}
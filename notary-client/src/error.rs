@@ -0,0 +1,49 @@
+use hyper::StatusCode;
+use notary_api_types::{ErrorCode, JsonLimitError};
+
+/// An error that can occur while interacting with the notary server.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ClientError {
+    /// An IO error occurred on the connection to the notary server.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// The HTTP exchange with the notary server failed.
+    #[error(transparent)]
+    Http(#[from] hyper::Error),
+    /// The request could not be built.
+    #[error(transparent)]
+    Request(#[from] hyper::http::Error),
+    /// A request or response body could not be (de)serialized.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// A response body exceeds the JSON limits of the client.
+    #[error(transparent)]
+    JsonLimit(#[from] JsonLimitError),
+    /// The name of the notary server is not a valid TLS server name.
+    #[error("invalid notary server name: {0}")]
+    ServerName(String),
+    /// The notary server responded with an unexpected status.
+    #[error("notary server responded with {status}: {message}")]
+    Response {
+        /// The status of the response.
+        status: StatusCode,
+        /// The error code returned by the notary server, if the body was an error response.
+        code: Option<ErrorCode>,
+        /// The error message returned by the notary server, or the body of the response.
+        message: String,
+    },
+}
+
+impl ClientError {
+    /// Returns `true` if the request may succeed when retried.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Io(_) | Self::Http(_) => true,
+            Self::Response { status, .. } => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,15 @@
+//! Client for the notary server HTTP API.
+//!
+//! The client configures notarization sessions and upgrades connections to the notary server,
+//! so that they can be passed to the prover.
+
+#![deny(missing_docs, unreachable_pub, unused_must_use)]
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+mod client;
+mod error;
+
+pub use client::{NotaryClient, NotaryClientBuilder, NotaryClientBuilderError, NotaryConnection};
+pub use error::ClientError;
+pub use notary_api_types as types;
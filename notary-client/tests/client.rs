@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use hyper::StatusCode;
+use notary_client::{
+    types::{ByteCount, ErrorCode, NotarizationEstimateRequest, NotarizationSessionRequest},
+    ClientError, NotaryClient,
+};
+use notary_server::{
+    run_server, AuthorizationProperties, ClientType, ClockProperties, JsonLimitsProperties,
+    LoggingProperties, NotarizationProperties, NotaryServerProperties, NotarySigningKeyProperties,
+    ProxyProperties, PublicApiProperties, ServerProperties, TLSProperties,
+};
+use rustls::{Certificate, RootCertStore};
+
+const NOTARY_CA_CERT_BYTES: &[u8] = include_bytes!("../../notary-server/fixture/tls/rootCA.crt");
+const NOTARY_PORT: u16 = 7060;
+const MAX_TRANSCRIPT_SIZE: usize = 1 << 14;
+
+fn server_config() -> NotaryServerProperties {
+    NotaryServerProperties {
+        server: ServerProperties {
+            name: "tlsnotaryserver.io".to_string(),
+            host: "127.0.0.1".to_string(),
+            port: NOTARY_PORT,
+            html_info: "example html response".to_string(),
+        },
+        notarization: NotarizationProperties {
+            max_transcript_size: MAX_TRANSCRIPT_SIZE,
+            max_concurrent_sessions: None,
+            max_session_duration_secs: None,
+            max_session_cpu_ms: None,
+            max_session_memory_bytes: None,
+            session_ttl_secs: None,
+        },
+        tls: TLSProperties {
+            enabled: true,
+            private_key_pem_path: "../notary-server/fixture/tls/notary.key".to_string(),
+            certificate_pem_path: "../notary-server/fixture/tls/notary.crt".to_string(),
+        },
+        notary_key: NotarySigningKeyProperties {
+            private_key_pem_path: "../notary-server/fixture/notary/notary.key".to_string(),
+            public_key_pem_path: "../notary-server/fixture/notary/notary.pub".to_string(),
+        },
+        additional_notary_keys: vec![],
+        logging: LoggingProperties {
+            level: "DEBUG".to_string(),
+            filter: None,
+            sample_ratio: None,
+            redacted_fields: vec![],
+        },
+        authorization: AuthorizationProperties {
+            enabled: false,
+            whitelist_csv_path: "../notary-server/fixture/auth/whitelist.csv".to_string(),
+        },
+        proxy: ProxyProperties::default(),
+        public_api: PublicApiProperties::default(),
+        clock: ClockProperties::default(),
+        json_limits: JsonLimitsProperties::default(),
+    }
+}
+
+fn client() -> NotaryClient {
+    let mut reader = std::io::BufReader::new(NOTARY_CA_CERT_BYTES);
+    let certificate = rustls_pemfile::certs(&mut reader).unwrap().remove(0);
+    let mut root_store = RootCertStore::empty();
+    root_store.add(&Certificate(certificate)).unwrap();
+
+    NotaryClient::builder()
+        .host("127.0.0.1")
+        .port(NOTARY_PORT)
+        .tls_server_name("tlsnotaryserver.io")
+        .root_cert_store(root_store)
+        .retry_backoff(Duration::from_millis(50))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_client() {
+    tokio::spawn(async move {
+        run_server(&server_config()).await.unwrap();
+    });
+
+    let client = client();
+
+    // The client retries until the server is listening
+    client.warm_up(2).await.unwrap();
+    client.info().await.unwrap();
+    // Idle connections are reused by later requests
+    client.load().await.unwrap();
+
+    let estimate = client
+        .estimate(&NotarizationEstimateRequest {
+            max_sent_data: Some(ByteCount::new(1 << 12)),
+            max_recv_data: Some(ByteCount::new(1 << 12)),
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        estimate.max_transcript_size,
+        ByteCount::new(MAX_TRANSCRIPT_SIZE)
+    );
+
+    // Requests exceeding the limits of the server are rejected without retrying
+    let err = client
+        .create_session(&NotarizationSessionRequest {
+            client_type: ClientType::Tcp,
+            max_sent_data: Some(ByteCount::new(MAX_TRANSCRIPT_SIZE)),
+            max_recv_data: Some(ByteCount::new(MAX_TRANSCRIPT_SIZE)),
+            signature_algorithm: None,
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ClientError::Response {
+            status: StatusCode::BAD_REQUEST,
+            code: Some(ErrorCode::BadRequest),
+            ..
+        }
+    ));
+
+    let (_connection, session_id) = client
+        .request_notarization(Some(ByteCount::new(1 << 12)), Some(ByteCount::new(1 << 12)))
+        .await
+        .unwrap();
+    assert!(!session_id.is_empty());
+
+    // Session ids can only be used once
+    assert!(client.upgrade_tcp(&session_id).await.is_err());
+}
@@ -0,0 +1,256 @@
+use chrono::{DateTime, Utc};
+use eyre::{eyre, Result};
+use hyper::{client::conn, header, Body, Method, Request, Uri};
+use rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicI64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+use tokio_rustls::TlsConnector;
+use tracing::{debug, error, info, warn};
+
+use crate::config::ClockProperties;
+
+/// Latest result of the clock check, shared with the handlers
+#[derive(Debug)]
+pub struct ClockStatus {
+    /// Skew of the local clock in milliseconds, positive if the time sources are ahead
+    skew_ms: AtomicI64,
+    /// Whether the skew has been measured at least once
+    measured: AtomicBool,
+    /// Whether the skew is within the configured threshold
+    in_sync: AtomicBool,
+}
+
+impl Default for ClockStatus {
+    fn default() -> Self {
+        Self {
+            skew_ms: AtomicI64::new(0),
+            measured: AtomicBool::new(false),
+            // Without time sources the clock is trusted as is
+            in_sync: AtomicBool::new(true),
+        }
+    }
+}
+
+impl ClockStatus {
+    /// Returns the last measured skew in milliseconds, if the skew has been measured
+    pub fn skew_ms(&self) -> Option<i64> {
+        self.measured
+            .load(Ordering::Relaxed)
+            .then(|| self.skew_ms.load(Ordering::Relaxed))
+    }
+
+    /// Returns whether the skew was within the threshold when it was last measured
+    pub fn in_sync(&self) -> bool {
+        self.in_sync.load(Ordering::Relaxed)
+    }
+
+    fn record(&self, skew_ms: i64, max_skew: Duration) {
+        let in_sync = skew_ms.unsigned_abs() <= max_skew.as_millis() as u64;
+        self.skew_ms.store(skew_ms, Ordering::Relaxed);
+        self.measured.store(true, Ordering::Relaxed);
+        self.in_sync.store(in_sync, Ordering::Relaxed);
+
+        if in_sync {
+            info!(skew_ms, "Clock is in sync with the time sources");
+        } else {
+            error!(
+                skew_ms,
+                "Clock skew exceeds the threshold, new sessions are refused"
+            );
+        }
+    }
+}
+
+/// Checks the clock once, then keeps checking it periodically in the background if an interval is
+/// configured
+pub async fn start_clock_check(config: &ClockProperties, status: Arc<ClockStatus>) {
+    if config.time_sources.is_empty() {
+        debug!("Skipping clock check as no time source is configured");
+        return;
+    }
+
+    check_clock(config, &status).await;
+
+    if let Some(interval) = config.check_interval_secs {
+        let config = config.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval));
+            // The first tick completes immediately, and the clock has just been checked
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                check_clock(&config, &status).await;
+            }
+        });
+    }
+}
+
+/// Measures the skew against every time source and records the median. A time source which does
+/// not answer within the timeout is unavailable. If no time source can be reached, the previous
+/// result is kept
+async fn check_clock(config: &ClockProperties, status: &ClockStatus) {
+    let timeout = config.timeout();
+    let mut skews = Vec::with_capacity(config.time_sources.len());
+    for source in &config.time_sources {
+        match tokio::time::timeout(timeout, measure_skew(source)).await {
+            Ok(Ok(skew_ms)) => {
+                debug!(?source, skew_ms, "Measured clock skew");
+                skews.push(skew_ms);
+            }
+            Ok(Err(err)) => warn!(?source, "Failed to measure clock skew: {err}"),
+            Err(_) => warn!(?source, ?timeout, "Timed out measuring clock skew"),
+        }
+    }
+
+    match median(&mut skews) {
+        Some(skew_ms) => status.record(skew_ms, Duration::from_secs(config.max_skew_secs)),
+        None => error!("Failed to reach any time source for the clock check"),
+    }
+}
+
+/// Returns the median of the values, or `None` if there are none
+fn median(values: &mut [i64]) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    Some(if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2
+    } else {
+        values[mid]
+    })
+}
+
+/// Measures the skew of the local clock in milliseconds against the Date header returned by an
+/// HTTP(S) time source. The local time is taken halfway through the request to account for latency
+async fn measure_skew(source: &str) -> Result<i64> {
+    let uri: Uri = source.parse()?;
+    let host = uri
+        .host()
+        .ok_or_else(|| eyre!("Time source has no host"))?
+        .to_string();
+    let tls = match uri.scheme_str() {
+        Some("https") => true,
+        Some("http") => false,
+        _ => return Err(eyre!("Time source must be an http or https url")),
+    };
+    let port = uri.port_u16().unwrap_or(if tls { 443 } else { 80 });
+
+    let socket = TcpStream::connect((host.as_str(), port)).await?;
+    let request = Request::builder()
+        .method(Method::HEAD)
+        .uri(uri.path_and_query().map_or("/", |path| path.as_str()))
+        .header(header::HOST, &host)
+        .body(Body::empty())?;
+
+    let sent_at = Utc::now();
+    let started = Instant::now();
+    let response = if tls {
+        let server_name = ServerName::try_from(host.as_str())?;
+        let socket = tls_connector().connect(server_name, socket).await?;
+        send_request(socket, request).await?
+    } else {
+        send_request(socket, request).await?
+    };
+    let local = sent_at + chrono::Duration::from_std(started.elapsed() / 2)?;
+
+    let date = response
+        .headers()
+        .get(header::DATE)
+        .ok_or_else(|| eyre!("Time source did not return a Date header"))?
+        .to_str()?;
+    let remote: DateTime<Utc> = DateTime::parse_from_rfc2822(date)?.into();
+
+    Ok((remote - local).num_milliseconds())
+}
+
+async fn send_request<T: AsyncRead + AsyncWrite + Send + Unpin + 'static>(
+    socket: T,
+    request: Request<Body>,
+) -> Result<hyper::Response<Body>> {
+    let (mut sender, connection) = conn::handshake(socket).await?;
+    tokio::spawn(connection);
+    Ok(sender.send_request(request).await?)
+}
+
+fn tls_connector() -> TlsConnector {
+    let mut root_store = RootCertStore::empty();
+    root_store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            ta.subject,
+            ta.spki,
+            ta.name_constraints,
+        )
+    }));
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_store)
+        .with_no_client_auth();
+
+    TlsConnector::from(Arc::new(config))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&mut []), None);
+        assert_eq!(median(&mut [3, -5, 1]), Some(1));
+        assert_eq!(median(&mut [4, 0, 10, -2]), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_check_clock_timeout() {
+        // A time source which accepts the connection but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (_socket, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let config = ClockProperties {
+            time_sources: vec![format!("http://{addr}/")],
+            max_skew_secs: 1,
+            check_interval_secs: None,
+            timeout_secs: Some(1),
+        };
+        let status = ClockStatus::default();
+
+        let started = Instant::now();
+        check_clock(&config, &status).await;
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        // The unavailable source is not recorded, so the clock is still trusted
+        assert_eq!(status.skew_ms(), None);
+        assert!(status.in_sync());
+
+        server.abort();
+    }
+
+    #[test]
+    fn test_clock_status() {
+        let status = ClockStatus::default();
+        assert!(status.in_sync());
+        assert_eq!(status.skew_ms(), None);
+
+        status.record(-2500, Duration::from_secs(2));
+        assert!(!status.in_sync());
+        assert_eq!(status.skew_ms(), Some(-2500));
+
+        status.record(1500, Duration::from_secs(2));
+        assert!(status.in_sync());
+    }
+}
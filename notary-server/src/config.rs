@@ -1,6 +1,13 @@
+use eyre::{ensure, Result};
+use notary_api_types::JsonLimits;
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
 use serde::Deserialize;
+use std::{collections::HashMap, net::IpAddr, path::Path, str::FromStr, time::Duration};
+use tracing::Level;
 
-#[derive(Clone, Debug, Deserialize, Default)]
+use crate::service::limits::SessionLimits;
+
+#[derive(Clone, Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub struct NotaryServerProperties {
     /// Name and address of the notary server
@@ -11,13 +18,310 @@ pub struct NotaryServerProperties {
     pub tls: TLSProperties,
     /// File path of private key (in PEM format) used to sign the notarization
     pub notary_key: NotarySigningKeyProperties,
+    /// File paths of additional keys (in PEM format) on other curves, which provers can select
+    /// when initializing a session. The algorithm of each key is given by its curve
+    #[serde(default)]
+    pub additional_notary_keys: Vec<NotarySigningKeyProperties>,
     /// Setting for logging
     pub logging: LoggingProperties,
     /// Setting for authorization
     pub authorization: AuthorizationProperties,
+    /// Setting for the WebSocket proxy to target servers
+    #[serde(default)]
+    pub proxy: ProxyProperties,
+    /// Setting for routes which can be called without an API key
+    #[serde(default)]
+    pub public_api: PublicApiProperties,
+    /// Setting for the checks of the server clock against trusted time sources
+    #[serde(default)]
+    pub clock: ClockProperties,
+    /// Setting for the limits on the JSON request bodies
+    #[serde(default)]
+    pub json_limits: JsonLimitsProperties,
+}
+
+/// Routes which can be made public, i.e. every route behind the auth middleware except /session
+pub const PUBLIC_API_ROUTES: [&str; 7] = [
+    "/",
+    "/healthcheck",
+    "/info",
+    "/config/schema",
+    "/load",
+    "/estimate",
+    "/inspect-proof",
+];
+
+impl NotaryServerProperties {
+    /// Checks the values of the config which cannot be enforced by its types, including that the
+    /// referenced files exist
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.notarization.max_transcript_size > 0,
+            "Max transcript size must be greater than 0"
+        );
+        ensure!(
+            self.notarization.max_concurrent_sessions != Some(0),
+            "Max concurrent sessions must be greater than 0"
+        );
+        ensure!(
+            self.notarization.max_session_duration_secs != Some(0),
+            "Max session duration must be greater than 0"
+        );
+        ensure!(
+            self.notarization.max_session_cpu_ms != Some(0),
+            "Max session CPU time must be greater than 0"
+        );
+        ensure!(
+            self.notarization.max_session_cpu_ms.is_none() || cfg!(unix),
+            "Max session CPU time is only supported on unix platforms"
+        );
+        ensure!(
+            self.notarization.max_session_memory_bytes != Some(0),
+            "Max session memory must be greater than 0"
+        );
+        ensure!(
+            self.notarization.max_session_memory_bytes.is_none()
+                || cfg!(feature = "alloc-accounting"),
+            "Max session memory requires the alloc-accounting feature"
+        );
+        ensure!(
+            self.notarization.session_ttl_secs != Some(0),
+            "Session TTL must be greater than 0"
+        );
+
+        if self.logging.filter.is_none() {
+            ensure!(
+                Level::from_str(&self.logging.level).is_ok(),
+                "Invalid log level: {}",
+                self.logging.level
+            );
+        }
+        if let Some(sample_ratio) = self.logging.sample_ratio {
+            ensure!(
+                (0.0..=1.0).contains(&sample_ratio),
+                "Log sample ratio must be between 0 and 1"
+            );
+        }
+
+        if self.proxy.enabled {
+            ensure!(
+                self.proxy.max_connections > 0,
+                "Max proxied connections must be greater than 0"
+            );
+            ensure!(
+                self.proxy.max_bytes_per_second != Some(0),
+                "Max proxied bytes per second must be greater than 0"
+            );
+            for entry in &self.proxy.allowed_hosts {
+                if let Some((host, port)) = entry.rsplit_once(':') {
+                    ensure!(
+                        !host.is_empty() && port.parse::<u16>().is_ok(),
+                        "Invalid allowed proxy target: {entry}"
+                    );
+                }
+            }
+        }
+
+        for route in &self.public_api.routes {
+            ensure!(
+                PUBLIC_API_ROUTES.contains(&route.as_str()),
+                "Route cannot be made public: {route}"
+            );
+        }
+        ensure!(
+            self.public_api.max_requests_per_minute != Some(0),
+            "Max public requests per minute must be greater than 0"
+        );
+        ensure!(
+            self.public_api.max_processing_ms != Some(0),
+            "Max public request processing time must be greater than 0"
+        );
+
+        if !self.clock.time_sources.is_empty() {
+            ensure!(
+                self.clock.max_skew_secs > 0,
+                "Max clock skew must be greater than 0"
+            );
+            ensure!(
+                self.clock.check_interval_secs != Some(0),
+                "Clock check interval must be greater than 0"
+            );
+            ensure!(
+                self.clock.timeout_secs != Some(0),
+                "Clock check timeout must be greater than 0"
+            );
+        }
+        for source in &self.clock.time_sources {
+            ensure!(
+                source.starts_with("https://") || source.starts_with("http://"),
+                "Time source must be an http or https url: {source}"
+            );
+        }
+
+        ensure!(
+            self.json_limits.max_depth != Some(0),
+            "Max JSON depth must be greater than 0"
+        );
+
+        let mut files = vec![
+            &self.notary_key.private_key_pem_path,
+            &self.notary_key.public_key_pem_path,
+        ];
+        for key in &self.additional_notary_keys {
+            files.push(&key.private_key_pem_path);
+            files.push(&key.public_key_pem_path);
+        }
+        if self.tls.enabled {
+            files.push(&self.tls.private_key_pem_path);
+            files.push(&self.tls.certificate_pem_path);
+        }
+        if self.authorization.enabled {
+            files.push(&self.authorization.whitelist_csv_path);
+        }
+        for file in files {
+            ensure!(Path::new(file).is_file(), "File not found: {file}");
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the JSON schema of the server config
+pub fn config_schema() -> RootSchema {
+    schema_for!(NotaryServerProperties)
+}
+
+#[derive(Clone, Debug, Deserialize, Default, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProxyProperties {
+    /// Switch to turn on or off the /proxy endpoint, which lets browser provers connect to target
+    /// servers over WebSocket
+    pub enabled: bool,
+    /// Targets that can be connected to through the proxy, either as `host:port` or as `host`,
+    /// which only allows the default port 443
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    /// Maximum number of proxied connections open at the same time
+    pub max_connections: usize,
+    /// Maximum bandwidth of each direction of a proxied connection in bytes per second. No limit
+    /// is applied if not set
+    #[serde(default)]
+    pub max_bytes_per_second: Option<u64>,
+    /// Addresses that target hosts are pinned to instead of being resolved through DNS
+    #[serde(default)]
+    pub pinned_addresses: HashMap<String, IpAddr>,
+}
+
+/// Port of the target server if a proxy request does not set one, and the only port allowed for
+/// entries of [ProxyProperties::allowed_hosts] without a port
+pub const DEFAULT_PROXY_PORT: u16 = 443;
+
+impl ProxyProperties {
+    /// Returns whether connections to the port of the host can be proxied
+    pub fn is_allowed(&self, host: &str, port: u16) -> bool {
+        self.allowed_hosts
+            .iter()
+            .any(|entry| match entry.rsplit_once(':') {
+                Some((allowed_host, allowed_port)) => {
+                    allowed_host == host && allowed_port.parse() == Ok(port)
+                }
+                None => entry == host && port == DEFAULT_PROXY_PORT,
+            })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub struct PublicApiProperties {
+    /// Routes which skip the auth middleware, e.g. /inspect-proof, see [PUBLIC_API_ROUTES]
+    #[serde(default)]
+    pub routes: Vec<String>,
+    /// Maximum number of requests per minute to the public routes, shared by all callers. No
+    /// limit is applied if not set
+    #[serde(default)]
+    pub max_requests_per_minute: Option<u32>,
+    /// Maximum size in bytes of the request bodies sent to the public routes, which also bounds
+    /// the time spent parsing them. No limit is applied if not set
+    #[serde(default)]
+    pub max_body_bytes: Option<u64>,
+    /// Maximum time in milliseconds spent deserializing a request body sent to a public route,
+    /// after which the request is rejected. No limit is applied if not set
+    #[serde(default)]
+    pub max_processing_ms: Option<u64>,
+}
+
+impl PublicApiProperties {
+    /// Returns whether the route skips the auth middleware
+    pub fn is_public(&self, path: &str) -> bool {
+        self.routes.iter().any(|route| route == path)
+    }
+
+    /// Returns the time allowed to deserialize a request body sent to a public route, if limited
+    pub fn max_processing_time(&self) -> Option<Duration> {
+        self.max_processing_ms.map(Duration::from_millis)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub struct ClockProperties {
+    /// HTTP(S) urls whose Date response header is compared with the server clock, e.g.
+    /// https://www.google.com. The clock is not checked if empty
+    #[serde(default)]
+    pub time_sources: Vec<String>,
+    /// Maximum skew in seconds between the server clock and the median of the time sources, new
+    /// sessions are refused while it is exceeded
+    #[serde(default)]
+    pub max_skew_secs: u64,
+    /// Interval in seconds between clock checks after the one on startup. The clock is only
+    /// checked on startup if not set
+    #[serde(default)]
+    pub check_interval_secs: Option<u64>,
+    /// Time in seconds after which a time source that has not answered is considered
+    /// unavailable. Defaults to 5 if not set
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
 }
 
-#[derive(Clone, Debug, Deserialize, Default)]
+/// Default of [ClockProperties::timeout_secs]
+pub const DEFAULT_CLOCK_TIMEOUT_SECS: u64 = 5;
+
+impl ClockProperties {
+    /// Returns the time that a time source has to answer
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.unwrap_or(DEFAULT_CLOCK_TIMEOUT_SECS))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub struct JsonLimitsProperties {
+    /// Maximum nesting depth of arrays and objects in JSON request bodies. Defaults to 32 if not
+    /// set
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// Maximum length in bytes of a string in JSON request bodies. Defaults to 1048576 if not set
+    #[serde(default)]
+    pub max_string_length: Option<usize>,
+    /// Maximum number of elements of an array in JSON request bodies, which must fit the revealed
+    /// bytes of the proofs sent to /inspect-proof. Defaults to 1048576 if not set
+    #[serde(default)]
+    pub max_array_length: Option<usize>,
+}
+
+impl JsonLimitsProperties {
+    /// Returns the limits, using the defaults for those which are not set
+    pub fn limits(&self) -> JsonLimits {
+        let default = JsonLimits::default();
+        JsonLimits {
+            max_depth: self.max_depth.unwrap_or(default.max_depth),
+            max_string_length: self.max_string_length.unwrap_or(default.max_string_length),
+            max_array_length: self.max_array_length.unwrap_or(default.max_array_length),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub struct AuthorizationProperties {
     /// Switch to turn on or off auth middleware
@@ -26,14 +330,55 @@ pub struct AuthorizationProperties {
     pub whitelist_csv_path: String,
 }
 
-#[derive(Clone, Debug, Deserialize, Default)]
+#[derive(Clone, Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub struct NotarizationProperties {
     /// Global limit for maximum transcript size in bytes
     pub max_transcript_size: usize,
+    /// Maximum number of sessions that can be active or waiting to be started at the same time,
+    /// new sessions are rejected once it is reached. No limit is applied if not set
+    #[serde(default)]
+    pub max_concurrent_sessions: Option<usize>,
+    /// Maximum duration of a notarization in seconds, sessions exceeding it are aborted. No limit
+    /// is applied if not set
+    #[serde(default)]
+    pub max_session_duration_secs: Option<u64>,
+    /// Maximum CPU time of a notarization in milliseconds, sessions exceeding it are aborted. Only
+    /// supported on unix platforms. No limit is applied if not set
+    #[serde(default)]
+    pub max_session_cpu_ms: Option<u64>,
+    /// Maximum number of bytes of memory held by a notarization, sessions exceeding it are
+    /// aborted. Requires the server to be built with the alloc-accounting feature. No limit is
+    /// applied if not set
+    #[serde(default)]
+    pub max_session_memory_bytes: Option<u64>,
+    /// Time in seconds that a session waits for the prover to connect after it is initialized,
+    /// after which it expires and no longer counts towards the concurrent sessions. Defaults to 60
+    /// if not set
+    #[serde(default)]
+    pub session_ttl_secs: Option<u64>,
 }
 
-#[derive(Clone, Debug, Deserialize, Default)]
+/// Default of [NotarizationProperties::session_ttl_secs]
+pub const DEFAULT_SESSION_TTL_SECS: u64 = 60;
+
+impl NotarizationProperties {
+    /// Returns the time that a session waits for the prover to connect
+    pub fn session_ttl(&self) -> Duration {
+        Duration::from_secs(self.session_ttl_secs.unwrap_or(DEFAULT_SESSION_TTL_SECS))
+    }
+
+    /// Returns the budgets of each notarization
+    pub fn session_limits(&self) -> SessionLimits {
+        SessionLimits {
+            max_duration: self.max_session_duration_secs.map(Duration::from_secs),
+            max_cpu_time: self.max_session_cpu_ms.map(Duration::from_millis),
+            max_memory: self.max_session_memory_bytes,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub struct ServerProperties {
     /// Used for testing purpose
@@ -45,7 +390,7 @@ pub struct ServerProperties {
     pub html_info: String,
 }
 
-#[derive(Clone, Debug, Deserialize, Default)]
+#[derive(Clone, Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub struct TLSProperties {
     /// Flag to turn on/off TLS between prover and notary (should always be turned on unless TLS is handled by external setup e.g. reverse proxy, cloud)
@@ -54,14 +399,14 @@ pub struct TLSProperties {
     pub certificate_pem_path: String,
 }
 
-#[derive(Clone, Debug, Deserialize, Default)]
+#[derive(Clone, Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub struct NotarySigningKeyProperties {
     pub private_key_pem_path: String,
     pub public_key_pem_path: String,
 }
 
-#[derive(Clone, Debug, Deserialize, Default)]
+#[derive(Clone, Debug, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub struct LoggingProperties {
     /// Log verbosity level of the default filtering logic, which is notary_server=<level>,tlsn_verifier=<level>,tls_mpc=<level>
@@ -70,4 +415,87 @@ pub struct LoggingProperties {
     /// Custom filtering logic, refer to the syntax here https://docs.rs/tracing-subscriber/latest/tracing_subscriber/filter/struct.EnvFilter.html#example-syntax
     /// This will override the default filtering logic above
     pub filter: Option<String>,
+    /// Ratio of events below the warning level that are logged, between 0 and 1. All events are
+    /// logged if not set
+    #[serde(default)]
+    pub sample_ratio: Option<f64>,
+    /// Names of fields and headers whose values are redacted from the logs and from the details
+    /// of error responses, e.g. authorization. Matched case-insensitively
+    #[serde(default)]
+    pub redacted_fields: Vec<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::parse_config_file;
+
+    #[test]
+    fn test_validate() {
+        let mut config: NotaryServerProperties = parse_config_file("./config/config.yaml").unwrap();
+        config.validate().unwrap();
+
+        config.logging.sample_ratio = Some(2.0);
+        assert!(config.validate().is_err());
+
+        config.logging.sample_ratio = None;
+        config.public_api.routes = vec!["/session".to_string()];
+        assert!(config.validate().is_err());
+
+        config.public_api.routes = vec!["/inspect-proof".to_string()];
+        config.validate().unwrap();
+
+        config.public_api.max_processing_ms = Some(0);
+        assert!(config.validate().is_err());
+
+        config.public_api.max_processing_ms = Some(1000);
+        config.validate().unwrap();
+
+        config.clock.time_sources = vec!["https://www.google.com".to_string()];
+        assert!(config.validate().is_err());
+
+        config.clock.max_skew_secs = 5;
+        config.validate().unwrap();
+
+        config.proxy.enabled = true;
+        config.proxy.allowed_hosts = vec!["example.com:https".to_string()];
+        assert!(config.validate().is_err());
+
+        config.proxy.allowed_hosts = vec!["example.com:8443".to_string()];
+        config.validate().unwrap();
+
+        config.notary_key.private_key_pem_path = "./fixture/notary/missing.key".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_proxy_is_allowed() {
+        let proxy = ProxyProperties {
+            allowed_hosts: vec![
+                "example.com".to_string(),
+                "api.example.com:8443".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        assert!(proxy.is_allowed("example.com", 443));
+        assert!(!proxy.is_allowed("example.com", 22));
+        assert!(proxy.is_allowed("api.example.com", 8443));
+        assert!(!proxy.is_allowed("api.example.com", 443));
+        assert!(!proxy.is_allowed("other.example.com", 443));
+    }
+
+    #[test]
+    fn test_config_schema() {
+        let schema = serde_json::to_value(config_schema()).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+
+        // Property names follow the kebab-case of the config file
+        assert!(properties.contains_key("notary-key"));
+        assert!(properties.contains_key("proxy"));
+        // Fields with defaults are optional
+        let required = schema["required"].as_array().unwrap();
+        assert!(required.contains(&"notary-key".into()));
+        assert!(!required.contains(&"proxy".into()));
+    }
 }
@@ -7,4 +7,17 @@ pub struct CliFields {
     /// Configuration file location
     #[structopt(long, default_value = "./config/config.yaml")]
     pub config_file: String,
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Commands that can be run instead of starting the server.
+#[derive(Clone, Debug, StructOpt)]
+pub enum Command {
+    /// Checks that the configuration file is valid and exits
+    ValidateConfig,
+    /// Prints the JSON schema of the configuration file and exits
+    ConfigSchema,
+    /// Prints an example OpenTelemetry collector configuration for the protocol spans and exits
+    OtelCollectorConfig,
 }
@@ -1,79 +1,378 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-use chrono::{DateTime, Utc};
-use p256::ecdsa::SigningKey;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use std::sync::Mutex;
-use tokio::sync::Mutex as AsyncMutex;
 
-use crate::{config::NotarizationProperties, domain::auth::AuthorizationWhitelistRecord};
+use crate::{
+    clock::ClockStatus,
+    config::{NotarizationProperties, ProxyProperties, PublicApiProperties},
+    domain::{
+        auth::AuthorizationWhitelistRecord,
+        session::{SessionState, SessionStore},
+    },
+    error::NotaryServerError,
+    service::limits::ExceededLimit,
+    signer::NotarySigners,
+};
 
-/// Response object of the /session API
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub use notary_api_types::{
+    AbortedSessionStats, ByteCount, ByteRange, ClientType, ErrorCode, ErrorResponse,
+    InspectProofResponse, JsonLimits, LoadResponse, NotarizationEstimateRequest,
+    NotarizationEstimateResponse, NotarizationRequestQuery, NotarizationSessionRequest,
+    NotarizationSessionResponse, ProxyStats, SignatureAlgorithm, TranscriptSummary,
+};
+
+/// Request query of the /proxy API
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct NotarizationSessionResponse {
-    /// Unique session id that is generated by notary and shared to prover
+pub struct ProxyRequestQuery {
+    /// Id of a session returned by /session, which authorizes the connection
     pub session_id: String,
+    /// Host of the target server
+    pub host: String,
+    /// Port of the target server, defaults to 443
+    pub port: Option<u16>,
 }
 
-/// Request object of the /session API
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct NotarizationSessionRequest {
-    pub client_type: ClientType,
-    /// Maximum data that can be sent by the prover
-    pub max_sent_data: Option<usize>,
-    /// Maximum data that can be received by the prover
-    pub max_recv_data: Option<usize>,
+/// Counters of the WebSocket proxy, see [ProxyStats]
+#[derive(Debug, Default)]
+pub struct ProxyMetrics {
+    /// Number of proxied connections currently open, which is also used to enforce the limit
+    pub open_connections: AtomicUsize,
+    pub accepted_connections: AtomicU64,
+    pub rejected_connections: AtomicU64,
+    pub failed_connections: AtomicU64,
+    pub bytes_sent: AtomicU64,
+    pub bytes_received: AtomicU64,
 }
 
-/// Request query of the /notarize API
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct NotarizationRequestQuery {
-    /// Session id that is returned from /session API
-    pub session_id: String,
+impl ProxyMetrics {
+    /// Returns a snapshot of the counters
+    pub fn stats(&self) -> ProxyStats {
+        ProxyStats {
+            open_connections: self.open_connections.load(Ordering::Relaxed),
+            accepted_connections: self.accepted_connections.load(Ordering::Relaxed),
+            rejected_connections: self.rejected_connections.load(Ordering::Relaxed),
+            failed_connections: self.failed_connections.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+        }
+    }
 }
 
-/// Types of client that the prover is using
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub enum ClientType {
-    /// Client that has access to the transport layer
-    Tcp,
-    /// Client that cannot directly access transport layer, e.g. browser extension
-    Websocket,
+/// Counters of the notarizations aborted for exceeding their limits, see [AbortedSessionStats]
+#[derive(Debug, Default)]
+pub struct SessionLimitMetrics {
+    pub timed_out: AtomicU64,
+    pub cpu_time_exceeded: AtomicU64,
+    pub memory_exceeded: AtomicU64,
 }
 
-/// Session configuration data to be stored in temporary storage
-#[derive(Clone, Debug)]
-pub struct SessionData {
-    pub max_sent_data: Option<usize>,
-    pub max_recv_data: Option<usize>,
-    pub created_at: DateTime<Utc>,
+impl SessionLimitMetrics {
+    /// Counts the error of a notarization if it was aborted by one of its limits
+    pub fn record(&self, error: &NotaryServerError) {
+        let counter = match error {
+            NotaryServerError::Timeout(_) => &self.timed_out,
+            NotaryServerError::ResourceLimit(ExceededLimit::CpuTime(_)) => &self.cpu_time_exceeded,
+            NotaryServerError::ResourceLimit(ExceededLimit::Memory(_)) => &self.memory_exceeded,
+            _ => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns a snapshot of the counters
+    pub fn stats(&self) -> AbortedSessionStats {
+        AbortedSessionStats {
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+            cpu_time_exceeded: self.cpu_time_exceeded.load(Ordering::Relaxed),
+            memory_exceeded: self.memory_exceeded.load(Ordering::Relaxed),
+        }
+    }
 }
 
 /// Global data that needs to be shared with the axum handlers
 #[derive(Clone, Debug)]
 pub struct NotaryGlobals {
-    pub notary_signing_key: SigningKey,
+    /// Signing keys of the notary, one per supported signature algorithm
+    pub notary_signers: NotarySigners,
     pub notarization_config: NotarizationProperties,
-    /// A temporary storage to store configuration data, mainly used for WebSocket client
-    pub store: Arc<AsyncMutex<HashMap<String, SessionData>>>,
+    /// A temporary storage of the sessions which have not ended yet, with their configuration data
+    pub store: SessionStore,
     /// Whitelist of API keys for authorization purpose
     pub authorization_whitelist: Option<Arc<Mutex<HashMap<String, AuthorizationWhitelistRecord>>>>,
+    /// Number of notarizations currently running
+    pub active_sessions: Arc<AtomicUsize>,
+    /// Moving average of the duration of past notarizations in milliseconds
+    pub average_session_ms: Arc<AtomicU64>,
+    /// Counters of the notarizations aborted by their limits
+    pub session_limit_metrics: Arc<SessionLimitMetrics>,
+    /// Bandwidth observed in past notarizations
+    pub session_calibration: Arc<SessionCalibration>,
+    pub proxy_config: ProxyProperties,
+    /// Counters of the proxied connections
+    pub proxy_metrics: Arc<ProxyMetrics>,
+    pub public_api_config: PublicApiProperties,
+    /// Rate limiter shared by all requests to public routes, if a limit is set
+    pub public_api_limiter: Option<Arc<Mutex<RateLimiter>>>,
+    /// Latest result of the check of the server clock against the time sources
+    pub clock_status: Arc<ClockStatus>,
+    /// Limits checked before deserializing JSON request bodies
+    pub json_limits: JsonLimits,
 }
 
 impl NotaryGlobals {
     pub fn new(
-        notary_signing_key: SigningKey,
+        notary_signers: NotarySigners,
         notarization_config: NotarizationProperties,
+        proxy_config: ProxyProperties,
+        public_api_config: PublicApiProperties,
+        json_limits: JsonLimits,
         authorization_whitelist: Option<Arc<Mutex<HashMap<String, AuthorizationWhitelistRecord>>>>,
     ) -> Self {
         Self {
-            notary_signing_key,
+            notary_signers,
+            store: SessionStore::new(notarization_config.session_ttl()),
             notarization_config,
-            store: Default::default(),
             authorization_whitelist,
+            active_sessions: Default::default(),
+            average_session_ms: Default::default(),
+            session_limit_metrics: Default::default(),
+            session_calibration: Default::default(),
+            proxy_config,
+            proxy_metrics: Default::default(),
+            public_api_limiter: public_api_config
+                .max_requests_per_minute
+                .map(|max| Arc::new(Mutex::new(RateLimiter::per_minute(max)))),
+            public_api_config,
+            clock_status: Default::default(),
+            json_limits,
+        }
+    }
+
+    /// Marks a notarization as running until the returned guard is dropped
+    pub fn track_session(&self) -> ActiveSessionGuard {
+        self.active_sessions.fetch_add(1, Ordering::Relaxed);
+        ActiveSessionGuard {
+            active_sessions: self.active_sessions.clone(),
+            average_session_ms: self.average_session_ms.clone(),
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Returns the number of sessions which have been initialized but not yet upgraded to a
+    /// notarization. Upgraded sessions are only counted as active once the connection is
+    /// established
+    pub fn queued_sessions(&self) -> usize {
+        self.store.count(SessionState::Created) + self.store.count(SessionState::Upgraded)
+    }
+
+    /// Returns the expected milliseconds until a session started now completes, assuming that it
+    /// waits for the active and queued sessions and that each takes the average duration of past
+    /// notarizations, the same model as [LoadResponse::backlog_seconds]
+    pub fn expected_wall_time_ms(&self) -> Option<u64> {
+        let average_session_ms = self.average_session_ms.load(Ordering::Relaxed);
+        if average_session_ms == 0 {
+            return None;
+        }
+        let sessions_ahead = self.active_sessions.load(Ordering::Relaxed) + self.queued_sessions();
+
+        Some((sessions_ahead as u64 + 1).saturating_mul(average_session_ms))
+    }
+
+    /// Returns the current load of the notary, considering both running notarizations and
+    /// sessions waiting in the store
+    pub async fn load(&self) -> LoadResponse {
+        let active_sessions = self.active_sessions.load(Ordering::Relaxed);
+        let queued_sessions = self.queued_sessions();
+        let average_session_ms = self.average_session_ms.load(Ordering::Relaxed);
+        let accepting = self
+            .notarization_config
+            .max_concurrent_sessions
+            .map_or(true, |max| active_sessions + queued_sessions < max)
+            && self.clock_status.in_sync();
+
+        LoadResponse {
+            active_sessions,
+            queued_sessions,
+            backlog_seconds: (active_sessions + queued_sessions) as u64 * average_session_ms / 1000,
+            accepting,
+            clock_skew_ms: self.clock_status.skew_ms(),
+            proxy: self
+                .proxy_config
+                .enabled
+                .then(|| self.proxy_metrics.stats()),
+            aborted_sessions: self.session_limit_metrics.stats(),
+        }
+    }
+}
+
+/// Guard which keeps a notarization counted as active, see [NotaryGlobals::track_session]
+#[derive(Debug)]
+pub struct ActiveSessionGuard {
+    active_sessions: Arc<AtomicUsize>,
+    average_session_ms: Arc<AtomicU64>,
+    started_at: Instant,
+}
+
+impl Drop for ActiveSessionGuard {
+    fn drop(&mut self) {
+        self.active_sessions.fetch_sub(1, Ordering::Relaxed);
+
+        update_moving_average(
+            &self.average_session_ms,
+            self.started_at.elapsed().as_millis() as u64,
+        );
+    }
+}
+
+/// Adds a sample to an exponential moving average with a weight of 1/8 for the latest sample,
+/// where 0 means that no sample has been added yet
+fn update_moving_average(average: &AtomicU64, sample: u64) {
+    let _ = average.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |average| {
+        Some(if average == 0 {
+            sample
+        } else {
+            average.saturating_mul(7).saturating_add(sample) / 8
+        })
+    });
+}
+
+/// Observations of past notarizations which calibrate the /estimate API
+#[derive(Debug, Default)]
+pub struct SessionCalibration {
+    /// Moving average of the bytes exchanged with the prover per byte of the transcript limits of
+    /// successful notarizations
+    bytes_per_transcript_byte: AtomicU64,
+}
+
+impl SessionCalibration {
+    /// Records the bytes exchanged in a successful notarization with the given transcript limits
+    pub fn record(&self, bytes_exchanged: u64, transcript_size: ByteCount) {
+        if transcript_size.get() == 0 {
+            return;
+        }
+        // The cost of the MPC grows linearly with the transcript limits, and every byte of them
+        // costs thousands of bytes of OT and garbled circuits, so the integer division is precise
+        // enough
+        update_moving_average(
+            &self.bytes_per_transcript_byte,
+            (bytes_exchanged / transcript_size.get() as u64).max(1),
+        );
+    }
+
+    /// Returns the bytes expected to be exchanged in a notarization with the given transcript
+    /// limits, if a notarization has been recorded
+    pub fn estimated_bandwidth(&self, transcript_size: ByteCount) -> Option<ByteCount> {
+        match self.bytes_per_transcript_byte.load(Ordering::Relaxed) {
+            0 => None,
+            per_byte => Some(ByteCount::new(
+                (per_byte as usize).saturating_mul(transcript_size.get()),
+            )),
+        }
+    }
+}
+
+/// Token bucket which allows bursts of up to the limit, refilled evenly over each minute
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn per_minute(max_requests: u32) -> Self {
+        Self {
+            capacity: max_requests as f64,
+            tokens: max_requests as f64,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Returns whether a request is allowed now, consuming a token if it is
+    pub fn try_acquire(&mut self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    /// Returns how long it takes until the next request is allowed
+    pub fn retry_after(&self) -> Duration {
+        let missing = (1.0 - self.tokens).max(0.0);
+        Duration::from_secs_f64(missing / self.capacity * 60.0)
+    }
+
+    fn try_acquire_at(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = (self.tokens
+            + elapsed.as_secs_f64() / Duration::from_secs(60).as_secs_f64() * self.capacity)
+            .min(self.capacity);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_session_calibration() {
+        let calibration = SessionCalibration::default();
+        let bytes = ByteCount::new;
+        assert_eq!(calibration.estimated_bandwidth(bytes(1024)), None);
+
+        calibration.record(8 << 20, bytes(1024));
+        assert_eq!(
+            calibration.estimated_bandwidth(bytes(2048)),
+            Some(bytes(16 << 20))
+        );
+
+        // Later sessions move the average by 1/8 of their difference
+        calibration.record(16 << 20, bytes(1024));
+        assert_eq!(
+            calibration.estimated_bandwidth(bytes(1)),
+            Some(bytes(9 * 1024))
+        );
+
+        // Sessions without a transcript are not recorded
+        calibration.record(1 << 20, bytes(0));
+        assert_eq!(
+            calibration.estimated_bandwidth(bytes(1)),
+            Some(bytes(9 * 1024))
+        );
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut limiter = RateLimiter::per_minute(2);
+        let start = limiter.refilled_at;
+
+        assert!(limiter.try_acquire_at(start));
+        assert!(limiter.try_acquire_at(start));
+        assert!(!limiter.try_acquire_at(start));
+        assert_eq!(limiter.retry_after(), Duration::from_secs(30));
+
+        // One token is refilled every 30 seconds
+        assert!(!limiter.try_acquire_at(start + Duration::from_secs(20)));
+        assert!(limiter.try_acquire_at(start + Duration::from_secs(30)));
+        assert!(!limiter.try_acquire_at(start + Duration::from_secs(30)));
+
+        // Tokens are capped by the limit
+        let later = start + Duration::from_secs(600);
+        assert!(limiter.try_acquire_at(later));
+        assert!(limiter.try_acquire_at(later));
+        assert!(!limiter.try_acquire_at(later));
+    }
+}
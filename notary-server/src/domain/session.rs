@@ -0,0 +1,386 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use notary_api_types::{ByteCount, SignatureAlgorithm};
+use tracing::{info, warn};
+
+/// Session configuration data to be stored in temporary storage
+#[derive(Clone, Debug)]
+pub struct SessionData {
+    pub max_sent_data: Option<ByteCount>,
+    pub max_recv_data: Option<ByteCount>,
+    /// Algorithm of the signature over the session header, selected when the session is created
+    pub signature_algorithm: SignatureAlgorithm,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Stage of a notarization session in its lifecycle
+///
+/// A session goes through `Created -> Upgraded -> Notarizing -> Signed`, and can fail at any stage.
+/// A session expires if the prover does not connect within the TTL of the store. `Signed`, `Failed`
+/// and `Expired` are terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SessionState {
+    /// The session has been initialized via /session and waits for the prover to connect
+    Created,
+    /// The prover's connection has been accepted for upgrade via /notarize
+    Upgraded,
+    /// The upgraded connection is established and the notarization is running
+    Notarizing,
+    /// The notarization completed and the session header was signed
+    Signed,
+    /// The session ended without a signature
+    Failed,
+    /// The prover did not connect before the session expired
+    Expired,
+}
+
+impl SessionState {
+    /// Returns whether the session can move from this state to `next`
+    pub fn can_transition_to(self, next: SessionState) -> bool {
+        use SessionState::*;
+
+        matches!(
+            (self, next),
+            (Created, Upgraded)
+                | (Upgraded, Notarizing)
+                | (Notarizing, Signed)
+                | (Created | Upgraded | Notarizing, Failed)
+                | (Created, Expired)
+        )
+    }
+
+    /// Returns whether the session has ended
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            SessionState::Signed | SessionState::Failed | SessionState::Expired
+        )
+    }
+}
+
+impl fmt::Display for SessionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Errors that can occur when changing the state of a session
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum SessionStateError {
+    #[error("Session id {0} does not exist")]
+    NotFound(String),
+    #[error("Session id {id} cannot move from {from} to {to}")]
+    IllegalTransition {
+        id: String,
+        from: SessionState,
+        to: SessionState,
+    },
+    #[error("Maximum number of concurrent sessions reached: {0}")]
+    CapacityReached(usize),
+}
+
+#[derive(Debug)]
+struct Session {
+    data: SessionData,
+    state: SessionState,
+}
+
+/// Temporary storage of the sessions which have not ended yet
+///
+/// All state changes go through [SessionStore::transition], which rejects illegal transitions and
+/// logs a lifecycle event for every change. Sessions are removed once they reach a terminal state.
+/// Sessions still in the `Created` state after the TTL are expired whenever the store is accessed,
+/// so that sessions which are never upgraded do not hold capacity.
+#[derive(Clone, Debug)]
+pub struct SessionStore {
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+    ttl: Duration,
+}
+
+impl SessionStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            sessions: Default::default(),
+            ttl,
+        }
+    }
+
+    /// Stores a new session in the `Created` state, unless `max_sessions` sessions have not ended
+    /// yet. The check and the insertion happen under the same lock, so the limit is never exceeded
+    pub fn create(
+        &self,
+        id: String,
+        data: SessionData,
+        max_sessions: Option<ByteCount>,
+    ) -> Result<(), SessionStateError> {
+        let mut sessions = self.lock();
+        if let Some(max) = max_sessions.filter(|max| sessions.len() >= *max) {
+            return Err(SessionStateError::CapacityReached(max));
+        }
+
+        info!(session_id = ?id, state = %SessionState::Created, "Session state changed");
+        sessions.insert(
+            id,
+            Session {
+                data,
+                state: SessionState::Created,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Moves a session to the given state, returning its configuration data
+    pub fn transition(&self, id: &str, to: SessionState) -> Result<SessionData, SessionStateError> {
+        let mut sessions = self.lock();
+        let session = sessions
+            .get_mut(id)
+            .ok_or_else(|| SessionStateError::NotFound(id.to_string()))?;
+
+        let from = session.state;
+        if !from.can_transition_to(to) {
+            return Err(SessionStateError::IllegalTransition {
+                id: id.to_string(),
+                from,
+                to,
+            });
+        }
+
+        info!(session_id = ?id, %from, state = %to, "Session state changed");
+        session.state = to;
+        let data = session.data.clone();
+        if to.is_terminal() {
+            sessions.remove(id);
+        }
+
+        Ok(data)
+    }
+
+    /// Returns whether the session exists and has not ended yet
+    pub fn contains(&self, id: &str) -> bool {
+        self.lock().contains_key(id)
+    }
+
+    /// Returns the number of stored sessions in the given state
+    pub fn count(&self, state: SessionState) -> usize {
+        self.lock()
+            .values()
+            .filter(|session| session.state == state)
+            .count()
+    }
+
+    /// Locks the sessions, expiring those which have waited for the prover longer than the TTL
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Session>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let now = Utc::now();
+        sessions.retain(|id, session| {
+            let expired = session.state == SessionState::Created
+                && (now - session.data.created_at)
+                    .to_std()
+                    .map_or(false, |age| age > self.ttl);
+            if expired {
+                info!(
+                    session_id = ?id,
+                    from = %SessionState::Created,
+                    state = %SessionState::Expired,
+                    "Session state changed"
+                );
+            }
+            !expired
+        });
+
+        sessions
+    }
+}
+
+/// Handle to a session which has been upgraded, which moves the session to `Failed` if it is
+/// dropped before reaching a terminal state, e.g. when the connection upgrade fails
+#[derive(Debug)]
+pub struct SessionHandle {
+    store: SessionStore,
+    id: String,
+    state: SessionState,
+}
+
+impl SessionHandle {
+    /// Moves the session in `store` to `Upgraded` and returns a handle to it
+    pub fn upgrade(
+        store: &SessionStore,
+        id: String,
+    ) -> Result<(Self, SessionData), SessionStateError> {
+        let data = store.transition(&id, SessionState::Upgraded)?;
+        let handle = Self {
+            store: store.clone(),
+            id,
+            state: SessionState::Upgraded,
+        };
+
+        Ok((handle, data))
+    }
+
+    /// Returns the id of the session
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Moves the session to the given state
+    pub fn advance(&mut self, to: SessionState) -> Result<(), SessionStateError> {
+        self.store.transition(&self.id, to)?;
+        self.state = to;
+        Ok(())
+    }
+}
+
+impl Drop for SessionHandle {
+    fn drop(&mut self) {
+        if !self.state.is_terminal() {
+            warn!(session_id = ?self.id, state = %self.state, "Session ended without completing");
+            let _ = self.store.transition(&self.id, SessionState::Failed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn session_data() -> SessionData {
+        SessionData {
+            max_sent_data: None,
+            max_recv_data: None,
+            signature_algorithm: SignatureAlgorithm::P256,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Returns the data of a session created longer ago than the TTL of [store]
+    fn stale_session_data() -> SessionData {
+        SessionData {
+            created_at: Utc::now() - chrono::Duration::seconds(61),
+            ..session_data()
+        }
+    }
+
+    fn store() -> SessionStore {
+        SessionStore::new(Duration::from_secs(60))
+    }
+
+    #[test]
+    fn test_session_lifecycle() {
+        let store = store();
+        store
+            .create("id".to_string(), session_data(), None)
+            .unwrap();
+        assert_eq!(store.count(SessionState::Created), 1);
+
+        let (mut handle, _) = SessionHandle::upgrade(&store, "id".to_string()).unwrap();
+        assert_eq!(store.count(SessionState::Upgraded), 1);
+
+        // A session id can only be used once
+        assert_eq!(
+            SessionHandle::upgrade(&store, "id".to_string()).unwrap_err(),
+            SessionStateError::IllegalTransition {
+                id: "id".to_string(),
+                from: SessionState::Upgraded,
+                to: SessionState::Upgraded,
+            }
+        );
+
+        handle.advance(SessionState::Notarizing).unwrap();
+        handle.advance(SessionState::Signed).unwrap();
+        // Ended sessions are removed from the store
+        assert_eq!(store.count(SessionState::Signed), 0);
+        assert!(matches!(
+            SessionHandle::upgrade(&store, "id".to_string()),
+            Err(SessionStateError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_dropped_session_fails() {
+        let store = store();
+        store
+            .create("id".to_string(), session_data(), None)
+            .unwrap();
+
+        let (handle, _) = SessionHandle::upgrade(&store, "id".to_string()).unwrap();
+        drop(handle);
+
+        assert_eq!(store.count(SessionState::Upgraded), 0);
+        assert!(matches!(
+            store.transition("id", SessionState::Notarizing),
+            Err(SessionStateError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_illegal_transitions() {
+        use SessionState::*;
+
+        assert!(!Created.can_transition_to(Notarizing));
+        assert!(!Upgraded.can_transition_to(Signed));
+        assert!(!Upgraded.can_transition_to(Expired));
+        assert!(!Signed.can_transition_to(Failed));
+        assert!(!Failed.can_transition_to(Notarizing));
+        assert!(!Expired.can_transition_to(Upgraded));
+
+        assert!(Created.can_transition_to(Failed));
+        assert!(Created.can_transition_to(Expired));
+    }
+
+    #[test]
+    fn test_session_expiry() {
+        let store = store();
+        store
+            .create("stale".to_string(), stale_session_data(), None)
+            .unwrap();
+        store
+            .create("fresh".to_string(), session_data(), None)
+            .unwrap();
+
+        // Only the session which has not expired is kept
+        assert_eq!(store.count(SessionState::Created), 1);
+        assert!(matches!(
+            SessionHandle::upgrade(&store, "stale".to_string()),
+            Err(SessionStateError::NotFound(_))
+        ));
+        SessionHandle::upgrade(&store, "fresh".to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_session_capacity() {
+        let sessions = store();
+        sessions
+            .create("a".to_string(), session_data(), Some(2))
+            .unwrap();
+        let (handle, _) = SessionHandle::upgrade(&sessions, "a".to_string()).unwrap();
+        sessions
+            .create("b".to_string(), session_data(), Some(2))
+            .unwrap();
+
+        // Sessions count towards the limit until they end
+        assert_eq!(
+            sessions.create("c".to_string(), session_data(), Some(2)),
+            Err(SessionStateError::CapacityReached(2))
+        );
+        drop(handle);
+        sessions
+            .create("c".to_string(), session_data(), Some(2))
+            .unwrap();
+
+        // Expired sessions do not count towards the limit
+        let store = store();
+        store
+            .create("stale".to_string(), stale_session_data(), Some(1))
+            .unwrap();
+        store
+            .create("d".to_string(), session_data(), Some(1))
+            .unwrap();
+    }
+}
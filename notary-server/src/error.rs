@@ -1,12 +1,15 @@
 use axum::{
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
 use eyre::Report;
-use std::error::Error;
+use notary_api_types::{ErrorCode, ErrorResponse, JsonLimitError, PROBLEM_JSON_CONTENT_TYPE};
+use std::{error::Error, time::Duration};
 
 use tlsn_verifier::tls::{VerifierConfigBuilderError, VerifierError};
 
+use crate::service::limits::ExceededLimit;
+
 #[derive(Debug, thiserror::Error)]
 pub enum NotaryServerError {
     #[error(transparent)]
@@ -19,6 +22,23 @@ pub enum NotaryServerError {
     BadProverRequest(String),
     #[error("Unauthorized request from prover: {0}")]
     UnauthorizedProverRequest(String),
+    #[error("Notary is unavailable: {0}")]
+    Unavailable(String),
+    #[error("Notarization exceeded the maximum session duration of {0:?}")]
+    Timeout(Duration),
+    #[error("Notarization exceeded its {0}")]
+    ResourceLimit(ExceededLimit),
+    #[error("Request exceeded the processing time budget of {0:?}")]
+    ProcessingTimeout(Duration),
+    #[error("Too many requests: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Duration,
+    },
+    #[error("Request body is too large: {message}")]
+    PayloadTooLarge { message: String, limit: u64 },
+    #[error("Request body exceeds a JSON limit: {0}")]
+    JsonLimit(#[from] JsonLimitError),
 }
 
 impl From<VerifierError> for NotaryServerError {
@@ -33,23 +53,179 @@ impl From<VerifierConfigBuilderError> for NotaryServerError {
     }
 }
 
-/// Trait implementation to convert this error into an axum http response
+impl NotaryServerError {
+    /// Returns the status and machine-readable code of the error
+    fn status_and_code(&self) -> (StatusCode, ErrorCode) {
+        match self {
+            NotaryServerError::Unexpected(_)
+            | NotaryServerError::Connection(_)
+            | NotaryServerError::Notarization(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::Internal)
+            }
+            NotaryServerError::BadProverRequest(_) => {
+                (StatusCode::BAD_REQUEST, ErrorCode::BadRequest)
+            }
+            NotaryServerError::UnauthorizedProverRequest(_) => {
+                (StatusCode::UNAUTHORIZED, ErrorCode::Unauthorized)
+            }
+            NotaryServerError::Unavailable(_) => {
+                (StatusCode::SERVICE_UNAVAILABLE, ErrorCode::Unavailable)
+            }
+            NotaryServerError::Timeout(_) => (StatusCode::GATEWAY_TIMEOUT, ErrorCode::Timeout),
+            NotaryServerError::ResourceLimit(_) | NotaryServerError::ProcessingTimeout(_) => {
+                (StatusCode::SERVICE_UNAVAILABLE, ErrorCode::ResourceLimit)
+            }
+            NotaryServerError::RateLimited { .. } => {
+                (StatusCode::TOO_MANY_REQUESTS, ErrorCode::RateLimited)
+            }
+            NotaryServerError::PayloadTooLarge { .. } => {
+                (StatusCode::PAYLOAD_TOO_LARGE, ErrorCode::PayloadTooLarge)
+            }
+            NotaryServerError::JsonLimit(err) => (StatusCode::BAD_REQUEST, err.code()),
+        }
+    }
+
+    /// Returns how long the client should wait before retrying, if known
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            NotaryServerError::RateLimited { retry_after, .. } => Some(*retry_after),
+            _ => None,
+        }
+    }
+
+    /// Returns the limit which was exceeded, if any
+    fn limit(&self) -> Option<u64> {
+        match self {
+            NotaryServerError::PayloadTooLarge { limit, .. } => Some(*limit),
+            NotaryServerError::Timeout(max_duration) => Some(max_duration.as_secs()),
+            NotaryServerError::ResourceLimit(exceeded) => Some(exceeded.value()),
+            NotaryServerError::ProcessingTimeout(max_time) => Some(max_time.as_millis() as u64),
+            NotaryServerError::JsonLimit(err) => Some(err.limit() as u64),
+            _ => None,
+        }
+    }
+}
+
+/// Trait implementation to convert this error into an RFC 7807 problem details response
 impl IntoResponse for NotaryServerError {
     fn into_response(self) -> Response {
-        match self {
-            bad_request_error @ NotaryServerError::BadProverRequest(_) => {
-                (StatusCode::BAD_REQUEST, bad_request_error.to_string()).into_response()
-            }
-            unauthorized_request_error @ NotaryServerError::UnauthorizedProverRequest(_) => (
-                StatusCode::UNAUTHORIZED,
-                unauthorized_request_error.to_string(),
-            )
-                .into_response(),
-            _ => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Something wrong happened.",
-            )
-                .into_response(),
+        let (status, code) = self.status_and_code();
+        let detail = match code {
+            // Do not leak details of internal errors to the prover
+            ErrorCode::Internal => "Something wrong happened.".to_string(),
+            _ => crate::server_tracing::redact(&self.to_string()),
+        };
+        // Round up so that clients retrying after the advertised delay are not limited again
+        let retry_after = self
+            .retry_after()
+            .map(|duration| duration.as_secs() + u64::from(duration.subsec_nanos() > 0));
+
+        let body = ErrorResponse {
+            retry_after,
+            limit: self.limit(),
+            ..ErrorResponse::new(code, status.as_u16(), detail)
+        };
+
+        let mut response = (status, Json(body)).into_response();
+        let headers = response.headers_mut();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(PROBLEM_JSON_CONTENT_TYPE),
+        );
+        if let Some(secs) = retry_after {
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// One instance of every error, which fails to compile when a variant is not covered
+    fn all_errors() -> Vec<NotaryServerError> {
+        let errors = vec![
+            NotaryServerError::Unexpected(eyre::eyre!("unexpected")),
+            NotaryServerError::Connection("connection".to_string()),
+            NotaryServerError::Notarization(Box::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "notarization",
+            ))),
+            NotaryServerError::BadProverRequest("bad request".to_string()),
+            NotaryServerError::UnauthorizedProverRequest("unauthorized".to_string()),
+            NotaryServerError::Unavailable("unavailable".to_string()),
+            NotaryServerError::Timeout(Duration::from_secs(60)),
+            NotaryServerError::ResourceLimit(ExceededLimit::CpuTime(Duration::from_secs(30))),
+            NotaryServerError::ResourceLimit(ExceededLimit::Memory(1 << 30)),
+            NotaryServerError::ProcessingTimeout(Duration::from_millis(1000)),
+            NotaryServerError::RateLimited {
+                message: "rate limited".to_string(),
+                retry_after: Duration::from_millis(1500),
+            },
+            NotaryServerError::PayloadTooLarge {
+                message: "too large".to_string(),
+                limit: 1024,
+            },
+            NotaryServerError::JsonLimit(JsonLimitError::TooDeep(32)),
+        ];
+
+        for error in &errors {
+            match error {
+                NotaryServerError::Unexpected(_)
+                | NotaryServerError::Connection(_)
+                | NotaryServerError::Notarization(_)
+                | NotaryServerError::BadProverRequest(_)
+                | NotaryServerError::UnauthorizedProverRequest(_)
+                | NotaryServerError::Unavailable(_)
+                | NotaryServerError::Timeout(_)
+                | NotaryServerError::ResourceLimit(_)
+                | NotaryServerError::ProcessingTimeout(_)
+                | NotaryServerError::RateLimited { .. }
+                | NotaryServerError::PayloadTooLarge { .. }
+                | NotaryServerError::JsonLimit(_) => {}
+            }
+        }
+
+        errors
+    }
+
+    #[tokio::test]
+    async fn test_problem_details() {
+        for error in all_errors() {
+            let (status, code) = error.status_and_code();
+            let limit = error.limit();
+            let response = error.into_response();
+
+            assert_eq!(response.status(), status);
+            assert_eq!(
+                response.headers()[header::CONTENT_TYPE],
+                PROBLEM_JSON_CONTENT_TYPE
+            );
+
+            let retry_after = response
+                .headers()
+                .get(header::RETRY_AFTER)
+                .map(|value| value.to_str().unwrap().parse::<u64>().unwrap());
+
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let problem: ErrorResponse = serde_json::from_slice(&body).unwrap();
+
+            assert_eq!(problem.type_uri, code.type_uri());
+            assert_eq!(problem.title, code.title());
+            assert_eq!(problem.status, status.as_u16());
+            assert_eq!(problem.code, code);
+            assert!(!problem.detail.is_empty());
+            assert_eq!(problem.retry_after, retry_after);
+            assert_eq!(problem.limit, limit);
+
+            if code == ErrorCode::RateLimited {
+                assert_eq!(retry_after, Some(2));
+            }
+            if code == ErrorCode::Internal {
+                assert_eq!(problem.detail, "Something wrong happened.");
+            }
         }
     }
 }
@@ -1,3 +1,4 @@
+mod clock;
 mod config;
 mod domain;
 mod error;
@@ -5,17 +6,26 @@ mod middleware;
 mod server;
 mod server_tracing;
 mod service;
+mod signer;
 mod util;
 
 pub use config::{
-    AuthorizationProperties, LoggingProperties, NotarizationProperties, NotaryServerProperties,
-    NotarySigningKeyProperties, ServerProperties, TLSProperties,
+    config_schema, AuthorizationProperties, ClockProperties, JsonLimitsProperties,
+    LoggingProperties, NotarizationProperties, NotaryServerProperties, NotarySigningKeyProperties,
+    ProxyProperties, PublicApiProperties, ServerProperties, TLSProperties,
 };
 pub use domain::{
-    cli::CliFields,
-    notary::{ClientType, NotarizationSessionRequest, NotarizationSessionResponse},
+    cli::{CliFields, Command},
+    notary::{
+        ByteCount, ByteRange, ClientType, ErrorCode, ErrorResponse, InspectProofResponse,
+        LoadResponse, NotarizationEstimateRequest, NotarizationEstimateResponse,
+        NotarizationSessionRequest, NotarizationSessionResponse, SignatureAlgorithm,
+        TranscriptSummary,
+    },
 };
 pub use error::NotaryServerError;
 pub use server::{read_pem_file, run_server};
-pub use server_tracing::init_tracing;
+pub use server_tracing::{init_tracing, otel_collector_config};
+#[cfg(feature = "alloc-accounting")]
+pub use service::limits::CountingAllocator;
 pub use util::parse_config_file;
@@ -3,15 +3,38 @@ use structopt::StructOpt;
 use tracing::debug;
 
 use notary_server::{
-    init_tracing, parse_config_file, run_server, CliFields, NotaryServerError,
-    NotaryServerProperties,
+    config_schema, init_tracing, otel_collector_config, parse_config_file, run_server, CliFields,
+    Command, NotaryServerError, NotaryServerProperties,
 };
 
+#[cfg(feature = "alloc-accounting")]
+#[global_allocator]
+static ALLOCATOR: notary_server::CountingAllocator = notary_server::CountingAllocator;
+
 #[tokio::main]
 async fn main() -> Result<(), NotaryServerError> {
     // Load command line arguments which contains the config file location
     let cli_fields: CliFields = CliFields::from_args();
+    if let Some(Command::ConfigSchema) = cli_fields.command {
+        let schema = serde_json::to_string_pretty(&config_schema())
+            .map_err(|err| eyre!("Failed to serialize config schema: {err}"))?;
+        println!("{schema}");
+        return Ok(());
+    }
+    if let Some(Command::OtelCollectorConfig) = cli_fields.command {
+        println!("{}", otel_collector_config());
+        return Ok(());
+    }
+
     let config: NotaryServerProperties = parse_config_file(&cli_fields.config_file)?;
+    config
+        .validate()
+        .map_err(|err| eyre!("Invalid config {}: {err}", cli_fields.config_file))?;
+
+    if let Some(Command::ValidateConfig) = cli_fields.command {
+        println!("Config {} is valid", cli_fields.config_file);
+        return Ok(());
+    }
 
     // Set up tracing for logging
     init_tracing(&config).map_err(|err| eyre!("Failed to set up tracing: {err}"))?;
@@ -22,6 +22,10 @@ where
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let notary_globals = NotaryGlobals::from_ref(state);
+        if notary_globals.public_api_config.is_public(parts.uri.path()) {
+            return check_public_request(parts, &notary_globals).map(|_| Self);
+        }
+
         let Some(whitelist) = notary_globals.authorization_whitelist else {
             trace!("Skipping authorization as whitelist is not set.");
             return Ok(Self);
@@ -52,6 +56,47 @@ where
     }
 }
 
+/// Applies the limits of public routes to a request, in place of the API key check
+fn check_public_request(
+    parts: &Parts,
+    notary_globals: &NotaryGlobals,
+) -> Result<(), NotaryServerError> {
+    if let Some(limiter) = &notary_globals.public_api_limiter {
+        let mut limiter = limiter.lock().unwrap();
+        if !limiter.try_acquire() {
+            let err_msg = format!("Rate limit of {} exceeded.", parts.uri.path());
+            error!(err_msg);
+            return Err(NotaryServerError::RateLimited {
+                message: err_msg,
+                retry_after: limiter.retry_after(),
+            });
+        }
+    }
+
+    if let Some(max_body_bytes) = notary_globals.public_api_config.max_body_bytes {
+        // Streamed bodies are rejected as their size is not known upfront
+        let content_length = match parts.headers.get(header::CONTENT_LENGTH) {
+            Some(value) => value
+                .to_str()
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok()),
+            None if parts.headers.contains_key(header::TRANSFER_ENCODING) => None,
+            None => Some(0),
+        };
+        if content_length.map_or(true, |length| length > max_body_bytes) {
+            let err_msg = format!("Request body exceeds the limit of {max_body_bytes} bytes.");
+            error!(err_msg);
+            return Err(NotaryServerError::PayloadTooLarge {
+                message: err_msg,
+                limit: max_body_bytes,
+            });
+        }
+    }
+
+    trace!("Public request allowed.");
+    Ok(())
+}
+
 /// Helper function to check if an API key is in whitelist
 fn api_key_is_valid(
     api_key: &str,
@@ -11,10 +11,10 @@ use hyper::server::{
     accept::Accept,
     conn::{AddrIncoming, Http},
 };
+use notary_api_types::PublicKeyInfo;
 use notify::{
     event::ModifyKind, Error, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
 };
-use p256::{ecdsa::SigningKey, pkcs8::DecodePrivateKey};
 use rustls::{Certificate, PrivateKey, ServerConfig};
 use std::{
     collections::HashMap,
@@ -33,7 +33,8 @@ use tower::MakeService;
 use tracing::{debug, error, info};
 
 use crate::{
-    config::{NotaryServerProperties, NotarySigningKeyProperties},
+    clock::start_clock_check,
+    config::{config_schema, NotaryServerProperties, NotarySigningKeyProperties},
     domain::{
         auth::{authorization_whitelist_vec_into_hashmap, AuthorizationWhitelistRecord},
         notary::NotaryGlobals,
@@ -41,15 +42,25 @@ use crate::{
     },
     error::NotaryServerError,
     middleware::AuthorizationMiddleware,
-    service::{initialize, upgrade_protocol},
+    service::{estimate, initialize, inspect_proof, load, proxy::proxy, upgrade_protocol},
+    signer::{NotarySigner, NotarySigners},
     util::parse_csv_file,
 };
 
 /// Start a TCP server (with or without TLS) to accept notarization request for both TCP and WebSocket clients
 #[tracing::instrument(skip(config))]
 pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotaryServerError> {
-    // Load the private key for notarized transcript signing
-    let notary_signing_key = load_notary_signing_key(&config.notary_key).await?;
+    // Load the private keys for notarized transcript signing
+    let notary_signers = NotarySigners::new(
+        load_notary_signing_key(&config.notary_key).await?,
+        futures::future::try_join_all(
+            config
+                .additional_notary_keys
+                .iter()
+                .map(load_notary_signing_key),
+        )
+        .await?,
+    )?;
     // Build TLS acceptor if it is turned on
     let tls_acceptor = if !config.tls.enabled {
         debug!("Skipping TLS setup as it is turned off.");
@@ -101,14 +112,31 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
 
     let protocol = Arc::new(Http::new());
     let notary_globals = NotaryGlobals::new(
-        notary_signing_key,
+        notary_signers.clone(),
         config.notarization.clone(),
+        config.proxy.clone(),
+        config.public_api.clone(),
+        config.json_limits.limits(),
         authorization_whitelist,
     );
+    // Check the clock before accepting sessions, as the session headers are signed with its time
+    start_clock_check(&config.clock, notary_globals.clock_status.clone()).await;
 
     // Parameters needed for the info endpoint
-    let public_key = std::fs::read_to_string(&config.notary_key.public_key_pem_path)
-        .map_err(|err| eyre!("Failed to load notary public signing key for notarization: {err}"))?;
+    let public_keys = std::iter::once(&config.notary_key)
+        .chain(&config.additional_notary_keys)
+        .zip(notary_signers.algorithms())
+        .map(|(key, algorithm)| {
+            let public_key = std::fs::read_to_string(&key.public_key_pem_path).map_err(|err| {
+                eyre!("Failed to load notary public signing key for notarization: {err}")
+            })?;
+            Ok(PublicKeyInfo {
+                algorithm,
+                public_key,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let public_key = public_keys[0].public_key.clone();
     let version = env!("CARGO_PKG_VERSION").to_string();
     let git_commit_hash = env!("GIT_COMMIT_HASH").to_string();
     let git_commit_timestamp = env!("GIT_COMMIT_TIMESTAMP").to_string();
@@ -140,6 +168,7 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
                     Json(InfoResponse {
                         version,
                         public_key,
+                        public_keys,
                         git_commit_hash,
                         git_commit_timestamp,
                     }),
@@ -147,7 +176,14 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
                     .into_response()
             }),
         )
+        .route(
+            "/config/schema",
+            get(|| async move { (StatusCode::OK, Json(config_schema())).into_response() }),
+        )
+        .route("/load", get(load))
         .route("/session", post(initialize))
+        .route("/estimate", post(estimate))
+        .route("/inspect-proof", post(inspect_proof))
         // Not applying auth middleware to /notarize endpoint for now as we can rely on our
         // short-lived session id generated from /session endpoint, as it is not possible
         // to use header for API key for websocket /notarize endpoint due to browser restriction
@@ -158,6 +194,10 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
             NotaryGlobals,
         >(notary_globals.clone()))
         .route("/notarize", get(upgrade_protocol))
+        // Not applying auth middleware to /proxy endpoint for the same reason as /notarize, the
+        // connection is authorized by the session id instead and the target hosts are restricted
+        // by the allowlist
+        .route("/proxy", get(proxy))
         .layer(CorsLayer::permissive())
         .with_state(notary_globals);
     let mut app = router.into_make_service();
@@ -215,13 +255,16 @@ pub async fn run_server(config: &NotaryServerProperties) -> Result<(), NotarySer
 }
 
 /// Load notary signing key from static file
-async fn load_notary_signing_key(config: &NotarySigningKeyProperties) -> Result<SigningKey> {
+async fn load_notary_signing_key(config: &NotarySigningKeyProperties) -> Result<NotarySigner> {
     debug!("Loading notary server's signing key");
 
-    let notary_signing_key = SigningKey::read_pkcs8_pem_file(&config.private_key_pem_path)
+    let notary_signing_key = NotarySigner::read_pkcs8_pem_file(&config.private_key_pem_path)
         .map_err(|err| eyre!("Failed to load notary signing key for notarization: {err}"))?;
 
-    debug!("Successfully loaded notary server's signing key!");
+    debug!(
+        algorithm = ?notary_signing_key.algorithm(),
+        "Successfully loaded notary server's signing key!"
+    );
     Ok(notary_signing_key)
 }
 
@@ -358,7 +401,7 @@ mod test {
             private_key_pem_path: "./fixture/notary/notary.key".to_string(),
             public_key_pem_path: "./fixture/notary/notary.pub".to_string(),
         };
-        let result: Result<SigningKey> = load_notary_signing_key(&config).await;
+        let result: Result<NotarySigner> = load_notary_signing_key(&config).await;
         assert!(result.is_ok(), "Could not load notary private key");
     }
 
@@ -1,10 +1,82 @@
-use eyre::Result;
-use std::str::FromStr;
-use tracing::Level;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+use eyre::{ensure, Result};
+use std::{
+    fmt::{self, Write},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, OnceLock,
+    },
+};
+use tracing::{
+    field::{Field, Visit},
+    Level, Subscriber,
+};
+use tracing_subscriber::{
+    field::RecordFields,
+    filter::dynamic_filter_fn,
+    fmt::{
+        format::{Compact, Format, Writer},
+        FormatFields, MakeWriter,
+    },
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer, Registry,
+};
+
+use serde_json::json;
+use tlsn_common::span;
 
 use crate::config::NotaryServerProperties;
 
+/// Placeholder written in place of redacted values
+const REDACTED: &str = "[REDACTED]";
+
+/// Lowercase names of the fields to redact, set once the tracing is initialized
+static REDACTED_FIELDS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Returns an example OpenTelemetry collector configuration in YAML, which receives the spans of
+/// the protocol phases from both parties over OTLP and groups them by the hash of the session id,
+/// so that the traces of the prover and the notary of a session can be joined
+pub fn otel_collector_config() -> String {
+    let config = json!({
+        "receivers": {
+            "otlp": {
+                "protocols": { "grpc": {}, "http": {} }
+            }
+        },
+        "processors": {
+            "filter/tlsn": {
+                "error_mode": "ignore",
+                "traces": {
+                    "span": [format!(
+                        "not IsMatch(name, \"^({})$\")",
+                        span::PHASES.join("|")
+                    )]
+                }
+            },
+            "groupbyattrs/tlsn": {
+                "keys": [span::ATTR_SESSION_ID_HASH]
+            },
+            "batch": {}
+        },
+        "exporters": {
+            "debug": { "verbosity": "detailed" }
+        },
+        "service": {
+            "pipelines": {
+                "traces": {
+                    "receivers": ["otlp"],
+                    "processors": ["filter/tlsn", "groupbyattrs/tlsn", "batch"],
+                    "exporters": ["debug"]
+                }
+            }
+        }
+    });
+
+    serde_yaml::to_string(&config).expect("collector config is serializable")
+}
+
 pub fn init_tracing(config: &NotaryServerProperties) -> Result<()> {
     // Retrieve log filtering logic from config
     let directives = match &config.logging.filter {
@@ -18,12 +90,21 @@ pub fn init_tracing(config: &NotaryServerProperties) -> Result<()> {
     };
     let filter_layer = EnvFilter::builder().parse(directives)?;
 
-    // Format the log
-    let format_layer = tracing_subscriber::fmt::layer()
-        // Use a more compact, abbreviated log format
-        .compact()
-        .with_thread_ids(true)
-        .with_thread_names(true);
+    let sample_ratio = config.logging.sample_ratio.unwrap_or(1.0);
+    ensure!(
+        (0.0..=1.0).contains(&sample_ratio),
+        "Log sample ratio must be between 0 and 1"
+    );
+    let sampler = Sampler::new(sample_ratio);
+
+    let redacted_fields =
+        REDACTED_FIELDS.get_or_init(|| lowercase(&config.logging.redacted_fields));
+    let format_layer = format_layer(redacted_fields, std::io::stdout)
+        // Only events are sampled, warnings and errors are never sampled out. A dynamic filter is
+        // used, as the result of a static filter is cached per callsite
+        .with_filter(dynamic_filter_fn(move |metadata, _| {
+            metadata.is_span() || *metadata.level() <= Level::WARN || sampler.sample()
+        }));
 
     Registry::default()
         .with(filter_layer)
@@ -32,3 +113,343 @@ pub fn init_tracing(config: &NotaryServerProperties) -> Result<()> {
 
     Ok(())
 }
+
+/// Redacts the values of the configured fields from a text which is sent to the prover, e.g. the
+/// detail of an error response
+pub(crate) fn redact(text: &str) -> String {
+    match REDACTED_FIELDS.get() {
+        Some(fields) if !fields.is_empty() => scrub(text, fields),
+        _ => text.to_string(),
+    }
+}
+
+/// Returns the layer which formats the log, redacting the values of the given fields
+fn format_layer<S, W>(
+    redacted_fields: &[String],
+    writer: W,
+) -> tracing_subscriber::fmt::Layer<S, ScrubbingFields, Format<Compact>, W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + 'static,
+{
+    tracing_subscriber::fmt::layer()
+        // Use a more compact, abbreviated log format
+        .compact()
+        .with_thread_ids(true)
+        .with_thread_names(true)
+        .fmt_fields(ScrubbingFields::new(redacted_fields))
+        .with_writer(writer)
+}
+
+fn lowercase(fields: &[String]) -> Vec<String> {
+    fields.iter().map(|field| field.to_lowercase()).collect()
+}
+
+/// Keeps a fixed ratio of the events passed to it, spread evenly over time
+#[derive(Clone)]
+struct Sampler {
+    ratio: f64,
+    count: Arc<AtomicU64>,
+}
+
+impl Sampler {
+    fn new(ratio: f64) -> Self {
+        Self {
+            ratio,
+            count: Default::default(),
+        }
+    }
+
+    /// Returns whether the next event should be kept
+    fn sample(&self) -> bool {
+        if self.ratio >= 1.0 {
+            return true;
+        }
+        let count = self.count.fetch_add(1, Ordering::Relaxed) as f64;
+        // Keep the event whenever the number of kept events should increase by one
+        ((count + 1.0) * self.ratio).floor() > (count * self.ratio).floor()
+    }
+}
+
+/// Formats the fields of events and spans, redacting the values of the configured fields
+///
+/// Fields are redacted by name before they are formatted, so styling of the output (e.g. ANSI
+/// colors) can't prevent a match. The formatted values of other fields are still scrubbed, as they
+/// may contain configured fields, e.g. in the debug output of a request or a header map.
+struct ScrubbingFields {
+    /// Lowercase names of the fields to redact
+    fields: Vec<String>,
+}
+
+impl ScrubbingFields {
+    fn new(fields: &[String]) -> Self {
+        Self {
+            fields: lowercase(fields),
+        }
+    }
+}
+
+impl<'writer> FormatFields<'writer> for ScrubbingFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'writer>, fields: R) -> fmt::Result {
+        let mut visitor = ScrubbingVisitor {
+            writer,
+            fields: &self.fields,
+            is_empty: true,
+            result: Ok(()),
+        };
+        fields.record(&mut visitor);
+        visitor.result
+    }
+}
+
+struct ScrubbingVisitor<'a> {
+    writer: Writer<'a>,
+    fields: &'a [String],
+    is_empty: bool,
+    result: fmt::Result,
+}
+
+impl ScrubbingVisitor<'_> {
+    fn scrub(&self, value: &str) -> String {
+        if self.fields.is_empty() {
+            value.to_string()
+        } else {
+            scrub(value, self.fields)
+        }
+    }
+}
+
+impl Visit for ScrubbingVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.record_debug(field, &format_args!("{value}"))
+        } else {
+            self.record_debug(field, &value)
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let name = field.name();
+        // Fields added by the log compatibility layer are not formatted, as with the default
+        // formatter
+        if self.result.is_err() || name.starts_with("log.") {
+            return;
+        }
+        let name = name.strip_prefix("r#").unwrap_or(name);
+        let separator = if self.is_empty { "" } else { " " };
+        self.is_empty = false;
+
+        self.result = if name == "message" {
+            let message = self.scrub(&format!("{value:?}"));
+            write!(self.writer, "{separator}{message}")
+        } else if self
+            .fields
+            .iter()
+            .any(|field| field.eq_ignore_ascii_case(name))
+        {
+            write!(self.writer, "{separator}{name}={REDACTED}")
+        } else {
+            let value = self.scrub(&format!("{value:?}"));
+            write!(self.writer, "{separator}{name}={value}")
+        };
+    }
+}
+
+/// Replaces the values of the given fields in a log line, matching the field names
+/// case-insensitively. Handles `key=value` pairs of the log format, `key: value` pairs of debug
+/// output (e.g. structs and header maps) and `"key": value` pairs of JSON
+fn scrub(line: &str, fields: &[String]) -> String {
+    let lowercase = line.to_ascii_lowercase();
+    let bytes = line.as_bytes();
+    let mut scrubbed = String::with_capacity(line.len());
+    let mut copied = 0;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let Some(field) = fields.iter().find(|field| {
+            lowercase.as_bytes()[pos..].starts_with(field.as_bytes())
+                && (pos == 0 || !is_ident_byte(bytes[pos - 1]))
+                && bytes
+                    .get(pos + field.len())
+                    .map_or(true, |byte| !is_ident_byte(*byte))
+        }) else {
+            pos += 1;
+            continue;
+        };
+
+        let mut value_start = pos + field.len();
+        if bytes.get(value_start) == Some(&b'"') {
+            value_start += 1;
+        }
+        value_start = match bytes.get(value_start) {
+            Some(b'=') => value_start + 1,
+            Some(b':') => value_start + 1,
+            _ => {
+                pos += field.len();
+                continue;
+            }
+        };
+        while bytes.get(value_start) == Some(&b' ') {
+            value_start += 1;
+        }
+
+        let value_end = value_end(bytes, value_start);
+        if value_end > value_start {
+            scrubbed.push_str(&line[copied..value_start]);
+            scrubbed.push_str(REDACTED);
+            copied = value_end;
+        }
+        pos = value_end.max(pos + field.len());
+    }
+
+    scrubbed.push_str(&line[copied..]);
+    scrubbed
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-'
+}
+
+/// Returns the end of the value starting at `start`, which is either a quoted string or runs
+/// until the next separator outside of brackets
+fn value_end(bytes: &[u8], start: usize) -> usize {
+    if bytes.get(start) == Some(&b'"') {
+        let mut pos = start + 1;
+        while pos < bytes.len() {
+            match bytes[pos] {
+                b'\\' => pos += 2,
+                b'"' => return pos + 1,
+                _ => pos += 1,
+            }
+        }
+        return bytes.len();
+    }
+
+    let mut depth = 0usize;
+    let mut pos = start;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' if depth == 0 => break,
+            b')' | b']' | b'}' => depth -= 1,
+            b',' | b' ' | b'\n' if depth == 0 => break,
+            _ => {}
+        }
+        pos += 1;
+    }
+    pos
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scrub() {
+        let fields = vec!["authorization".to_string(), "max_sent_data".to_string()];
+
+        assert_eq!(
+            scrub(
+                r#"payload=Ok(Json(Request { max_sent_data: Some(4096), max_recv_data: None }))"#,
+                &fields
+            ),
+            r#"payload=Ok(Json(Request { max_sent_data: [REDACTED], max_recv_data: None }))"#
+        );
+        assert_eq!(
+            scrub(
+                r#"headers={"authorization": "secret key", "host": "notary"}"#,
+                &fields
+            ),
+            r#"headers={"authorization": [REDACTED], "host": "notary"}"#
+        );
+        assert_eq!(
+            scrub("Authorization=abc other_max_sent_data=1", &fields),
+            "Authorization=[REDACTED] other_max_sent_data=1"
+        );
+    }
+
+    #[test]
+    fn test_format_layer() {
+        #[derive(Clone, Default)]
+        struct Output(Arc<std::sync::Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Output {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Request {
+            authorization: &'static str,
+            host: &'static str,
+        }
+
+        let output = Output::default();
+        let writer = output.clone();
+        // ANSI styling is enabled by default, which must not prevent the redaction
+        let layer =
+            format_layer(&["Authorization".to_string()], move || writer.clone()).with_ansi(true);
+        let subscriber = Registry::default().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("session", authorization = "span secret");
+            let _guard = span.enter();
+            tracing::info!(
+                authorization = "field secret",
+                request = ?Request {
+                    authorization: "nested secret",
+                    host: "notary",
+                },
+                "Received authorization=secret-token"
+            );
+        });
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(!output.contains("secret"), "{output}");
+        assert!(output.contains(REDACTED), "{output}");
+        assert!(output.contains("host: \"notary\""), "{output}");
+    }
+
+    #[test]
+    fn test_redact() {
+        REDACTED_FIELDS.get_or_init(|| vec!["authorization".to_string()]);
+
+        assert_eq!(
+            redact("Invalid request from prover: Authorization=abc"),
+            "Invalid request from prover: Authorization=[REDACTED]"
+        );
+    }
+
+    #[test]
+    fn test_otel_collector_config() {
+        let config: serde_yaml::Value = serde_yaml::from_str(&otel_collector_config()).unwrap();
+        let pipeline = &config["service"]["pipelines"]["traces"];
+        assert_eq!(pipeline["receivers"][0], "otlp");
+
+        let filter = config["processors"]["filter/tlsn"]["traces"]["span"][0]
+            .as_str()
+            .unwrap();
+        assert!(filter.contains("tlsn.tls_connection"));
+        assert_eq!(
+            config["processors"]["groupbyattrs/tlsn"]["keys"][0],
+            "tlsn.session_id_hash"
+        );
+    }
+
+    #[test]
+    fn test_sampler() {
+        let sampler = Sampler::new(0.25);
+        let kept = (0..100).filter(|_| sampler.sample()).count();
+        assert_eq!(kept, 25);
+
+        let sampler = Sampler::new(0.0);
+        assert!((0..100).all(|_| !sampler.sample()));
+    }
+}
@@ -1,33 +1,55 @@
 pub mod axum_websocket;
+pub mod counting;
+pub mod json;
+pub mod limits;
+pub mod proxy;
 pub mod tcp;
 pub mod websocket;
 
 use async_trait::async_trait;
 use axum::{
-    extract::{rejection::JsonRejection, FromRequestParts, Query, State},
+    extract::{FromRequestParts, Query, State},
     http::{header, request::Parts, StatusCode},
     response::{IntoResponse, Json, Response},
 };
 use axum_macros::debug_handler;
 use chrono::Utc;
-use p256::ecdsa::{Signature, SigningKey};
+use std::{
+    ops::Range,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tlsn_common::{
+    config::{ot_recv_estimate, ot_send_estimate, DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT},
+    Role,
+};
+use tlsn_core::{msg::PROTOCOL_VERSION, proof::TlsProof};
 use tlsn_verifier::tls::{Verifier, VerifierConfig};
 use tokio::io::{AsyncRead, AsyncWrite};
-use tokio_util::compat::TokioAsyncReadCompatExt;
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info};
 use uuid::Uuid;
 
 use crate::{
-    domain::notary::{
-        NotarizationRequestQuery, NotarizationSessionRequest, NotarizationSessionResponse,
-        NotaryGlobals, SessionData,
+    domain::{
+        notary::{
+            ByteCount, ByteRange, InspectProofResponse, NotarizationEstimateRequest,
+            NotarizationEstimateResponse, NotarizationRequestQuery, NotarizationSessionRequest,
+            NotarizationSessionResponse, NotaryGlobals, TranscriptSummary,
+        },
+        session::{SessionData, SessionHandle},
     },
     error::NotaryServerError,
     service::{
         axum_websocket::{header_eq, WebSocketUpgrade},
+        counting::CountingStream,
+        json::LimitedJson,
+        limits::run_limited,
         tcp::{tcp_notarize, TcpUpgrade},
         websocket::websocket_notarize,
     },
+    signer::NotarySigner,
 };
 
 /// A wrapper enum to facilitate extracting TCP connection for either WebSocket or TCP clients,
@@ -76,23 +98,37 @@ pub async fn upgrade_protocol(
     info!("Received upgrade protocol request");
     let session_id = params.session_id;
     // Fetch the configuration data from the store using the session_id
-    // This also removes the configuration data from the store as each session_id can only be used once
-    let (max_sent_data, max_recv_data) = match notary_globals.store.lock().await.remove(&session_id)
-    {
-        Some(data) => (data.max_sent_data, data.max_recv_data),
-        None => {
-            let err_msg = format!("Session id {} does not exist", session_id);
+    // This also moves the session out of the created state, as each session_id can only be used once
+    let (session, data) = match SessionHandle::upgrade(&notary_globals.store, session_id) {
+        Ok(upgraded) => upgraded,
+        Err(err) => {
+            let err_msg = err.to_string();
             error!(err_msg);
             return NotaryServerError::BadProverRequest(err_msg).into_response();
         }
     };
+    let (max_sent_data, max_recv_data) = (data.max_sent_data, data.max_recv_data);
+    // The algorithm was checked against the supported ones when the session was created
+    let Some(signer) = notary_globals
+        .notary_signers
+        .get(data.signature_algorithm)
+        .cloned()
+    else {
+        let err_msg = format!(
+            "Signature algorithm {:?} is not supported",
+            data.signature_algorithm
+        );
+        error!(err_msg);
+        return NotaryServerError::BadProverRequest(err_msg).into_response();
+    };
     // This completes the HTTP Upgrade request and returns a successful response to the client, meanwhile initiating the websocket or tcp connection
     match protocol_upgrade {
         ProtocolUpgrade::Ws(ws) => ws.on_upgrade(move |socket| {
             websocket_notarize(
                 socket,
                 notary_globals,
-                session_id,
+                session,
+                signer,
                 max_sent_data,
                 max_recv_data,
             )
@@ -101,7 +137,8 @@ pub async fn upgrade_protocol(
             tcp_notarize(
                 stream,
                 notary_globals,
-                session_id,
+                session,
+                signer,
                 max_sent_data,
                 max_recv_data,
             )
@@ -113,7 +150,7 @@ pub async fn upgrade_protocol(
 #[debug_handler(state = NotaryGlobals)]
 pub async fn initialize(
     State(notary_globals): State<NotaryGlobals>,
-    payload: Result<Json<NotarizationSessionRequest>, JsonRejection>,
+    payload: Result<LimitedJson<NotarizationSessionRequest>, NotaryServerError>,
 ) -> impl IntoResponse {
     info!(
         ?payload,
@@ -125,15 +162,25 @@ pub async fn initialize(
         Ok(payload) => payload,
         Err(err) => {
             error!("Malformed payload submitted for initializing notarization: {err}");
-            return NotaryServerError::BadProverRequest(err.to_string()).into_response();
+            return err.into_response();
         }
     };
 
     // Ensure that the max_transcript_size submitted is not larger than the global max limit configured in notary server
     if payload.max_sent_data.is_some() || payload.max_recv_data.is_some() {
-        let requested_transcript_size =
-            payload.max_sent_data.unwrap_or_default() + payload.max_recv_data.unwrap_or_default();
-        if requested_transcript_size > notary_globals.notarization_config.max_transcript_size {
+        let Some(requested_transcript_size) = payload
+            .max_sent_data
+            .unwrap_or_default()
+            .checked_add(payload.max_recv_data.unwrap_or_default())
+        else {
+            error!("Max transcript size requested overflows");
+            return NotaryServerError::BadProverRequest(
+                "Max transcript size requested exceeds the maximum threshold".to_string(),
+            )
+            .into_response();
+        };
+        if requested_transcript_size.get() > notary_globals.notarization_config.max_transcript_size
+        {
             error!(
                 "Max transcript size requested {:?} exceeds the maximum threshold {:?}",
                 requested_transcript_size, notary_globals.notarization_config.max_transcript_size
@@ -145,43 +192,186 @@ pub async fn initialize(
         }
     }
 
+    // Select the signer of the requested algorithm, or the default one
+    let signature_algorithm = match payload.signature_algorithm {
+        Some(algorithm) if notary_globals.notary_signers.get(algorithm).is_none() => {
+            error!("Signature algorithm {algorithm:?} requested is not supported");
+            return NotaryServerError::BadProverRequest(format!(
+                "Signature algorithm {algorithm:?} is not supported, supported algorithms are {:?}",
+                notary_globals.notary_signers.algorithms()
+            ))
+            .into_response();
+        }
+        Some(algorithm) => algorithm,
+        None => notary_globals.notary_signers.default_signer().algorithm(),
+    };
+
+    if !notary_globals.clock_status.in_sync() {
+        error!("Server clock is out of sync with the time sources");
+        return NotaryServerError::Unavailable(
+            "Server clock is out of sync with the time sources".to_string(),
+        )
+        .into_response();
+    }
+
     let prover_session_id = Uuid::new_v4().to_string();
 
-    // Store the configuration data in a temporary store
-    notary_globals.store.lock().await.insert(
+    // Store the configuration data in a temporary store, unless the notary is at capacity
+    if let Err(err) = notary_globals.store.create(
         prover_session_id.clone(),
         SessionData {
             max_sent_data: payload.max_sent_data,
             max_recv_data: payload.max_recv_data,
+            signature_algorithm,
             created_at: Utc::now(),
         },
-    );
-
-    trace!("Latest store state: {:?}", notary_globals.store);
+        notary_globals.notarization_config.max_concurrent_sessions,
+    ) {
+        error!("{err}");
+        return NotaryServerError::Unavailable(err.to_string()).into_response();
+    }
 
     // Return the session id in the response to the client
     (
         StatusCode::OK,
         Json(NotarizationSessionResponse {
             session_id: prover_session_id,
+            signature_algorithm: Some(signature_algorithm),
         }),
     )
         .into_response()
 }
 
-/// Run the notarization
+/// Handler to report the current load of the notary, e.g. for autoscalers
+pub async fn load(State(notary_globals): State<NotaryGlobals>) -> impl IntoResponse {
+    (StatusCode::OK, Json(notary_globals.load().await)).into_response()
+}
+
+/// Handler to summarize a proof without verifying it, e.g. for support tooling
+pub async fn inspect_proof(
+    payload: Result<LimitedJson<TlsProof>, NotaryServerError>,
+) -> impl IntoResponse {
+    let LimitedJson(proof) = match payload {
+        Ok(payload) => payload,
+        Err(err) => {
+            error!("Malformed proof submitted for inspection: {err}");
+            return err.into_response();
+        }
+    };
+
+    let header = &proof.session.header;
+    let (sent_ranges, recv_ranges) = proof.substrings.revealed_ranges();
+    let summarize = |length: usize, ranges: Vec<Range<usize>>| {
+        let revealed_bytes = ranges.iter().map(|range| range.len()).sum::<usize>();
+        TranscriptSummary {
+            length,
+            revealed_ranges: ranges
+                .into_iter()
+                .map(|range| ByteRange {
+                    start: range.start,
+                    end: range.end,
+                })
+                .collect(),
+            revealed_bytes,
+            redacted_bytes: length.saturating_sub(revealed_bytes),
+        }
+    };
+
+    (
+        StatusCode::OK,
+        Json(InspectProofResponse {
+            protocol_version: PROTOCOL_VERSION,
+            server_name: proof.session.session_info.server_name.as_str().to_string(),
+            time: header.time(),
+            signed: proof.session.signature.is_some(),
+            sent: summarize(header.sent_len(), sent_ranges.iter_ranges().collect()),
+            received: summarize(header.recv_len(), recv_ranges.iter_ranges().collect()),
+        }),
+    )
+        .into_response()
+}
+
+/// Handler to estimate the cost of a notarization before initializing a session
+#[debug_handler(state = NotaryGlobals)]
+pub async fn estimate(
+    State(notary_globals): State<NotaryGlobals>,
+    payload: Result<LimitedJson<NotarizationEstimateRequest>, NotaryServerError>,
+) -> impl IntoResponse {
+    info!(?payload, "Received request for notarization cost estimate");
+
+    let payload = match payload {
+        Ok(payload) => payload,
+        Err(err) => {
+            error!("Malformed payload submitted for notarization estimate: {err}");
+            return err.into_response();
+        }
+    };
+
+    let max_sent_data = payload.max_sent_data.unwrap_or(DEFAULT_MAX_SENT_LIMIT);
+    let max_recv_data = payload.max_recv_data.unwrap_or(DEFAULT_MAX_RECV_LIMIT);
+    let max_transcript_size =
+        ByteCount::new(notary_globals.notarization_config.max_transcript_size);
+
+    // Apply the same limit as the /session endpoint, so that the estimate is only given for
+    // sessions which would be accepted
+    let Some(requested_transcript_size) = max_sent_data.checked_add(max_recv_data) else {
+        error!("Max transcript size requested overflows");
+        return NotaryServerError::BadProverRequest(
+            "Max transcript size requested exceeds the maximum threshold".to_string(),
+        )
+        .into_response();
+    };
+    if requested_transcript_size > max_transcript_size {
+        error!(
+            "Max transcript size requested {:?} exceeds the maximum threshold {:?}",
+            requested_transcript_size, max_transcript_size
+        );
+        return NotaryServerError::BadProverRequest(
+            "Max transcript size requested exceeds the maximum threshold".to_string(),
+        )
+        .into_response();
+    }
+
+    // The sizes are bounded by the configured maximum from here on, which may still be too large
+    // for the estimate, so it saturates
+    let ot_count = ot_send_estimate(Role::Verifier, max_sent_data, max_recv_data).saturating_add(
+        ot_recv_estimate(Role::Verifier, max_sent_data, max_recv_data),
+    );
+
+    (
+        StatusCode::OK,
+        Json(NotarizationEstimateResponse {
+            max_sent_data,
+            max_recv_data,
+            ot_count,
+            estimated_bandwidth: notary_globals
+                .session_calibration
+                .estimated_bandwidth(requested_transcript_size),
+            expected_wall_time_ms: notary_globals.expected_wall_time_ms(),
+            pending_sessions: notary_globals.queued_sessions(),
+            max_transcript_size,
+        }),
+    )
+        .into_response()
+}
+
+/// Run the notarization within the limits of the session, calibrating the estimates with the
+/// bandwidth of successful notarizations
 pub async fn notary_service<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
     socket: T,
-    signing_key: &SigningKey,
+    notary_globals: &NotaryGlobals,
+    signer: &NotarySigner,
     session_id: &str,
-    max_sent_data: Option<usize>,
-    max_recv_data: Option<usize>,
+    max_sent_data: Option<ByteCount>,
+    max_recv_data: Option<ByteCount>,
 ) -> Result<(), NotaryServerError> {
     debug!(?session_id, "Starting notarization...");
 
     let mut config_builder = VerifierConfig::builder();
 
-    config_builder = config_builder.id(session_id);
+    config_builder = config_builder
+        .id(session_id)
+        .signature_algorithm(signer.protocol_algorithm());
 
     if let Some(max_sent_data) = max_sent_data {
         config_builder = config_builder.max_sent_data(max_sent_data);
@@ -192,10 +382,34 @@ pub async fn notary_service<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
     }
 
     let config = config_builder.build()?;
+    let transcript_size = ByteCount::new(
+        config
+            .max_sent_data()
+            .get()
+            .saturating_add(config.max_recv_data().get()),
+    );
+
+    let bytes_exchanged = Arc::new(AtomicU64::new(0));
+    let socket = CountingStream::new(socket, bytes_exchanged.clone());
+    let signer = signer.clone();
+    let notarize = async move {
+        signer
+            .notarize(Verifier::new(config), socket)
+            .await
+            .map_err(NotaryServerError::from)
+    };
+
+    // Dropping the notarization future aborts the session and closes the connection to the prover
+    run_limited(
+        notary_globals.notarization_config.session_limits(),
+        &notary_globals.session_limit_metrics,
+        notarize,
+    )
+    .await?;
 
-    Verifier::new(config)
-        .notarize::<_, Signature>(socket.compat(), signing_key)
-        .await?;
+    notary_globals
+        .session_calibration
+        .record(bytes_exchanged.load(Ordering::Relaxed), transcript_size);
 
     Ok(())
 }
@@ -0,0 +1,85 @@
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Stream which counts the bytes read from and written to the inner stream
+pub struct CountingStream<T> {
+    inner: T,
+    bytes: Arc<AtomicU64>,
+}
+
+impl<T> CountingStream<T> {
+    /// Wraps the stream, adding the bytes in both directions to the counter
+    pub fn new(inner: T, bytes: Arc<AtomicU64>) -> Self {
+        Self { inner, bytes }
+    }
+
+    fn count(&self, bytes: usize) {
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for CountingStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            self.count(buf.filled().len() - filled);
+        }
+        poll
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for CountingStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            self.count(written);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_counting_stream() {
+        let bytes = Arc::new(AtomicU64::new(0));
+        let (client, mut server) = tokio::io::duplex(64);
+        let mut client = CountingStream::new(client, bytes.clone());
+
+        client.write_all(b"hello").await.unwrap();
+        server.write_all(b"notary!").await.unwrap();
+        let mut buf = [0u8; 7];
+        client.read_exact(&mut buf).await.unwrap();
+
+        assert_eq!(&buf, b"notary!");
+        assert_eq!(bytes.load(Ordering::Relaxed), 12);
+    }
+}
@@ -0,0 +1,192 @@
+use async_trait::async_trait;
+use axum::{
+    body::{Bytes, HttpBody},
+    extract::{FromRef, FromRequest},
+    http::{header, HeaderMap, Request},
+    BoxError,
+};
+use eyre::eyre;
+use serde::de::DeserializeOwned;
+use std::{
+    io::{self, Read},
+    ops::Deref,
+    time::{Duration, Instant},
+};
+use tracing::error;
+
+use crate::{domain::notary::NotaryGlobals, error::NotaryServerError};
+
+/// Extractor of a JSON request body, which is checked against the configured JSON limits before
+/// it is deserialized
+#[derive(Debug)]
+pub struct LimitedJson<T>(pub T);
+
+impl<T> Deref for LimitedJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for LimitedJson<T>
+where
+    T: DeserializeOwned + Send + 'static,
+    S: Send + Sync,
+    NotaryGlobals: FromRef<S>,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = NotaryServerError;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_json_content_type(req.headers()) {
+            return Err(NotaryServerError::BadProverRequest(
+                "Expected request with `Content-Type: application/json`".to_string(),
+            ));
+        }
+
+        let notary_globals = NotaryGlobals::from_ref(state);
+        let max_processing_time = notary_globals
+            .public_api_config
+            .is_public(req.uri().path())
+            .then(|| notary_globals.public_api_config.max_processing_time())
+            .flatten();
+
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(|err| NotaryServerError::BadProverRequest(err.to_string()))?;
+
+        notary_globals.json_limits.check(&body)?;
+
+        match max_processing_time {
+            Some(max_processing_time) => deserialize_within(body, max_processing_time).await,
+            None => serde_json::from_slice(&body)
+                .map_err(|err| NotaryServerError::BadProverRequest(err.to_string())),
+        }
+        .map(LimitedJson)
+    }
+}
+
+/// Number of bytes read between two checks of the deadline
+const DEADLINE_CHECK_BYTES: usize = 4096;
+
+/// Deserializes the body on a blocking thread, so that requests to public routes cannot hold up
+/// the runtime, and aborts once the time budget is spent
+async fn deserialize_within<T>(body: Bytes, max_time: Duration) -> Result<T, NotaryServerError>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let deadline = Instant::now() + max_time;
+    let deserialization = tokio::task::spawn_blocking(move || {
+        serde_json::from_reader(DeadlineReader {
+            body: &body,
+            deadline,
+            unchecked: 0,
+        })
+    });
+
+    match tokio::time::timeout(max_time, deserialization).await {
+        Ok(Ok(Ok(value))) => Ok(value),
+        Ok(Ok(Err(err))) if !err.is_io() => {
+            Err(NotaryServerError::BadProverRequest(err.to_string()))
+        }
+        Ok(Err(err)) => Err(eyre!("Deserialization task failed: {err}").into()),
+        // The reader failed or the task was still running at the deadline
+        _ => {
+            error!("Deserialization of a public request exceeded {max_time:?}");
+            Err(NotaryServerError::ProcessingTimeout(max_time))
+        }
+    }
+}
+
+/// Reader of a request body which fails once the deadline has passed, which stops the
+/// deserialization instead of leaving it to run to completion on the blocking thread
+struct DeadlineReader<'a> {
+    body: &'a [u8],
+    deadline: Instant,
+    /// Bytes read since the deadline was last checked
+    unchecked: usize,
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.unchecked == 0 && Instant::now() >= self.deadline {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+
+        let read = self.body.read(buf)?;
+        self.unchecked += read;
+        if self.unchecked >= DEADLINE_CHECK_BYTES {
+            self.unchecked = 0;
+        }
+        Ok(read)
+    }
+}
+
+/// Returns whether the content type is application/json or another JSON media type, e.g.
+/// application/ld+json
+fn is_json_content_type(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    essence == "application/json"
+        || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_json_content_type() {
+        let headers = |content_type: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+            headers
+        };
+
+        assert!(is_json_content_type(&headers("application/json")));
+        assert!(is_json_content_type(&headers(
+            "Application/JSON; charset=utf-8"
+        )));
+        assert!(is_json_content_type(&headers("application/ld+json")));
+        assert!(!is_json_content_type(&headers("text/plain")));
+        assert!(!is_json_content_type(&HeaderMap::new()));
+    }
+
+    #[tokio::test]
+    async fn test_deserialize_within() {
+        let body = Bytes::from(serde_json::to_vec(&vec![42u64; 100_000]).unwrap());
+
+        let values: Vec<u64> = deserialize_within(body.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(values.len(), 100_000);
+
+        let result = deserialize_within::<Vec<u64>>(body, Duration::ZERO).await;
+        assert!(matches!(
+            result,
+            Err(NotaryServerError::ProcessingTimeout(max_time)) if max_time.is_zero()
+        ));
+
+        let result =
+            deserialize_within::<Vec<u64>>(Bytes::from("[1,"), Duration::from_secs(60)).await;
+        assert!(matches!(
+            result,
+            Err(NotaryServerError::BadProverRequest(_))
+        ));
+    }
+}
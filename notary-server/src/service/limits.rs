@@ -0,0 +1,346 @@
+use eyre::eyre;
+use std::{fmt, future::Future, thread, time::Duration};
+use tokio::sync::oneshot;
+use tracing::{debug, Span};
+
+use crate::{domain::notary::SessionLimitMetrics, NotaryServerError};
+
+#[cfg(feature = "alloc-accounting")]
+pub use counting_allocator::CountingAllocator;
+
+/// Interval at which a running notarization is checked against its CPU time and memory budgets
+const CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Budgets of a single notarization, each of which is only enforced if set
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionLimits {
+    /// Maximum wall-clock duration of the notarization
+    pub max_duration: Option<Duration>,
+    /// Maximum CPU time spent on the notarization
+    pub max_cpu_time: Option<Duration>,
+    /// Maximum number of bytes held by the notarization at any check
+    pub max_memory: Option<u64>,
+}
+
+/// Budget of a notarization which was exceeded, other than its duration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExceededLimit {
+    CpuTime(Duration),
+    Memory(u64),
+}
+
+impl ExceededLimit {
+    /// Returns the budget in the unit reported to clients, i.e. milliseconds or bytes
+    pub fn value(&self) -> u64 {
+        match self {
+            Self::CpuTime(max_cpu_time) => max_cpu_time.as_millis() as u64,
+            Self::Memory(max_memory) => *max_memory,
+        }
+    }
+}
+
+impl fmt::Display for ExceededLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CpuTime(max_cpu_time) => write!(f, "CPU time budget of {max_cpu_time:?}"),
+            Self::Memory(max_memory) => write!(f, "memory budget of {max_memory} bytes"),
+        }
+    }
+}
+
+/// Runs a notarization on a dedicated thread, aborting it once it exceeds one of its limits
+///
+/// The verifier is a single future which does not spawn tasks, so the CPU time of the thread and
+/// the memory allocated on it are those of the session. Work handed off to other threads, e.g. the
+/// I/O driver of the server runtime, is not accounted for. The budgets are checked whenever the
+/// notarization yields, at most every [CHECK_INTERVAL], so a step which never yields is only
+/// stopped once it completes
+pub async fn run_limited<F, T>(
+    limits: SessionLimits,
+    metrics: &SessionLimitMetrics,
+    notarization: F,
+) -> Result<T, NotaryServerError>
+where
+    F: Future<Output = Result<T, NotaryServerError>> + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    let span = Span::current();
+    thread::Builder::new()
+        .name("notary-session".to_string())
+        .spawn(move || {
+            let _span = span.enter();
+            let result = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|err| {
+                    NotaryServerError::Unexpected(eyre!(
+                        "Failed to build the session runtime: {err}"
+                    ))
+                })
+                .and_then(|runtime| runtime.block_on(enforce(limits, notarization)));
+            // The receiver is only gone if the connection handler was dropped
+            let _ = sender.send(result);
+        })
+        .map_err(|err| eyre!("Failed to spawn the session thread: {err}"))?;
+
+    let result = receiver
+        .await
+        .map_err(|_| eyre!("Session thread panicked"))?;
+    if let Err(err) = &result {
+        metrics.record(err);
+    }
+
+    result
+}
+
+/// Polls the notarization until it completes or exceeds one of the limits, dropping it in the
+/// latter case which aborts the session and closes the connection to the prover
+async fn enforce<F, T>(limits: SessionLimits, notarization: F) -> Result<T, NotaryServerError>
+where
+    F: Future<Output = Result<T, NotaryServerError>>,
+{
+    let usage = ResourceUsage::start();
+    let notarization = async {
+        match limits.max_duration {
+            Some(max_duration) => tokio::time::timeout(max_duration, notarization)
+                .await
+                .map_err(|_| NotaryServerError::Timeout(max_duration))?,
+            None => notarization.await,
+        }
+    };
+
+    let result = tokio::select! {
+        result = notarization => result,
+        exceeded = watch(&usage, limits) => Err(NotaryServerError::ResourceLimit(exceeded)),
+    };
+    debug!(
+        cpu_time = ?usage.cpu_time(),
+        memory = ?usage.memory(),
+        "Notarization used resources"
+    );
+
+    result
+}
+
+/// Resolves once the usage exceeds the CPU time or memory budget
+async fn watch(usage: &ResourceUsage, limits: SessionLimits) -> ExceededLimit {
+    if limits.max_cpu_time.is_none() && limits.max_memory.is_none() {
+        return std::future::pending().await;
+    }
+
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let (Some(max_cpu_time), Some(cpu_time)) = (limits.max_cpu_time, usage.cpu_time()) {
+            if cpu_time > max_cpu_time {
+                return ExceededLimit::CpuTime(max_cpu_time);
+            }
+        }
+        if let (Some(max_memory), Some(memory)) = (limits.max_memory, usage.memory()) {
+            if memory > max_memory {
+                return ExceededLimit::Memory(max_memory);
+            }
+        }
+    }
+}
+
+/// Resources used by the calling thread since [ResourceUsage::start]
+struct ResourceUsage {
+    cpu_time: Option<Duration>,
+    #[cfg(feature = "alloc-accounting")]
+    allocated: i64,
+}
+
+impl ResourceUsage {
+    fn start() -> Self {
+        Self {
+            cpu_time: thread_cpu_time(),
+            #[cfg(feature = "alloc-accounting")]
+            allocated: counting_allocator::thread_allocated_bytes(),
+        }
+    }
+
+    /// Returns the CPU time spent since the start, if the platform has a thread CPU clock
+    fn cpu_time(&self) -> Option<Duration> {
+        Some(thread_cpu_time()?.saturating_sub(self.cpu_time?))
+    }
+
+    /// Returns the number of bytes allocated and not yet freed since the start, if the counting
+    /// allocator is enabled
+    fn memory(&self) -> Option<u64> {
+        #[cfg(feature = "alloc-accounting")]
+        return Some((counting_allocator::thread_allocated_bytes() - self.allocated).max(0) as u64);
+        #[cfg(not(feature = "alloc-accounting"))]
+        return None;
+    }
+}
+
+/// Returns the CPU time consumed by the calling thread
+#[cfg(unix)]
+fn thread_cpu_time() -> Option<Duration> {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: the pointer is valid for writes of a timespec for the duration of the call
+    let result = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) };
+    (result == 0).then(|| Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+}
+
+#[cfg(not(unix))]
+fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+#[cfg(feature = "alloc-accounting")]
+mod counting_allocator {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+    };
+
+    thread_local! {
+        /// Bytes allocated minus bytes freed by the current thread
+        static ALLOCATED: Cell<i64> = const { Cell::new(0) };
+    }
+
+    /// Global allocator which counts the bytes held by each thread, which must be registered with
+    /// `#[global_allocator]` to enforce the memory budget of notarizations. Memory freed by
+    /// another thread than the one which allocated it is subtracted from the freeing thread
+    pub struct CountingAllocator;
+
+    fn add(bytes: i64) {
+        // The counter is unavailable while the thread is being torn down
+        let _ = ALLOCATED.try_with(|allocated| allocated.set(allocated.get() + bytes));
+    }
+
+    pub(super) fn thread_allocated_bytes() -> i64 {
+        ALLOCATED.try_with(Cell::get).unwrap_or_default()
+    }
+
+    // SAFETY: all calls are forwarded to the system allocator, the counter does not allocate
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                add(layout.size() as i64);
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                add(layout.size() as i64);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            add(-(layout.size() as i64));
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                add(new_size as i64 - layout.size() as i64);
+            }
+            new_ptr
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Instant;
+
+    #[cfg(feature = "alloc-accounting")]
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// Notarization which keeps the CPU busy for the duration, yielding in between
+    async fn busy(duration: Duration) -> Result<(), NotaryServerError> {
+        let start = Instant::now();
+        while start.elapsed() < duration {
+            std::hint::black_box((0..10_000u64).sum::<u64>());
+            tokio::task::yield_now().await;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_within_limits() {
+        let metrics = SessionLimitMetrics::default();
+        let limits = SessionLimits {
+            max_duration: Some(Duration::from_secs(60)),
+            max_cpu_time: Some(Duration::from_secs(60)),
+            max_memory: Some(1 << 30),
+        };
+
+        assert!(
+            run_limited(limits, &metrics, busy(Duration::from_millis(50)))
+                .await
+                .is_ok()
+        );
+        assert_eq!(metrics.stats(), Default::default());
+    }
+
+    #[tokio::test]
+    async fn test_duration_limit() {
+        let metrics = SessionLimitMetrics::default();
+        let limits = SessionLimits {
+            max_duration: Some(Duration::from_millis(50)),
+            ..Default::default()
+        };
+
+        let result = run_limited(limits, &metrics, std::future::pending::<Result<(), _>>()).await;
+
+        assert!(matches!(result, Err(NotaryServerError::Timeout(_))));
+        assert_eq!(metrics.stats().timed_out, 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cpu_time_limit() {
+        let metrics = SessionLimitMetrics::default();
+        let max_cpu_time = Duration::from_millis(200);
+        let limits = SessionLimits {
+            max_cpu_time: Some(max_cpu_time),
+            ..Default::default()
+        };
+
+        // The session is aborted well before it would finish on its own
+        let result = run_limited(limits, &metrics, busy(Duration::from_secs(60))).await;
+
+        assert!(matches!(
+            result,
+            Err(NotaryServerError::ResourceLimit(ExceededLimit::CpuTime(max))) if max == max_cpu_time
+        ));
+        assert_eq!(metrics.stats().cpu_time_exceeded, 1);
+    }
+
+    #[cfg(feature = "alloc-accounting")]
+    #[tokio::test]
+    async fn test_memory_limit() {
+        let metrics = SessionLimitMetrics::default();
+        let limits = SessionLimits {
+            max_memory: Some(1 << 20),
+            ..Default::default()
+        };
+
+        let result = run_limited(limits, &metrics, async {
+            let _buffer = std::hint::black_box(vec![1u8; 1 << 24]);
+            std::future::pending::<Result<(), _>>().await
+        })
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(NotaryServerError::ResourceLimit(ExceededLimit::Memory(_)))
+        ));
+        assert_eq!(metrics.stats().memory_exceeded, 1);
+    }
+}
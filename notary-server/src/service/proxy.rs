@@ -0,0 +1,289 @@
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{lookup_host, TcpStream},
+};
+use tracing::{error, info};
+use ws_stream_tungstenite::WsStream;
+
+use crate::{
+    config::DEFAULT_PROXY_PORT,
+    domain::notary::{NotaryGlobals, ProxyMetrics, ProxyRequestQuery},
+    service::axum_websocket::{WebSocket, WebSocketUpgrade},
+    NotaryServerError,
+};
+
+/// Size of the buffer used to copy data in each direction
+const BUFFER_SIZE: usize = 8192;
+
+/// Handler to proxy a WebSocket connection from a browser prover to a TCP connection to the target server
+pub async fn proxy(
+    ws: WebSocketUpgrade,
+    State(notary_globals): State<NotaryGlobals>,
+    Query(params): Query<ProxyRequestQuery>,
+) -> Response {
+    let config = &notary_globals.proxy_config;
+    if !config.enabled {
+        return NotaryServerError::BadProverRequest("Proxy is not enabled".to_string())
+            .into_response();
+    }
+
+    // Browsers can't send the API key when opening a websocket, so the connection is authorized by
+    // a session id instead, which can only be obtained from the authorized /session endpoint
+    if !notary_globals.store.contains(&params.session_id) {
+        let err_msg = format!("Session id {} does not exist", params.session_id);
+        error!(err_msg);
+        return NotaryServerError::UnauthorizedProverRequest(err_msg).into_response();
+    }
+
+    let metrics = notary_globals.proxy_metrics.clone();
+    let port = params.port.unwrap_or(DEFAULT_PROXY_PORT);
+    if !config.is_allowed(&params.host, port) {
+        metrics.rejected_connections.fetch_add(1, Ordering::Relaxed);
+        let err_msg = format!(
+            "Target {}:{port} is not allowed to be proxied to",
+            params.host
+        );
+        error!(err_msg);
+        return NotaryServerError::BadProverRequest(err_msg).into_response();
+    }
+
+    // Reserve a connection slot before upgrading, so that the limit also covers connections which
+    // are still being upgraded
+    let Some(slot) = ConnectionSlot::reserve(metrics, config.max_connections) else {
+        error!("Maximum number of proxied connections reached");
+        return NotaryServerError::Unavailable(
+            "Maximum number of proxied connections reached".to_string(),
+        )
+        .into_response();
+    };
+
+    let max_bytes_per_second = config.max_bytes_per_second;
+    let pinned_address = config.pinned_addresses.get(&params.host).copied();
+    // The slot is moved into the callback, so it is also released if the upgrade fails and the
+    // callback is dropped without being called
+    ws.on_upgrade(move |socket| async move {
+        slot.metrics
+            .accepted_connections
+            .fetch_add(1, Ordering::Relaxed);
+        proxy_connection(
+            socket,
+            params.host,
+            port,
+            pinned_address,
+            max_bytes_per_second,
+            &slot.metrics,
+        )
+        .await;
+    })
+}
+
+/// A reserved slot of the proxied connection limit, which is released when dropped
+#[derive(Debug)]
+struct ConnectionSlot {
+    metrics: Arc<ProxyMetrics>,
+}
+
+impl ConnectionSlot {
+    /// Reserves a slot unless `max_connections` connections are open already
+    fn reserve(metrics: Arc<ProxyMetrics>, max_connections: usize) -> Option<Self> {
+        let reserved = metrics
+            .open_connections
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                (count < max_connections).then_some(count + 1)
+            })
+            .is_ok();
+        if !reserved {
+            metrics.rejected_connections.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        Some(Self { metrics })
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.metrics
+            .open_connections
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Copies data between the WebSocket and the target server until either side closes the connection
+async fn proxy_connection(
+    socket: WebSocket,
+    host: String,
+    port: u16,
+    pinned_address: Option<IpAddr>,
+    max_bytes_per_second: Option<u64>,
+    metrics: &ProxyMetrics,
+) {
+    let address = match resolve(&host, port, pinned_address).await {
+        Ok(address) => address,
+        Err(err) => {
+            metrics.failed_connections.fetch_add(1, Ordering::Relaxed);
+            error!(?host, port, "Failed to resolve proxy target: {err}");
+            return;
+        }
+    };
+    let target = match TcpStream::connect(address).await {
+        Ok(target) => target,
+        Err(err) => {
+            metrics.failed_connections.fetch_add(1, Ordering::Relaxed);
+            error!(?host, %address, "Failed to connect to proxy target: {err}");
+            return;
+        }
+    };
+    // The resolved address is logged so that the server a prover talked to can be audited later
+    info!(?host, %address, "Proxying websocket connection");
+
+    // Wrap the websocket in WsStream so that we have AsyncRead and AsyncWrite implemented
+    let (ws_read, ws_write) = tokio::io::split(WsStream::new(socket.into_inner()));
+    let (target_read, target_write) = target.into_split();
+
+    match tokio::try_join!(
+        copy_with_limit(
+            ws_read,
+            target_write,
+            max_bytes_per_second,
+            &metrics.bytes_sent
+        ),
+        copy_with_limit(
+            target_read,
+            ws_write,
+            max_bytes_per_second,
+            &metrics.bytes_received
+        ),
+    ) {
+        Ok((sent, received)) => {
+            info!(?host, port, sent, received, "Proxied connection closed");
+        }
+        Err(err) => {
+            metrics.failed_connections.fetch_add(1, Ordering::Relaxed);
+            error!(?host, port, "Proxied connection failed: {err}");
+        }
+    }
+}
+
+/// Returns the address to connect to for the target server, using the pinned address of the host
+/// if there is one
+async fn resolve(
+    host: &str,
+    port: u16,
+    pinned_address: Option<IpAddr>,
+) -> std::io::Result<SocketAddr> {
+    if let Some(address) = pinned_address {
+        return Ok(SocketAddr::new(address, port));
+    }
+    lookup_host((host, port)).await?.next().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No address found for {host}"),
+        )
+    })
+}
+
+/// Copies data from `reader` to `writer` until EOF, returning the number of bytes copied. The
+/// copied bytes are also added to `copied` as they are written, so that they are counted even if
+/// the copy fails.
+///
+/// Data is only read once the previous chunk has been written, so a slow receiver slows down the
+/// sender instead of filling up buffers. If a limit is set, the copy is paused whenever it gets
+/// ahead of the allowed bandwidth.
+async fn copy_with_limit<R, W>(
+    mut reader: R,
+    mut writer: W,
+    max_bytes_per_second: Option<u64>,
+    copied: &AtomicU64,
+) -> std::io::Result<u64>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = vec![0u8; BUFFER_SIZE];
+    let start = Instant::now();
+    let mut total = 0u64;
+
+    loop {
+        let read = reader.read(&mut buf).await?;
+        if read == 0 {
+            writer.shutdown().await?;
+            return Ok(total);
+        }
+        writer.write_all(&buf[..read]).await?;
+        total += read as u64;
+        copied.fetch_add(read as u64, Ordering::Relaxed);
+
+        if let Some(max_bytes_per_second) = max_bytes_per_second {
+            let expected = Duration::from_secs_f64(total as f64 / max_bytes_per_second as f64);
+            let elapsed = start.elapsed();
+            if expected > elapsed {
+                tokio::time::sleep(expected - elapsed).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_pinned_address() {
+        let pinned = IpAddr::from([127, 0, 0, 1]);
+        let address = resolve("tlsnotary.org", 8443, Some(pinned)).await.unwrap();
+        assert_eq!(address, SocketAddr::new(pinned, 8443));
+    }
+
+    #[test]
+    fn test_connection_slot() {
+        let metrics = Arc::new(ProxyMetrics::default());
+
+        let slot = ConnectionSlot::reserve(metrics.clone(), 1).unwrap();
+        assert_eq!(metrics.open_connections.load(Ordering::Relaxed), 1);
+        assert!(ConnectionSlot::reserve(metrics.clone(), 1).is_none());
+        assert_eq!(metrics.rejected_connections.load(Ordering::Relaxed), 1);
+
+        // A slot whose upgrade callback is never called is released when the callback is dropped
+        let callback = move || drop(slot);
+        drop(callback);
+        assert_eq!(metrics.open_connections.load(Ordering::Relaxed), 0);
+        assert!(ConnectionSlot::reserve(metrics.clone(), 1).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_copy_with_limit() {
+        let data = vec![1u8; 3 * BUFFER_SIZE];
+        let mut output = Vec::new();
+
+        let counter = AtomicU64::default();
+
+        let start = Instant::now();
+        let copied = copy_with_limit(
+            data.as_slice(),
+            &mut output,
+            Some(BUFFER_SIZE as u64 * 10),
+            &counter,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(copied, data.len() as u64);
+        assert_eq!(counter.load(Ordering::Relaxed), copied);
+        assert_eq!(output, data);
+        // 3 chunks at 10 chunks per second
+        assert!(start.elapsed() >= Duration::from_millis(300));
+    }
+}
@@ -9,7 +9,15 @@ use hyper::upgrade::{OnUpgrade, Upgraded};
 use std::future::Future;
 use tracing::{debug, error, info};
 
-use crate::{domain::notary::NotaryGlobals, service::notary_service, NotaryServerError};
+use crate::{
+    domain::{
+        notary::{ByteCount, NotaryGlobals},
+        session::{SessionHandle, SessionState},
+    },
+    service::notary_service,
+    signer::NotarySigner,
+    NotaryServerError,
+};
 
 /// Custom extractor used to extract underlying TCP connection for TCP client — using the same upgrade primitives used by
 /// the WebSocket implementation where the underlying TCP connection (wrapped in an Upgraded object) only gets polled as an OnUpgrade future
@@ -79,14 +87,22 @@ impl TcpUpgrade {
 pub async fn tcp_notarize(
     stream: Upgraded,
     notary_globals: NotaryGlobals,
-    session_id: String,
-    max_sent_data: Option<usize>,
-    max_recv_data: Option<usize>,
+    mut session: SessionHandle,
+    signer: NotarySigner,
+    max_sent_data: Option<ByteCount>,
+    max_recv_data: Option<ByteCount>,
 ) {
+    let session_id = session.id().to_string();
     debug!(?session_id, "Upgraded to tcp connection");
+    let _session_guard = notary_globals.track_session();
+    if let Err(err) = session.advance(SessionState::Notarizing) {
+        error!(?session_id, "Failed to start notarization: {err}");
+        return;
+    }
     match notary_service(
         stream,
-        &notary_globals.notary_signing_key,
+        &notary_globals,
+        &signer,
         &session_id,
         max_sent_data,
         max_recv_data,
@@ -95,9 +111,11 @@ pub async fn tcp_notarize(
     {
         Ok(_) => {
             info!(?session_id, "Successful notarization using tcp!");
+            let _ = session.advance(SessionState::Signed);
         }
         Err(err) => {
             error!(?session_id, "Failed notarization using tcp: {err}");
+            let _ = session.advance(SessionState::Failed);
         }
     }
 }
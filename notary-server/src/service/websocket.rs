@@ -2,24 +2,36 @@ use tracing::{debug, error, info};
 use ws_stream_tungstenite::WsStream;
 
 use crate::{
-    domain::notary::NotaryGlobals,
+    domain::{
+        notary::{ByteCount, NotaryGlobals},
+        session::{SessionHandle, SessionState},
+    },
     service::{axum_websocket::WebSocket, notary_service},
+    signer::NotarySigner,
 };
 
 /// Perform notarization using the established websocket connection
 pub async fn websocket_notarize(
     socket: WebSocket,
     notary_globals: NotaryGlobals,
-    session_id: String,
-    max_sent_data: Option<usize>,
-    max_recv_data: Option<usize>,
+    mut session: SessionHandle,
+    signer: NotarySigner,
+    max_sent_data: Option<ByteCount>,
+    max_recv_data: Option<ByteCount>,
 ) {
+    let session_id = session.id().to_string();
     debug!(?session_id, "Upgraded to websocket connection");
+    let _session_guard = notary_globals.track_session();
+    if let Err(err) = session.advance(SessionState::Notarizing) {
+        error!(?session_id, "Failed to start notarization: {err}");
+        return;
+    }
     // Wrap the websocket in WsStream so that we have AsyncRead and AsyncWrite implemented
     let stream = WsStream::new(socket.into_inner());
     match notary_service(
         stream,
-        &notary_globals.notary_signing_key,
+        &notary_globals,
+        &signer,
         &session_id,
         max_sent_data,
         max_recv_data,
@@ -28,9 +40,11 @@ pub async fn websocket_notarize(
     {
         Ok(_) => {
             info!(?session_id, "Successful notarization using websocket!");
+            let _ = session.advance(SessionState::Signed);
         }
         Err(err) => {
             error!(?session_id, "Failed notarization using websocket: {err}");
+            let _ = session.advance(SessionState::Failed);
         }
     }
 }
@@ -0,0 +1,152 @@
+use eyre::{ensure, eyre, Result};
+use notary_api_types::SignatureAlgorithm;
+use p256::pkcs8::DecodePrivateKey;
+use tlsn_core::SessionHeader;
+use tlsn_verifier::tls::{state::Initialized, Verifier, VerifierError};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+/// Key used by the notary to sign session headers
+#[derive(Clone, Debug)]
+pub enum NotarySigner {
+    P256(p256::ecdsa::SigningKey),
+    K256(k256::ecdsa::SigningKey),
+}
+
+impl NotarySigner {
+    /// Loads a private key from a PKCS#8 PEM file, the algorithm is given by the curve of the key
+    pub fn read_pkcs8_pem_file(path: &str) -> Result<Self> {
+        if let Ok(key) = p256::ecdsa::SigningKey::read_pkcs8_pem_file(path) {
+            return Ok(Self::P256(key));
+        }
+
+        k256::ecdsa::SigningKey::read_pkcs8_pem_file(path)
+            .map(Self::K256)
+            .map_err(|err| eyre!("Neither a P-256 nor a secp256k1 key in {path}: {err}"))
+    }
+
+    /// Returns the algorithm of the signatures made with this key
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        match self {
+            Self::P256(_) => SignatureAlgorithm::P256,
+            Self::K256(_) => SignatureAlgorithm::K256,
+        }
+    }
+
+    /// Returns the algorithm of the signatures made with this key, as announced to the prover
+    /// during the protocol handshake
+    pub fn protocol_algorithm(&self) -> tlsn_core::SignatureAlgorithm {
+        match self {
+            Self::P256(_) => tlsn_core::SignatureAlgorithm::P256,
+            Self::K256(_) => tlsn_core::SignatureAlgorithm::K256,
+        }
+    }
+
+    /// Runs the notarization with the prover connected to the socket, signing the session header
+    /// with this key
+    pub async fn notarize<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
+        &self,
+        verifier: Verifier<Initialized>,
+        socket: T,
+    ) -> Result<SessionHeader, VerifierError> {
+        match self {
+            Self::P256(key) => {
+                verifier
+                    .notarize::<_, p256::ecdsa::Signature>(socket.compat(), key)
+                    .await
+            }
+            Self::K256(key) => {
+                verifier
+                    .notarize::<_, k256::ecdsa::Signature>(socket.compat(), key)
+                    .await
+            }
+        }
+    }
+}
+
+/// Signing keys of the notary, at most one per signature algorithm
+#[derive(Clone, Debug)]
+pub struct NotarySigners {
+    /// The default signer comes first
+    signers: Vec<NotarySigner>,
+}
+
+impl NotarySigners {
+    /// Creates the signers from the default key and keys on other curves
+    pub fn new(default: NotarySigner, additional: Vec<NotarySigner>) -> Result<Self> {
+        let mut signers = vec![default];
+        for signer in additional {
+            ensure!(
+                signers.iter().all(|s| s.algorithm() != signer.algorithm()),
+                "Multiple notary signing keys use the {:?} algorithm",
+                signer.algorithm()
+            );
+            signers.push(signer);
+        }
+
+        Ok(Self { signers })
+    }
+
+    /// Returns the signer used when the prover does not request an algorithm
+    pub fn default_signer(&self) -> &NotarySigner {
+        &self.signers[0]
+    }
+
+    /// Returns the signer of the algorithm, if the notary supports it
+    pub fn get(&self, algorithm: SignatureAlgorithm) -> Option<&NotarySigner> {
+        self.signers
+            .iter()
+            .find(|signer| signer.algorithm() == algorithm)
+    }
+
+    /// Returns the supported algorithms, starting with the default one
+    pub fn algorithms(&self) -> Vec<SignatureAlgorithm> {
+        self.signers.iter().map(NotarySigner::algorithm).collect()
+    }
+}
+
+impl From<NotarySigner> for NotarySigners {
+    fn from(signer: NotarySigner) -> Self {
+        Self {
+            signers: vec![signer],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_pkcs8_pem_file() {
+        let signer = NotarySigner::read_pkcs8_pem_file("./fixture/notary/notary.key").unwrap();
+        assert_eq!(signer.algorithm(), SignatureAlgorithm::P256);
+
+        let signer = NotarySigner::read_pkcs8_pem_file("./fixture/notary/notary_k256.key").unwrap();
+        assert_eq!(signer.algorithm(), SignatureAlgorithm::K256);
+
+        assert!(NotarySigner::read_pkcs8_pem_file("./fixture/notary/notary.pub").is_err());
+    }
+
+    #[test]
+    fn test_signers() {
+        let p256 = NotarySigner::read_pkcs8_pem_file("./fixture/notary/notary.key").unwrap();
+        let k256 = NotarySigner::read_pkcs8_pem_file("./fixture/notary/notary_k256.key").unwrap();
+
+        let signers = NotarySigners::new(k256.clone(), vec![p256.clone()]).unwrap();
+        assert_eq!(
+            signers.default_signer().algorithm(),
+            SignatureAlgorithm::K256
+        );
+        assert_eq!(
+            signers.algorithms(),
+            vec![SignatureAlgorithm::K256, SignatureAlgorithm::P256]
+        );
+        assert!(signers.get(SignatureAlgorithm::P256).is_some());
+
+        let signers = NotarySigners::from(p256.clone());
+        assert!(signers.get(SignatureAlgorithm::K256).is_none());
+
+        assert!(NotarySigners::new(p256.clone(), vec![p256]).is_err());
+    }
+}
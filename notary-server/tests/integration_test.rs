@@ -27,15 +27,19 @@ use tracing::debug;
 use ws_stream_tungstenite::WsStream;
 
 use notary_server::{
-    read_pem_file, run_server, AuthorizationProperties, LoggingProperties, NotarizationProperties,
+    read_pem_file, run_server, AuthorizationProperties, ByteCount, ClockProperties,
+    InspectProofResponse, JsonLimitsProperties, LoadResponse, LoggingProperties,
+    NotarizationEstimateRequest, NotarizationEstimateResponse, NotarizationProperties,
     NotarizationSessionRequest, NotarizationSessionResponse, NotaryServerProperties,
-    NotarySigningKeyProperties, ServerProperties, TLSProperties,
+    NotarySigningKeyProperties, ProxyProperties, PublicApiProperties, ServerProperties,
+    SignatureAlgorithm, TLSProperties,
 };
+use tlsn_core::{commitment::CommitmentKind, proof::TlsProof};
 
 const NOTARY_CA_CERT_PATH: &str = "./fixture/tls/rootCA.crt";
 const NOTARY_CA_CERT_BYTES: &[u8] = include_bytes!("../fixture/tls/rootCA.crt");
-const MAX_SENT: usize = 1 << 13;
-const MAX_RECV: usize = 1 << 13;
+const MAX_SENT: ByteCount = ByteCount::new(1 << 13);
+const MAX_RECV: ByteCount = ByteCount::new(1 << 13);
 
 fn get_server_config(port: u16, tls_enabled: bool) -> NotaryServerProperties {
     NotaryServerProperties {
@@ -47,6 +51,11 @@ fn get_server_config(port: u16, tls_enabled: bool) -> NotaryServerProperties {
         },
         notarization: NotarizationProperties {
             max_transcript_size: 1 << 14,
+            max_concurrent_sessions: None,
+            max_session_duration_secs: None,
+            max_session_cpu_ms: None,
+            max_session_memory_bytes: None,
+            session_ttl_secs: None,
         },
         tls: TLSProperties {
             enabled: tls_enabled,
@@ -57,14 +66,24 @@ fn get_server_config(port: u16, tls_enabled: bool) -> NotaryServerProperties {
             private_key_pem_path: "./fixture/notary/notary.key".to_string(),
             public_key_pem_path: "./fixture/notary/notary.pub".to_string(),
         },
+        additional_notary_keys: vec![NotarySigningKeyProperties {
+            private_key_pem_path: "./fixture/notary/notary_k256.key".to_string(),
+            public_key_pem_path: "./fixture/notary/notary_k256.pub".to_string(),
+        }],
         logging: LoggingProperties {
             level: "DEBUG".to_string(),
             filter: None,
+            sample_ratio: None,
+            redacted_fields: vec![],
         },
         authorization: AuthorizationProperties {
             enabled: false,
             whitelist_csv_path: "./fixture/auth/whitelist.csv".to_string(),
         },
+        proxy: ProxyProperties::default(),
+        public_api: PublicApiProperties::default(),
+        clock: ClockProperties::default(),
+        json_limits: JsonLimitsProperties::default(),
     }
 }
 
@@ -134,6 +153,60 @@ async fn tls_socket(notary_config: NotaryServerProperties) -> TlsStream<TcpStrea
         .unwrap()
 }
 
+/// Sends a request over a new connection to the notary, returning the status and the body of the
+/// response
+async fn send_request(
+    notary_config: &NotaryServerProperties,
+    request: Request<Body>,
+) -> (StatusCode, Vec<u8>) {
+    async fn send<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
+        socket: S,
+        request: Request<Body>,
+    ) -> (StatusCode, Vec<u8>) {
+        let (mut request_sender, connection) =
+            hyper::client::conn::handshake(socket).await.unwrap();
+        tokio::spawn(connection);
+
+        let response = request_sender.send_request(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body()).await.unwrap().to_vec();
+
+        (status, body)
+    }
+
+    if notary_config.tls.enabled {
+        send(tls_socket(notary_config.clone()).await, request).await
+    } else {
+        send(tcp_socket(notary_config.clone()).await, request).await
+    }
+}
+
+/// Builds a request to an API of the notary, with a JSON body if a payload is given
+fn api_request(
+    notary_config: &NotaryServerProperties,
+    method: &str,
+    path: &str,
+    payload: Option<String>,
+) -> Request<Body> {
+    let scheme = if notary_config.tls.enabled {
+        "https"
+    } else {
+        "http"
+    };
+    let host = &notary_config.server.host;
+
+    Request::builder()
+        .uri(format!(
+            "{scheme}://{host}:{}{path}",
+            notary_config.server.port
+        ))
+        .method(method)
+        .header("Host", host)
+        .header("Content-Type", "application/json")
+        .body(payload.map_or_else(Body::empty, Body::from))
+        .unwrap()
+}
+
 #[rstest]
 #[case::with_tls(
     setup_config_and_server(100, 7048, true),
@@ -153,7 +226,7 @@ async fn test_tcp_prover<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
     #[case]
     notary_socket: S,
 ) {
-    let notary_host = notary_config.server.host;
+    let notary_host = notary_config.server.host.clone();
     let notary_port = notary_config.server.port;
     let http_scheme = if notary_config.tls.enabled {
         "https"
@@ -173,6 +246,7 @@ async fn test_tcp_prover<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
         client_type: notary_server::ClientType::Tcp,
         max_sent_data: Some(MAX_SENT),
         max_recv_data: Some(MAX_RECV),
+        signature_algorithm: None,
     })
     .unwrap();
     let request = Request::builder()
@@ -310,9 +384,136 @@ async fn test_tcp_prover<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
     builder.commit_sent(&(0..sent_len)).unwrap();
     builder.commit_recv(&(0..recv_len)).unwrap();
 
-    _ = prover.finalize().await.unwrap();
+    let notarized_session = prover.finalize().await.unwrap();
 
     debug!("Done notarization!");
+
+    // Summarize a proof of the session which only reveals the sent data
+    let mut substrings_builder = notarized_session.data().build_substrings_proof();
+    substrings_builder
+        .reveal_sent(&(0..sent_len), CommitmentKind::Blake3)
+        .unwrap();
+    let proof = TlsProof {
+        session: notarized_session.session_proof(),
+        substrings: substrings_builder.build().unwrap(),
+    };
+
+    let (status, payload) = send_request(
+        &notary_config,
+        api_request(
+            &notary_config,
+            "POST",
+            "/inspect-proof",
+            Some(serde_json::to_string(&proof).unwrap()),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let summary = serde_json::from_slice::<InspectProofResponse>(&payload).unwrap();
+    assert_eq!(summary.server_name, SERVER_DOMAIN);
+    assert!(summary.signed);
+    assert_eq!(summary.sent.length, sent_len);
+    assert_eq!(summary.sent.revealed_bytes, sent_len);
+    assert_eq!(summary.sent.redacted_bytes, 0);
+    assert_eq!(summary.received.length, recv_len);
+    assert_eq!(summary.received.revealed_bytes, 0);
+    assert_eq!(summary.received.redacted_bytes, recv_len);
+
+    // A malformed proof is rejected
+    let (status, _) = send_request(
+        &notary_config,
+        api_request(
+            &notary_config,
+            "POST",
+            "/inspect-proof",
+            Some("{}".to_string()),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_estimate_and_load() {
+    let notary_config = setup_config_and_server(100, 7051, false).await;
+
+    let estimate = |max_sent_data, max_recv_data| {
+        send_request(
+            &notary_config,
+            api_request(
+                &notary_config,
+                "POST",
+                "/estimate",
+                Some(
+                    serde_json::to_string(&NotarizationEstimateRequest {
+                        max_sent_data,
+                        max_recv_data,
+                    })
+                    .unwrap(),
+                ),
+            ),
+        )
+    };
+    let load = || {
+        send_request(
+            &notary_config,
+            api_request(&notary_config, "GET", "/load", None),
+        )
+    };
+
+    let (status, payload) = estimate(Some(ByteCount::new(1024)), Some(ByteCount::new(2048))).await;
+    assert_eq!(status, StatusCode::OK);
+    let response = serde_json::from_slice::<NotarizationEstimateResponse>(&payload).unwrap();
+    assert_eq!(response.max_sent_data, ByteCount::new(1024));
+    assert_eq!(response.max_recv_data, ByteCount::new(2048));
+    assert!(response.ot_count > 0);
+    // Bandwidth and wall time are only estimated once a notarization has been observed
+    assert_eq!(response.estimated_bandwidth, None);
+    assert_eq!(response.expected_wall_time_ms, None);
+    assert_eq!(response.pending_sessions, 0);
+    assert_eq!(
+        response.max_transcript_size,
+        ByteCount::new(notary_config.notarization.max_transcript_size)
+    );
+
+    // Limits which exceed the maximum transcript size, or overflow when added up, are rejected
+    let (status, _) = estimate(Some(MAX_SENT), Some(ByteCount::new(1 << 14))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = estimate(Some(ByteCount::new(usize::MAX)), Some(ByteCount::new(1))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Initialized sessions are pending in /estimate and queued in /load
+    let (status, _) = send_request(
+        &notary_config,
+        api_request(
+            &notary_config,
+            "POST",
+            "/session",
+            Some(
+                serde_json::to_string(&NotarizationSessionRequest {
+                    client_type: notary_server::ClientType::Tcp,
+                    max_sent_data: Some(MAX_SENT),
+                    max_recv_data: Some(MAX_RECV),
+                    signature_algorithm: None,
+                })
+                .unwrap(),
+            ),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, payload) = estimate(Some(ByteCount::new(1024)), Some(ByteCount::new(2048))).await;
+    let response = serde_json::from_slice::<NotarizationEstimateResponse>(&payload).unwrap();
+    assert_eq!(response.pending_sessions, 1);
+
+    let (status, payload) = load().await;
+    assert_eq!(status, StatusCode::OK);
+    let response = serde_json::from_slice::<LoadResponse>(&payload).unwrap();
+    assert_eq!(response.active_sessions, 0);
+    assert_eq!(response.queued_sessions, 1);
+    assert!(response.accepting);
 }
 
 #[tokio::test]
@@ -348,6 +549,7 @@ async fn test_websocket_prover() {
         client_type: notary_server::ClientType::Websocket,
         max_sent_data: Some(MAX_SENT),
         max_recv_data: Some(MAX_RECV),
+        signature_algorithm: Some(SignatureAlgorithm::K256),
     })
     .unwrap();
 
@@ -378,6 +580,11 @@ async fn test_websocket_prover() {
 
     debug!("Notarization response: {:?}", notarization_response,);
 
+    assert_eq!(
+        notarization_response.signature_algorithm,
+        Some(SignatureAlgorithm::K256)
+    );
+
     // Connect to the Notary via TLS-Websocket
     //
     // Note: This will establish a new TLS-TCP connection instead of reusing the previous TCP connection
@@ -485,7 +692,13 @@ async fn test_websocket_prover() {
     builder.commit_sent(&(0..sent_len)).unwrap();
     builder.commit_recv(&(0..recv_len)).unwrap();
 
-    _ = prover.finalize().await.unwrap();
+    let notarized_session = prover.finalize().await.unwrap();
+
+    // The session header is signed with the key requested in the /session call
+    assert_eq!(
+        notarized_session.header().signature_algorithm(),
+        tlsn_core::SignatureAlgorithm::K256
+    );
 
     debug!("Done notarization!");
 }
@@ -23,7 +23,7 @@ use tokio_util::{
     io::{InspectReader, InspectWriter},
 };
 
-use tlsn_prover::tls::{Prover, ProverConfig};
+use tlsn_prover::tls::{ByteCount, Prover, ProverConfig};
 use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
 
 #[tokio::main]
@@ -120,8 +120,8 @@ async fn run_instance<S: AsyncWrite + AsyncRead + Send + Sync + Unpin + 'static>
             .id("test")
             .server_dns(SERVER_DOMAIN)
             .root_cert_store(root_store())
-            .max_sent_data(upload_size + 256)
-            .max_recv_data(download_size + 256)
+            .max_sent_data(ByteCount::new(upload_size + 256))
+            .max_recv_data(ByteCount::new(download_size + 256))
             .build()
             .context("invalid prover config")?,
     )
@@ -8,7 +8,7 @@ use tlsn_server_fixture::CA_CERT_DER;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_util::compat::TokioAsyncReadCompatExt;
 
-use tlsn_verifier::tls::{Verifier, VerifierConfig};
+use tlsn_verifier::tls::{ByteCount, Verifier, VerifierConfig};
 use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
 
 #[tokio::main]
@@ -67,8 +67,8 @@ async fn run_instance<S: AsyncWrite + AsyncRead + Send + Sync + Unpin + 'static>
         VerifierConfig::builder()
             .id("test")
             .cert_verifier(cert_verifier())
-            .max_sent_data(upload_size + 256)
-            .max_recv_data(download_size + 256)
+            .max_sent_data(ByteCount::new(upload_size + 256))
+            .max_recv_data(ByteCount::new(download_size + 256))
             .build()?,
     );
 
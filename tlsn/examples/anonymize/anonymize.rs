@@ -0,0 +1,31 @@
+use std::env;
+
+use tlsn_core::{
+    anonymize::{anonymize, RedactionConfig},
+    fixtures::{encoder_seed, notary_signing_key},
+    NotarizedSession,
+};
+
+/// Produces a sanitized reproduction of a notarized session which can be attached to bug reports.
+///
+/// Usage: `anonymize <notarized_session.json> <redaction.json> <output.json>`
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let [session_path, config_path, output_path] = args.as_slice() else {
+        eprintln!("Usage: anonymize <notarized_session.json> <redaction.json> <output.json>");
+        std::process::exit(1);
+    };
+
+    let session = std::fs::read_to_string(session_path).unwrap();
+    let session: NotarizedSession = serde_json::from_str(&session).unwrap();
+
+    let config = std::fs::read_to_string(config_path).unwrap();
+    let config: RedactionConfig = serde_json::from_str(&config).unwrap();
+
+    let bundle = anonymize(&session, &config, encoder_seed(), &notary_signing_key()).unwrap();
+
+    std::fs::write(output_path, serde_json::to_string_pretty(&bundle).unwrap()).unwrap();
+
+    println!("The sanitized session has been written to `{output_path}`");
+    println!("Proofs built from it verify against the test notary key in the bundle only");
+}
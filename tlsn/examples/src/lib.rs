@@ -5,7 +5,9 @@ use futures::{AsyncRead, AsyncWrite};
 use http_body_util::{BodyExt as _, Either, Empty, Full};
 use hyper::{client::conn::http1::Parts, Request, StatusCode};
 use hyper_util::rt::TokioIo;
-use notary_server::{ClientType, NotarizationSessionRequest, NotarizationSessionResponse};
+use notary_api_types::{
+    ByteCount, ClientType, NotarizationSessionRequest, NotarizationSessionResponse,
+};
 use rustls::{Certificate, ClientConfig, RootCertStore};
 use tlsn_verifier::tls::{Verifier, VerifierConfig};
 use tokio::net::TcpStream;
@@ -36,8 +38,8 @@ pub async fn run_notary<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(conn
 pub async fn request_notarization(
     host: &str,
     port: u16,
-    max_sent_data: Option<usize>,
-    max_recv_data: Option<usize>,
+    max_sent_data: Option<ByteCount>,
+    max_recv_data: Option<ByteCount>,
 ) -> (tokio_rustls::client::TlsStream<TcpStream>, String) {
     // Connect to the Notary via TLS-TCP
     let pem_file = std::str::from_utf8(include_bytes!(
@@ -83,6 +85,7 @@ pub async fn request_notarization(
         client_type: ClientType::Tcp,
         max_sent_data,
         max_recv_data,
+        signature_algorithm: None,
     })
     .unwrap();
 
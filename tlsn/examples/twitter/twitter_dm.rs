@@ -12,7 +12,7 @@ use tokio::io::AsyncWriteExt as _;
 use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
 use tracing::debug;
 
-use tlsn_prover::tls::{Prover, ProverConfig};
+use tlsn_prover::tls::{ByteCount, Prover, ProverConfig};
 
 // Setting of the application server
 const SERVER_DOMAIN: &str = "twitter.com";
@@ -24,8 +24,8 @@ const NOTARY_HOST: &str = "127.0.0.1";
 const NOTARY_PORT: u16 = 7047;
 
 // Configuration of notarization
-const NOTARY_MAX_SENT: usize = 1 << 12;
-const NOTARY_MAX_RECV: usize = 1 << 14;
+const NOTARY_MAX_SENT: ByteCount = ByteCount::new(1 << 12);
+const NOTARY_MAX_RECV: ByteCount = ByteCount::new(1 << 14);
 
 #[tokio::main]
 async fn main() {
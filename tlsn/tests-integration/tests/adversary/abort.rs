@@ -0,0 +1,48 @@
+//! An honest party which aborts the session, informing its counterparty of the reason.
+
+use tlsn_core::msg::AbortCode;
+use tlsn_prover::tls::ProverError;
+use tlsn_verifier::tls::VerifierError;
+
+use crate::{run_prover, run_verifier};
+
+#[tokio::test]
+#[ignore]
+async fn test_prover_aborts_without_commitments() {
+    let (prover_socket, verifier_socket) = tokio::io::duplex(2 << 23);
+
+    // The prover can not build the commitments, as it did not commit to anything.
+    let prover = async {
+        run_prover(prover_socket)
+            .await
+            .start_notarize()
+            .finalize()
+            .await
+    };
+
+    let verifier = async {
+        let signing_key = p256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+
+        run_verifier(verifier_socket)
+            .await
+            .start_notarize()
+            .finalize::<p256::ecdsa::Signature>(&signing_key)
+            .await
+    };
+
+    let (prover_result, verifier_result) = tokio::join!(prover, verifier);
+
+    let err = prover_result.unwrap_err();
+    assert!(
+        matches!(err, ProverError::CommitmentBuilder(_)),
+        "unexpected error: {err}"
+    );
+
+    match verifier_result {
+        Err(VerifierError::Aborted(abort)) => {
+            assert_eq!(abort.code, AbortCode::Internal);
+            assert_eq!(abort.message, err.to_string());
+        }
+        res => panic!("unexpected result: {res:?}"),
+    }
+}
@@ -0,0 +1,98 @@
+//! Tests that the honest party detects each deviation of a malicious counterparty.
+
+mod abort;
+mod prover;
+mod verifier;
+
+use futures::AsyncWriteExt;
+use hyper::{body::to_bytes, Body, Request, StatusCode};
+use tls_core::{anchors::RootCertStore, verify::WebPkiVerifier};
+use tlsn_prover::tls::{state::Closed, Prover, ProverConfig};
+use tlsn_server_fixture::{CA_CERT_DER, SERVER_DOMAIN};
+use tlsn_verifier::tls::{state::Closed as VerifierClosed, Verifier, VerifierConfig};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
+
+fn root_store() -> RootCertStore {
+    let mut root_store = RootCertStore::empty();
+    root_store
+        .add(&tls_core::key::Certificate(CA_CERT_DER.to_vec()))
+        .unwrap();
+    root_store
+}
+
+/// Runs the prover through a request to the test server, returning it once the connection is
+/// closed.
+async fn run_prover<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
+    socket: T,
+) -> Prover<Closed> {
+    let (client_socket, server_socket) = tokio::io::duplex(2 << 16);
+
+    let server_task = tokio::spawn(tlsn_server_fixture::bind(server_socket.compat()));
+
+    let prover = Prover::new(
+        ProverConfig::builder()
+            .id("test")
+            .server_dns(SERVER_DOMAIN)
+            .root_cert_store(root_store())
+            .build()
+            .unwrap(),
+    )
+    .setup(socket.compat())
+    .await
+    .unwrap();
+
+    let (tls_connection, prover_fut) = prover.connect(client_socket.compat()).await.unwrap();
+
+    let prover_task = tokio::spawn(prover_fut);
+
+    let (mut request_sender, connection) = hyper::client::conn::handshake(tls_connection.compat())
+        .await
+        .unwrap();
+
+    let connection_task = tokio::spawn(connection.without_shutdown());
+
+    let request = Request::builder()
+        .uri(format!("https://{}", SERVER_DOMAIN))
+        .header("Host", SERVER_DOMAIN)
+        .header("Connection", "close")
+        .method("GET")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = request_sender.send_request(request).await.unwrap();
+
+    assert!(response.status() == StatusCode::OK);
+
+    _ = to_bytes(response.into_body()).await.unwrap();
+
+    server_task.await.unwrap();
+
+    let mut client_socket = connection_task.await.unwrap().unwrap().io.into_inner();
+
+    client_socket.close().await.unwrap();
+
+    prover_task.await.unwrap().unwrap()
+}
+
+/// Runs the verifier through the TLS connection of the prover, returning it once the connection
+/// is closed.
+async fn run_verifier<T: AsyncWrite + AsyncRead + Send + Sync + Unpin + 'static>(
+    socket: T,
+) -> Verifier<VerifierClosed> {
+    let verifier = Verifier::new(
+        VerifierConfig::builder()
+            .id("test")
+            .cert_verifier(WebPkiVerifier::new(root_store(), None))
+            .build()
+            .unwrap(),
+    );
+
+    verifier
+        .setup(socket.compat())
+        .await
+        .unwrap()
+        .run()
+        .await
+        .unwrap()
+}
@@ -0,0 +1,59 @@
+//! A malicious prover against an honest verifier.
+
+use tlsn_core::{msg::AbortCode, Direction};
+use tlsn_prover::tls::{adversary::ProverDeviation, ProverError};
+use tlsn_verifier::tls::VerifierError;
+
+use crate::{run_prover, run_verifier};
+
+/// Proves parts of the transcript with a prover deviating from the protocol, returning the
+/// result of the prover and the verifier's error.
+async fn prove(deviation: ProverDeviation) -> (Result<(), ProverError>, VerifierError) {
+    let (prover_socket, verifier_socket) = tokio::io::duplex(2 << 23);
+
+    let prover = async {
+        let mut prover = run_prover(prover_socket).await.start_prove();
+        let recv_len = prover.recv_transcript().data().len();
+
+        prover.reveal(0..recv_len, Direction::Received).unwrap();
+        prover.prove_with_deviation(deviation).await
+    };
+
+    let verifier = async {
+        let mut verifier = run_verifier(verifier_socket).await.start_verify();
+        verifier.receive().await
+    };
+
+    let (prover_result, verifier_result) = tokio::join!(prover, verifier);
+
+    (prover_result, verifier_result.unwrap_err())
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_wrong_cleartext() {
+    let (_, err) = prove(ProverDeviation::WrongCleartext).await;
+    assert!(
+        matches!(err, VerifierError::MpcError(_)),
+        "unexpected error: {err}"
+    );
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_range_exceeds_transcript() {
+    let (prover_result, err) = prove(ProverDeviation::RangeExceedsTranscript).await;
+    assert!(
+        matches!(err, VerifierError::InvalidRange),
+        "unexpected error: {err}"
+    );
+
+    // The prover learns why the verifier rejected the proof.
+    match prover_result {
+        Err(ProverError::Aborted(abort)) => {
+            assert_eq!(abort.code, AbortCode::PolicyViolation);
+            assert_eq!(abort.message, "range exceeds transcript length");
+        }
+        res => panic!("unexpected result: {res:?}"),
+    }
+}
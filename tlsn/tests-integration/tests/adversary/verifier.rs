@@ -0,0 +1,71 @@
+//! A malicious notary against an honest prover.
+
+use tlsn_core::session::{SessionHeaderField, SessionHeaderVerifyError};
+use tlsn_prover::tls::ProverError;
+use tlsn_verifier::tls::adversary::VerifierDeviation;
+
+use crate::{run_prover, run_verifier};
+
+/// Notarizes a session with a notary deviating from the protocol, returning the prover's error.
+async fn notarize(deviation: VerifierDeviation) -> ProverError {
+    let (prover_socket, verifier_socket) = tokio::io::duplex(2 << 23);
+
+    let prover = async {
+        let mut prover = run_prover(prover_socket).await.start_notarize();
+        let sent_len = prover.sent_transcript().data().len();
+        let recv_len = prover.recv_transcript().data().len();
+
+        let builder = prover.commitment_builder();
+        builder.commit_sent(&(0..sent_len)).unwrap();
+        builder.commit_recv(&(0..recv_len)).unwrap();
+
+        prover.finalize().await
+    };
+
+    let verifier = async {
+        let signing_key = p256::ecdsa::SigningKey::from_bytes(&[1u8; 32].into()).unwrap();
+
+        run_verifier(verifier_socket)
+            .await
+            .start_notarize()
+            .finalize_with_deviation::<p256::ecdsa::Signature>(&signing_key, deviation)
+            .await
+    };
+
+    let (prover_result, _) = tokio::join!(prover, verifier);
+
+    prover_result.unwrap_err()
+}
+
+fn assert_mismatched_field(err: ProverError, field: SessionHeaderField) {
+    match err {
+        ProverError::InconsistentHeader(SessionHeaderVerifyError::InconsistentHeader(fields)) => {
+            assert_eq!(fields, vec![field])
+        }
+        err => panic!("unexpected error: {err}"),
+    }
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_wrong_encoder_seed() {
+    let err = notarize(VerifierDeviation::WrongEncoderSeed).await;
+    assert_mismatched_field(err, SessionHeaderField::EncoderSeed);
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_wrong_merkle_root() {
+    let err = notarize(VerifierDeviation::WrongMerkleRoot).await;
+    assert_mismatched_field(err, SessionHeaderField::MerkleRoot);
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_close_before_signing() {
+    let err = notarize(VerifierDeviation::CloseBeforeSigning).await;
+    assert!(
+        matches!(err, ProverError::IOError(_) | ProverError::MuxerError(_)),
+        "unexpected error: {err}"
+    );
+}
@@ -0,0 +1,270 @@
+//! Message channels which detect messages reordered, dropped or replayed by a faulty transport.
+//!
+//! The multiplexer preserves the order of messages on a stream, but a buggy relay between the
+//! parties may still reorder or drop them. Each message sent on a [`SequencedChannel`] is framed
+//! with the id of the channel and its sequence number, which the receiving side checks before
+//! passing the message on.
+//!
+//! The frames are not authenticated, so a malicious relay can rewrite the channel ids and sequence
+//! numbers consistently. Protection against a malicious relay requires an authenticated connection
+//! between the parties, e.g. TLS to the notary.
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{ready, Sink, Stream};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use utils_aio::{
+    duplex::Duplex,
+    mux::{MuxChannel, MuxerError},
+};
+
+use crate::mux::MuxControl;
+
+/// A message framed with its position on a channel.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Frame<T> {
+    /// The id of the channel the message was sent on.
+    pub channel_id: String,
+    /// The number of messages sent on the channel in the same direction before this one.
+    pub seq: u64,
+    /// The message.
+    pub msg: T,
+}
+
+/// A channel which frames the messages sent on it, see [`Frame`].
+///
+/// Receiving a message with an unexpected channel id or sequence number returns an
+/// [`io::ErrorKind::InvalidData`] error, after which the channel must not be used anymore.
+pub struct SequencedChannel<T> {
+    id: String,
+    inner: Box<dyn Duplex<Frame<T>>>,
+    send_seq: u64,
+    recv_seq: u64,
+}
+
+impl<T> SequencedChannel<T> {
+    /// Creates a new sequenced channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the channel, which must be the same for both parties.
+    /// * `inner` - The channel the frames are sent on.
+    pub fn new(id: impl Into<String>, inner: Box<dyn Duplex<Frame<T>>>) -> Self {
+        Self {
+            id: id.into(),
+            inner,
+            send_seq: 0,
+            recv_seq: 0,
+        }
+    }
+
+    /// Returns the id of the channel.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Checks that a received frame is the next one on this channel.
+    fn check(&mut self, frame: Frame<T>) -> Result<T, io::Error> {
+        if frame.channel_id != self.id {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "received message for channel {} on channel {}",
+                    frame.channel_id, self.id
+                ),
+            ));
+        }
+
+        if frame.seq != self.recv_seq {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "received message {} on channel {}, expected message {}",
+                    frame.seq, self.id, self.recv_seq
+                ),
+            ));
+        }
+
+        self.recv_seq += 1;
+
+        Ok(frame.msg)
+    }
+}
+
+impl<T> Stream for SequencedChannel<T> {
+    type Item = Result<T, io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let frame = ready!(Pin::new(&mut self.inner).poll_next(cx));
+        Poll::Ready(frame.map(|frame| frame.and_then(|frame| self.check(frame))))
+    }
+}
+
+impl<T> Sink<T> for SequencedChannel<T> {
+    type Error = io::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, msg: T) -> Result<(), Self::Error> {
+        let frame = Frame {
+            channel_id: self.id.clone(),
+            seq: self.send_seq,
+            msg,
+        };
+        Pin::new(&mut self.inner).start_send(frame)?;
+        self.send_seq += 1;
+
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+/// Opens a [`SequencedChannel`] on the multiplexer.
+///
+/// # Arguments
+///
+/// * `mux` - The multiplexer controller.
+/// * `id` - The id of the channel, which must be the same for both parties.
+pub async fn open_sequenced_channel<T>(
+    mux: &mut MuxControl,
+    id: &str,
+) -> Result<SequencedChannel<T>, MuxerError>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+{
+    let inner: Box<dyn Duplex<Frame<T>>> = mux.get_channel(id).await?;
+
+    Ok(SequencedChannel::new(id, inner))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use futures::{executor::block_on, SinkExt, StreamExt};
+
+    use super::*;
+
+    /// A duplex which receives a fixed list of frames and records the frames sent on it.
+    #[derive(Default)]
+    struct TestDuplex {
+        received: VecDeque<Frame<u8>>,
+        sent: Vec<Frame<u8>>,
+    }
+
+    impl Stream for TestDuplex {
+        type Item = Result<Frame<u8>, io::Error>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Ready(self.received.pop_front().map(Ok))
+        }
+    }
+
+    impl Sink<Frame<u8>> for TestDuplex {
+        type Error = io::Error;
+
+        fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn start_send(mut self: Pin<&mut Self>, frame: Frame<u8>) -> Result<(), io::Error> {
+            self.sent.push(frame);
+            Ok(())
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn frame(channel_id: &str, seq: u64, msg: u8) -> Frame<u8> {
+        Frame {
+            channel_id: channel_id.to_string(),
+            seq,
+            msg,
+        }
+    }
+
+    fn channel(received: Vec<Frame<u8>>) -> SequencedChannel<u8> {
+        SequencedChannel::new(
+            "test",
+            Box::new(TestDuplex {
+                received: received.into(),
+                sent: Vec::new(),
+            }),
+        )
+    }
+
+    fn assert_invalid_data(result: Option<Result<u8, io::Error>>) {
+        assert_eq!(
+            result.unwrap().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_sequenced_channel_in_order() {
+        let mut channel = channel(vec![frame("test", 0, 1), frame("test", 1, 2)]);
+
+        block_on(async {
+            channel.send(3).await.unwrap();
+            channel.send(4).await.unwrap();
+
+            assert_eq!(channel.next().await.unwrap().unwrap(), 1);
+            assert_eq!(channel.next().await.unwrap().unwrap(), 2);
+            // The inner channel closing is passed on, the protocol detects truncation
+            assert!(channel.next().await.is_none());
+        });
+    }
+
+    #[test]
+    fn test_sequenced_channel_reordered() {
+        let mut channel = channel(vec![frame("test", 1, 2), frame("test", 0, 1)]);
+
+        assert_invalid_data(block_on(channel.next()));
+    }
+
+    #[test]
+    fn test_sequenced_channel_dropped() {
+        let mut channel = channel(vec![frame("test", 0, 1), frame("test", 2, 3)]);
+
+        block_on(async {
+            assert_eq!(channel.next().await.unwrap().unwrap(), 1);
+            assert_invalid_data(channel.next().await);
+        });
+    }
+
+    #[test]
+    fn test_sequenced_channel_replayed() {
+        let mut channel = channel(vec![frame("test", 0, 1), frame("test", 0, 1)]);
+
+        block_on(async {
+            assert_eq!(channel.next().await.unwrap().unwrap(), 1);
+            assert_invalid_data(channel.next().await);
+        });
+    }
+
+    #[test]
+    fn test_sequenced_channel_wrong_channel() {
+        let mut channel = channel(vec![frame("other", 0, 1)]);
+
+        assert_invalid_data(block_on(channel.next()));
+    }
+}
@@ -1,11 +1,25 @@
 //! TLSNotary protocol config and config utilities.
 
-use crate::Role;
+pub use tlsn_units::{BitCount, ByteCount};
+
+use crate::{mux::MuxConfig, Role};
 
 /// Default for the maximum number of bytes that can be sent (4Kb).
-pub const DEFAULT_MAX_SENT_LIMIT: usize = 1 << 12;
+pub const DEFAULT_MAX_SENT_LIMIT: ByteCount = ByteCount::new(1 << 12);
 /// Default for the maximum number of bytes that can be received (16Kb).
-pub const DEFAULT_MAX_RECV_LIMIT: usize = 1 << 14;
+pub const DEFAULT_MAX_RECV_LIMIT: ByteCount = ByteCount::new(1 << 14);
+
+/// Sessions whose transcript limits add up to at most this many bytes use the
+/// [`SessionProfile::Small`] profile (1Kb).
+pub const SMALL_SESSION_LIMIT: ByteCount = ByteCount::new(1 << 10);
+/// Maximum number of bytes buffered per multiplexer stream in a small session, which is also the
+/// receive window (4Mb).
+const SMALL_SESSION_MAX_BUFFER_SIZE: usize = 4 << 20;
+/// Number of threads which garble the stream cipher in each direction of a small session.
+const SMALL_SESSION_MPC_THREADS: usize = 2;
+/// Number of threads which garble the stream cipher in each direction of a standard session, the
+/// default of `tls-mpc`.
+const STANDARD_SESSION_MPC_THREADS: usize = 8;
 
 // Determined experimentally, will be subject to change if underlying protocols are modified.
 const KE_OTS: usize = 3360;
@@ -13,32 +27,121 @@ const KE_OTS: usize = 3360;
 const GHASH_OTS: usize = 65664 * 2;
 // Extra cushion room, eg. for sharing J0 blocks.
 const EXTRA_OTS: usize = 16384;
-const OTS_PER_BYTE_SENT: usize = 8;
-// Without deferred decryption we use 16, with it we use 8.
-const OTS_PER_BYTE_RECV: usize = 16;
+const OTS_PER_BIT_SENT: usize = 1;
+// Without deferred decryption we use 2, with it we use 1.
+const OTS_PER_BIT_RECV: usize = 2;
 
 /// Returns an estimate of the number of OTs that will be sent.
-pub fn ot_send_estimate(role: Role, max_sent_data: usize, max_recv_data: usize) -> usize {
+pub fn ot_send_estimate(role: Role, max_sent_data: ByteCount, max_recv_data: ByteCount) -> usize {
     match role {
         Role::Prover => KE_OTS + GHASH_OTS + EXTRA_OTS,
         Role::Verifier => {
-            KE_OTS
-                + EXTRA_OTS
-                + (max_sent_data * OTS_PER_BYTE_SENT)
-                + (max_recv_data * OTS_PER_BYTE_RECV)
+            (KE_OTS + EXTRA_OTS).saturating_add(transcript_ots(max_sent_data, max_recv_data))
         }
     }
 }
 
 /// Returns an estimate of the number of OTs that will be received.
-pub fn ot_recv_estimate(role: Role, max_sent_data: usize, max_recv_data: usize) -> usize {
+pub fn ot_recv_estimate(role: Role, max_sent_data: ByteCount, max_recv_data: ByteCount) -> usize {
     match role {
         Role::Prover => {
-            KE_OTS
-                + EXTRA_OTS
-                + (max_sent_data * OTS_PER_BYTE_SENT)
-                + (max_recv_data * OTS_PER_BYTE_RECV)
+            (KE_OTS + EXTRA_OTS).saturating_add(transcript_ots(max_sent_data, max_recv_data))
         }
         Role::Verifier => KE_OTS + GHASH_OTS + EXTRA_OTS,
     }
 }
+
+/// Returns the number of OTs used for the bits of the transcript, saturating on overflow.
+fn transcript_ots(max_sent_data: ByteCount, max_recv_data: ByteCount) -> usize {
+    let bits = |bytes: ByteCount| bytes.to_bits().map_or(usize::MAX, BitCount::get);
+
+    bits(max_sent_data)
+        .saturating_mul(OTS_PER_BIT_SENT)
+        .saturating_add(bits(max_recv_data).saturating_mul(OTS_PER_BIT_RECV))
+}
+
+/// Profile of a session, which tunes the protocol for the size of the transcript.
+///
+/// The profile is selected from the declared transcript limits. Both parties know the limits
+/// before the session starts, so they select the same profile without an extra round trip.
+///
+/// The profile sets the number of MPC threads of the stream cipher and the multiplexer buffers.
+/// The OT extension and encoding batch sizes are fixed by the `mpz` dependencies and are not
+/// tuned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionProfile {
+    /// A session with a transcript of at most [`SMALL_SESSION_LIMIT`] bytes.
+    Small,
+    /// Any other session.
+    Standard,
+}
+
+impl SessionProfile {
+    /// Selects the profile for the given transcript limits.
+    pub fn from_limits(max_sent_data: ByteCount, max_recv_data: ByteCount) -> Self {
+        match max_sent_data.checked_add(max_recv_data) {
+            Some(total) if total <= SMALL_SESSION_LIMIT => Self::Small,
+            _ => Self::Standard,
+        }
+    }
+
+    /// Returns the number of threads which garble the stream cipher in each direction.
+    ///
+    /// Every thread is set up separately, with its own multiplexer stream and OTs, which a small
+    /// transcript does not have enough blocks to amortize. Both parties must use the same number.
+    pub fn mpc_threads(&self) -> usize {
+        match self {
+            Self::Small => SMALL_SESSION_MPC_THREADS,
+            Self::Standard => STANDARD_SESSION_MPC_THREADS,
+        }
+    }
+
+    /// Returns the multiplexer configuration for the profile.
+    pub fn mux_config(&self) -> MuxConfig {
+        match self {
+            Self::Small => MuxConfig::builder()
+                .max_buffer_size(SMALL_SESSION_MAX_BUFFER_SIZE)
+                .receive_window(SMALL_SESSION_MAX_BUFFER_SIZE as u32)
+                .build()
+                .unwrap(),
+            Self::Standard => MuxConfig::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_profile() {
+        assert_eq!(
+            SessionProfile::from_limits(ByteCount::new(256), ByteCount::new(768)),
+            SessionProfile::Small
+        );
+        assert_eq!(
+            SessionProfile::from_limits(ByteCount::new(256), ByteCount::new(769)),
+            SessionProfile::Standard
+        );
+        assert_eq!(
+            SessionProfile::from_limits(DEFAULT_MAX_SENT_LIMIT, DEFAULT_MAX_RECV_LIMIT),
+            SessionProfile::Standard
+        );
+        assert_eq!(
+            SessionProfile::from_limits(ByteCount::new(usize::MAX), ByteCount::new(1)),
+            SessionProfile::Standard
+        );
+
+        assert!(SessionProfile::Small.mpc_threads() < SessionProfile::Standard.mpc_threads());
+    }
+
+    #[test]
+    fn test_ot_estimate_saturates() {
+        let max = ByteCount::new(usize::MAX);
+        assert_eq!(ot_send_estimate(Role::Verifier, max, max), usize::MAX);
+        assert_eq!(
+            ot_recv_estimate(Role::Prover, ByteCount::new(1), ByteCount::new(1)),
+            KE_OTS + EXTRA_OTS + 8 + 16
+        );
+    }
+}
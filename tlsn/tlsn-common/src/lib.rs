@@ -4,8 +4,11 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
+pub mod channel;
 pub mod config;
+pub mod msg;
 pub mod mux;
+pub mod span;
 
 /// The party's role in the TLSN protocol.
 ///
@@ -16,3 +19,13 @@ pub enum Role {
     /// The verifier.
     Verifier,
 }
+
+impl Role {
+    /// Returns the name of the role, as used in span attributes.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Prover => "prover",
+            Role::Verifier => "verifier",
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Receiving protocol messages from the peer.
+
+use std::io::{Error, ErrorKind};
+
+use tlsn_core::msg::{Abort, TlsnMessage};
+
+/// Receives the next message from a channel, expecting it to be of the given variant.
+///
+/// Evaluates to a `Result` of the message contents, with the given error type. An [`Abort`]
+/// sent by the peer in place of the expected message is converted into the error, so that the
+/// reason of the peer reaches the caller. See [`unexpected_msg`] for the other cases.
+///
+/// # Example
+///
+/// ```ignore
+/// let hello = expect_msg_or_abort!(channel, TlsnMessage::Hello, ProverError)?;
+/// ```
+#[macro_export]
+macro_rules! expect_msg_or_abort {
+    ($channel:expr, $expected:path, $error:ty) => {{
+        use ::futures::StreamExt as _;
+
+        match $channel.next().await {
+            Some(Ok($expected(msg))) => Ok(msg),
+            msg => Err($crate::msg::unexpected_msg::<$error>(msg)),
+        }
+    }};
+}
+
+/// Returns the error for a message which was received in place of the expected one.
+///
+/// * An [`Abort`] is converted into the error as is.
+/// * Any other message is an [`InvalidData`](ErrorKind::InvalidData) IO error.
+/// * A closed channel is an [`UnexpectedEof`](ErrorKind::UnexpectedEof) IO error.
+pub fn unexpected_msg<E>(msg: Option<Result<TlsnMessage, Error>>) -> E
+where
+    E: From<Abort> + From<Error>,
+{
+    match msg {
+        Some(Ok(TlsnMessage::Abort(abort))) => E::from(abort),
+        Some(Ok(msg)) => E::from(Error::new(
+            ErrorKind::InvalidData,
+            format!("unexpected message: {:?}", msg),
+        )),
+        Some(Err(e)) => E::from(e),
+        None => E::from(Error::from(ErrorKind::UnexpectedEof)),
+    }
+}
@@ -1,20 +1,143 @@
 //! Multiplexer used in the TLSNotary protocol.
+//!
+//! Control messages share the connection with large OT and garbled circuit batches. To prevent
+//! them from being queued behind those batches, the streams of the [control
+//! channels](MuxConfig::control_streams) are prioritized: while a frame of a control message is
+//! waiting to be handed to the connection, writes on all other streams are paused.
 
-use utils_aio::codec::BincodeMux;
+use std::{
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
 
-use futures::{AsyncRead, AsyncWrite};
+use async_trait::async_trait;
+use futures::{ready, AsyncRead, AsyncWrite};
 use uid_mux::{yamux, UidYamux, UidYamuxControl};
+use utils_aio::{
+    codec::BincodeMux,
+    mux::{MuxStream, MuxerError},
+};
 
 use crate::Role;
 
 /// Multiplexer supporting unique deterministic stream IDs.
 pub type Mux<T> = UidYamux<T>;
 /// Multiplexer controller providing streams with a codec attached.
-pub type MuxControl = BincodeMux<UidYamuxControl>;
+pub type MuxControl = BincodeMux<PriorityControl>;
 
 const KB: usize = 1024;
 const MB: usize = 1024 * KB;
 
+/// Default for the maximum number of concurrent streams, which is enough for the channels opened
+/// during a session (see PR #418).
+pub const DEFAULT_MAX_NUM_STREAMS: usize = 40;
+/// Default for the maximum number of bytes buffered per stream (16Mb).
+pub const DEFAULT_MAX_BUFFER_SIZE: usize = 16 * MB;
+/// Default for the receive window of each stream (16Mb).
+pub const DEFAULT_RECEIVE_WINDOW: u32 = 16 * MB as u32;
+/// Minimum receive window of a stream supported by yamux (256Kb).
+pub const MIN_RECEIVE_WINDOW: u32 = 256 * KB as u32;
+/// Default ids of the control channels, which are prioritized over the other channels.
+pub const DEFAULT_CONTROL_STREAMS: &[&str] = &["handshake", "notarize", "prove-verify", "finalize"];
+
+/// Flow control configuration for the multiplexer.
+///
+/// Both parties should use the same configuration. Control messages share the connection with
+/// large OT and garbled circuit batches, so the receive window bounds how long a control message
+/// can be queued behind them.
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(build_fn(validate = "Self::validate"))]
+pub struct MuxConfig {
+    /// Maximum number of concurrent streams.
+    #[builder(default = "DEFAULT_MAX_NUM_STREAMS")]
+    max_num_streams: usize,
+    /// Maximum number of bytes buffered per stream.
+    ///
+    /// Must be at least the receive window, as the peer may send that many bytes before the
+    /// stream is read.
+    #[builder(default = "DEFAULT_MAX_BUFFER_SIZE")]
+    max_buffer_size: usize,
+    /// Number of bytes the peer may send on a stream before waiting for a window update.
+    #[builder(default = "DEFAULT_RECEIVE_WINDOW")]
+    receive_window: u32,
+    /// Ids of the control channels, whose messages are written before the messages of the other
+    /// channels.
+    #[builder(
+        setter(custom),
+        default = "DEFAULT_CONTROL_STREAMS.iter().map(|id| id.to_string()).collect()"
+    )]
+    control_streams: Vec<String>,
+}
+
+impl MuxConfigBuilder {
+    /// Sets the ids of the control channels.
+    pub fn control_streams<I, S>(&mut self, ids: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.control_streams = Some(ids.into_iter().map(Into::into).collect());
+        self
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.max_num_streams == Some(0) {
+            return Err("max_num_streams must be greater than 0".to_string());
+        }
+
+        let receive_window = self.receive_window.unwrap_or(DEFAULT_RECEIVE_WINDOW);
+        if receive_window < MIN_RECEIVE_WINDOW {
+            return Err(format!(
+                "receive_window must be at least {MIN_RECEIVE_WINDOW} bytes"
+            ));
+        }
+
+        let max_buffer_size = self.max_buffer_size.unwrap_or(DEFAULT_MAX_BUFFER_SIZE);
+        if max_buffer_size < receive_window as usize {
+            return Err(format!(
+                "max_buffer_size must be at least receive_window ({receive_window})"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for MuxConfig {
+    fn default() -> Self {
+        Self::builder().build().unwrap()
+    }
+}
+
+impl MuxConfig {
+    /// Creates a new builder for `MuxConfig`.
+    pub fn builder() -> MuxConfigBuilder {
+        MuxConfigBuilder::default()
+    }
+
+    /// Returns the maximum number of concurrent streams.
+    pub fn max_num_streams(&self) -> usize {
+        self.max_num_streams
+    }
+
+    /// Returns the maximum number of bytes buffered per stream.
+    pub fn max_buffer_size(&self) -> usize {
+        self.max_buffer_size
+    }
+
+    /// Returns the receive window of each stream.
+    pub fn receive_window(&self) -> u32 {
+        self.receive_window
+    }
+
+    /// Returns the ids of the control channels.
+    pub fn control_streams(&self) -> &[String] {
+        &self.control_streams
+    }
+}
+
 /// Attaches a multiplexer to the provided socket.
 ///
 /// Returns the multiplexer and a controller for creating streams with a codec attached.
@@ -23,15 +146,16 @@ const MB: usize = 1024 * KB;
 ///
 /// * `socket` - The socket to attach the multiplexer to.
 /// * `role` - The role of the party using the multiplexer.
+/// * `config` - The multiplexer configuration.
 pub fn attach_mux<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
     socket: T,
     role: Role,
+    config: &MuxConfig,
 ) -> (Mux<T>, MuxControl) {
     let mut mux_config = yamux::Config::default();
-    // See PR #418
-    mux_config.set_max_num_streams(40);
-    mux_config.set_max_buffer_size(16 * MB);
-    mux_config.set_receive_window(16 * MB as u32);
+    mux_config.set_max_num_streams(config.max_num_streams);
+    mux_config.set_max_buffer_size(config.max_buffer_size);
+    mux_config.set_receive_window(config.receive_window);
 
     let mux_role = match role {
         Role::Prover => yamux::Mode::Client,
@@ -39,7 +163,413 @@ pub fn attach_mux<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
     };
 
     let mux = UidYamux::new(mux_config, socket, mux_role);
-    let ctrl = BincodeMux::new(mux.control());
+    let ctrl = BincodeMux::new(PriorityControl {
+        inner: mux.control(),
+        gate: Default::default(),
+        control_streams: config.control_streams.clone().into(),
+    });
 
     (mux, ctrl)
 }
+
+/// The priority of a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Control messages, which are written before data.
+    Control,
+    /// Bulk data, e.g. OT and garbled circuit batches.
+    Data,
+}
+
+/// Pauses the writes of data streams while frames of control messages are written.
+#[derive(Debug, Default)]
+struct PriorityGate {
+    state: Mutex<GateState>,
+}
+
+#[derive(Debug, Default)]
+struct GateState {
+    /// Number of control streams with a frame which has not been handed to the connection yet.
+    pending_control: usize,
+    /// Data streams waiting for the control frames to be handed to the connection.
+    waiting: Vec<Waker>,
+}
+
+impl PriorityGate {
+    fn acquire(&self) {
+        self.state.lock().unwrap().pending_control += 1;
+    }
+
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.pending_control -= 1;
+        if state.pending_control == 0 {
+            state.waiting.drain(..).for_each(Waker::wake);
+        }
+    }
+
+    fn poll_data(&self, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.pending_control == 0 {
+            return Poll::Ready(());
+        }
+
+        if !state.waiting.iter().any(|w| w.will_wake(cx.waker())) {
+            state.waiting.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+}
+
+/// A multiplexer controller which prioritizes the streams of control channels.
+#[derive(Debug, Clone)]
+pub struct PriorityControl {
+    inner: UidYamuxControl,
+    gate: Arc<PriorityGate>,
+    control_streams: Arc<[String]>,
+}
+
+impl PriorityControl {
+    /// Returns the priority of the stream with the given id.
+    pub fn priority(&self, id: &str) -> Priority {
+        if self.control_streams.iter().any(|control| control == id) {
+            Priority::Control
+        } else {
+            Priority::Data
+        }
+    }
+
+    /// Closes the connection.
+    pub async fn close(&mut self) -> Result<(), MuxerError> {
+        self.inner.close().await
+    }
+}
+
+#[async_trait]
+impl MuxStream for PriorityControl {
+    type Stream = PriorityStream<yamux::Stream>;
+
+    async fn get_stream(&mut self, id: &str) -> Result<Self::Stream, MuxerError> {
+        let stream = self.inner.get_stream(id).await?;
+
+        Ok(PriorityStream::new(
+            stream,
+            self.priority(id),
+            self.gate.clone(),
+        ))
+    }
+}
+
+/// A stream whose writes are scheduled according to its [`Priority`].
+///
+/// A control stream holds back the data streams only while one of its writes is pending, i.e.
+/// until the frame is handed to the connection, and not until it is flushed. Waiting for the
+/// flush could deadlock the parties, as the peer may only read the control stream after it
+/// received data which is held back. Data streams only wait for control frames which have not
+/// been handed to the connection yet, not for data which is already queued.
+#[derive(Debug)]
+pub struct PriorityStream<S> {
+    inner: S,
+    priority: Priority,
+    gate: Arc<PriorityGate>,
+    /// Whether this control stream has a pending write.
+    holding: bool,
+}
+
+impl<S> PriorityStream<S> {
+    fn new(inner: S, priority: Priority, gate: Arc<PriorityGate>) -> Self {
+        Self {
+            inner,
+            priority,
+            gate,
+            holding: false,
+        }
+    }
+
+    /// Returns the priority of the stream.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    fn release(&mut self) {
+        if self.holding {
+            self.holding = false;
+            self.gate.release();
+        }
+    }
+}
+
+impl<S> Drop for PriorityStream<S> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for PriorityStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for PriorityStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.priority {
+            Priority::Control => {
+                if !self.holding {
+                    self.holding = true;
+                    self.gate.acquire();
+                }
+            }
+            Priority::Data => ready!(self.gate.poll_data(cx)),
+        }
+
+        let res = ready!(Pin::new(&mut self.inner).poll_write(cx, buf));
+        self.release();
+
+        Poll::Ready(res)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Releases the gate if a pending write was abandoned
+        self.release();
+
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.release();
+
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+    use futures::{
+        executor::{block_on, LocalPool},
+        future::poll_fn,
+        io::Cursor,
+        task::LocalSpawnExt,
+        AsyncReadExt, AsyncWriteExt,
+    };
+
+    use super::*;
+
+    fn stream(priority: Priority, gate: &Arc<PriorityGate>) -> PriorityStream<Cursor<Vec<u8>>> {
+        PriorityStream::new(Cursor::new(Vec::new()), priority, gate.clone())
+    }
+
+    /// A connection which queues at most `capacity` frames, each write being one frame.
+    struct Link {
+        frames: VecDeque<&'static str>,
+        capacity: usize,
+        waiting: Vec<Waker>,
+    }
+
+    impl Link {
+        /// Sends the next frame, waking the streams waiting for capacity.
+        fn pop(&mut self) -> Option<&'static str> {
+            let frame = self.frames.pop_front();
+            self.waiting.drain(..).for_each(Waker::wake);
+            frame
+        }
+    }
+
+    /// A stream writing frames with its id to a [`Link`].
+    struct LinkStream {
+        link: Rc<RefCell<Link>>,
+        id: &'static str,
+    }
+
+    impl AsyncWrite for LinkStream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let mut link = self.link.borrow_mut();
+            if link.frames.len() >= link.capacity {
+                link.waiting.push(cx.waker().clone());
+                return Poll::Pending;
+            }
+            link.frames.push_back(self.id);
+
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    const LINK_CAPACITY: usize = 4;
+    const DATA_FRAMES: usize = 64;
+
+    /// Returns the number of data frames sent before a control message which is written while a
+    /// data stream saturates the link.
+    ///
+    /// # Arguments
+    ///
+    /// * `control_priority` - The priority of the stream the control message is written on.
+    fn data_frames_before_control(control_priority: Priority) -> usize {
+        let link = Rc::new(RefCell::new(Link {
+            frames: VecDeque::new(),
+            capacity: LINK_CAPACITY,
+            waiting: Vec::new(),
+        }));
+        let gate = Arc::new(PriorityGate::default());
+        let link_stream = |id| LinkStream {
+            link: link.clone(),
+            id,
+        };
+        let mut data = PriorityStream::new(link_stream("data"), Priority::Data, gate.clone());
+        let mut control = PriorityStream::new(link_stream("control"), control_priority, gate);
+
+        let mut pool = LocalPool::new();
+        let spawner = pool.spawner();
+        spawner
+            .spawn_local(async move {
+                for _ in 0..DATA_FRAMES {
+                    data.write_all(b"data").await.unwrap();
+                }
+            })
+            .unwrap();
+        // The data stream fills the link and waits for capacity
+        pool.run_until_stalled();
+        spawner
+            .spawn_local(async move { control.write_all(b"control").await.unwrap() })
+            .unwrap();
+        pool.run_until_stalled();
+
+        let mut data_frames = 0;
+        loop {
+            let frame = link.borrow_mut().pop();
+            match frame.expect("control message was sent") {
+                "control" => return data_frames,
+                _ => data_frames += 1,
+            }
+            pool.run_until_stalled();
+        }
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(MuxConfig::builder().build().is_ok());
+        assert!(MuxConfig::builder().max_num_streams(0).build().is_err());
+        assert!(MuxConfig::builder()
+            .receive_window(MIN_RECEIVE_WINDOW - 1)
+            .build()
+            .is_err());
+        assert!(MuxConfig::builder().max_buffer_size(MB).build().is_err());
+        assert!(MuxConfig::builder()
+            .max_buffer_size(MB)
+            .receive_window(MB as u32)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_priority() {
+        let config = MuxConfig::builder()
+            .control_streams(["control"])
+            .build()
+            .unwrap();
+        assert_eq!(config.control_streams(), ["control".to_string()]);
+
+        let default = MuxConfig::default();
+        assert!(default.control_streams().iter().any(|id| id == "notarize"));
+    }
+
+    #[test]
+    fn test_data_waits_for_pending_control_write() {
+        let gate = Arc::new(PriorityGate::default());
+        let mut control = stream(Priority::Control, &gate);
+        let mut data = stream(Priority::Data, &gate);
+
+        // A pending control write holds back the data streams
+        gate.acquire();
+        block_on(async {
+            let mut write = data.write_all(b"data");
+            assert!(futures::poll!(&mut write).is_pending());
+            gate.release();
+            write.await.unwrap();
+        });
+
+        // A completed control write does not, even before it is flushed
+        block_on(async {
+            poll_fn(|cx| Pin::new(&mut control).poll_write(cx, b"control"))
+                .await
+                .unwrap();
+            data.write_all(b"data").await.unwrap();
+        });
+
+        assert_eq!(data.inner.get_ref(), b"datadata");
+    }
+
+    #[test]
+    fn test_dropped_control_stream_releases_data() {
+        let link = Rc::new(RefCell::new(Link {
+            frames: VecDeque::from(["data"]),
+            capacity: 1,
+            waiting: Vec::new(),
+        }));
+        let gate = Arc::new(PriorityGate::default());
+        let mut control = PriorityStream::new(
+            LinkStream {
+                link: link.clone(),
+                id: "control",
+            },
+            Priority::Control,
+            gate.clone(),
+        );
+
+        block_on(async {
+            let mut write = control.write_all(b"control");
+            assert!(futures::poll!(&mut write).is_pending());
+        });
+        assert_eq!(gate.state.lock().unwrap().pending_control, 1);
+
+        // The write is abandoned and the stream dropped
+        drop(control);
+        assert_eq!(gate.state.lock().unwrap().pending_control, 0);
+    }
+
+    /// Regression test for control messages being blocked behind bulk data.
+    ///
+    /// Without prioritization the control message is only sent after all the data, as the data
+    /// stream takes the capacity of the link whenever it becomes available. With prioritization it
+    /// is only queued behind the frames which were already handed to the link.
+    #[test]
+    fn test_no_head_of_line_blocking() {
+        let ungated = data_frames_before_control(Priority::Data);
+        let gated = data_frames_before_control(Priority::Control);
+
+        assert_eq!(ungated, DATA_FRAMES);
+        assert!(gated <= LINK_CAPACITY, "{gated} data frames sent first");
+    }
+
+    #[test]
+    fn test_read_passthrough() {
+        let gate = Arc::new(PriorityGate::default());
+        let mut stream = PriorityStream::new(Cursor::new(b"hello".to_vec()), Priority::Data, gate);
+
+        let mut buf = Vec::new();
+        block_on(stream.read_to_end(&mut buf)).unwrap();
+        assert_eq!(buf, b"hello");
+    }
+}
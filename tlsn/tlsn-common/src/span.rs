@@ -0,0 +1,57 @@
+//! Names and attributes of the spans emitted for the protocol phases.
+//!
+//! The prover and the verifier emit a span with the same name for each phase, carrying the
+//! attributes below, so that the traces of both parties can be joined in observability tooling.
+//! The session id is hashed, so that the traces can be joined without exporting the id itself.
+
+use sha2::{Digest, Sha256};
+
+/// Span of the protocol version handshake and the MPC setup.
+pub const SETUP: &str = "tlsn.setup";
+/// Span of the TLS connection to the server.
+pub const TLS_CONNECTION: &str = "tlsn.tls_connection";
+/// Span of the finalization of a notarization.
+pub const NOTARIZE: &str = "tlsn.notarize";
+/// Span of proving, or verifying, parts of the transcript.
+pub const PROVE: &str = "tlsn.prove";
+/// Names of all the spans of the protocol phases.
+pub const PHASES: [&str; 4] = [SETUP, TLS_CONNECTION, NOTARIZE, PROVE];
+
+/// Attribute holding the name of the phase, e.g. `setup`.
+pub const ATTR_PHASE: &str = "tlsn.phase";
+/// Attribute holding the role of the party emitting the span, either `prover` or `verifier`.
+pub const ATTR_PEER_ROLE: &str = "tlsn.peer_role";
+/// Attribute holding the hash of the session id, see [`session_id_hash`].
+pub const ATTR_SESSION_ID_HASH: &str = "tlsn.session_id_hash";
+/// Attribute holding the number of bytes sent to the server, set on the TLS connection span.
+pub const ATTR_BYTES_SENT: &str = "tlsn.bytes_sent";
+/// Attribute holding the number of bytes received from the server, set on the TLS connection span.
+pub const ATTR_BYTES_RECV: &str = "tlsn.bytes_recv";
+/// Names of all the attributes of the spans.
+pub const ATTRIBUTES: [&str; 5] = [
+    ATTR_PHASE,
+    ATTR_PEER_ROLE,
+    ATTR_SESSION_ID_HASH,
+    ATTR_BYTES_SENT,
+    ATTR_BYTES_RECV,
+];
+
+/// Returns the hash of a session id which is attached to the spans, as the first 8 bytes of its
+/// SHA-256 digest in hex.
+pub fn session_id_hash(id: &str) -> String {
+    Sha256::digest(id.as_bytes())[..8]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_id_hash() {
+        // SHA-256 of "abc" starts with ba7816bf8f01cfea
+        assert_eq!(session_id_hash("abc"), "ba7816bf8f01cfea");
+    }
+}
@@ -0,0 +1,63 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use tlsn_core::{
+    commitment::TranscriptCommitmentBuilder, fixtures, proof::SubstringsProofBuilder, Transcript,
+};
+
+/// The number of committed ranges which are revealed.
+const RANGES: usize = 10_000;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("substrings");
+
+    let data_sent = vec![0u8; 16];
+    let data_recv = (0..RANGES * 2).map(|i| i as u8).collect::<Vec<_>>();
+    let transcript_tx = Transcript::new(data_sent.clone());
+    let transcript_rx = Transcript::new(data_recv.clone());
+
+    let mut commitment_builder = TranscriptCommitmentBuilder::new(
+        fixtures::encoding_provider(&data_sent, &data_recv),
+        data_sent.len(),
+        data_recv.len(),
+    );
+
+    let ids = (0..RANGES)
+        .map(|idx| {
+            commitment_builder
+                .commit_recv(&(idx * 2..idx * 2 + 1))
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let commitments = commitment_builder.build().unwrap();
+
+    let builder = || {
+        let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        for id in &ids {
+            builder.reveal_by_id(*id).unwrap();
+        }
+        builder
+    };
+
+    group.bench_function("build", |b| {
+        b.iter_batched(
+            builder,
+            |builder| bincode::serialize_into(std::io::sink(), &builder.build().unwrap()).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("build_streaming", |b| {
+        b.iter_batched(
+            builder,
+            |builder| {
+                bincode::serialize_into(std::io::sink(), &builder.build_streaming().unwrap())
+                    .unwrap()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,293 @@
+//! Produces sanitized reproductions of notarized sessions which can be attached to bug reports.
+//!
+//! The bytes of the transcripts selected by a [`RedactionConfig`] are replaced with placeholders
+//! which preserve their structure, e.g. the delimiters of HTTP messages and JSON documents. The
+//! commitments are then recomputed with a given encoder seed and the header is signed with a given
+//! test key, so that proofs built from the sanitized session verify against the test key, but not
+//! against any real notary.
+
+use std::{collections::HashMap, ops::Range};
+
+use mpz_circuits::types::ValueType;
+use mpz_garble_core::{ChaChaEncoder, Encoder};
+use p256::ecdsa::{signature::Signer, Signature as P256Signature, SigningKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commitment::{CommitmentId, TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError},
+    session::{CompactHeader, SignedCompactHeader},
+    Direction, EncodingId, EncodingProvider, NotarizedSession, NotaryPublicKey, SessionData,
+    SessionHeader, Transcript,
+};
+
+/// The ranges of the transcripts to replace with placeholders.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// Ranges of the sent transcript.
+    #[serde(default)]
+    pub sent: Vec<Range<usize>>,
+    /// Ranges of the received transcript.
+    #[serde(default)]
+    pub recv: Vec<Range<usize>>,
+}
+
+/// A sanitized notarized session, signed with the test key.
+#[derive(Serialize, Deserialize)]
+pub struct ReproductionBundle {
+    /// The public key of the test notary, which the session is signed with.
+    pub notary_public_key: NotaryPublicKey,
+    /// The sanitized session.
+    pub session: NotarizedSession,
+    /// The compact header of the sanitized session.
+    pub compact_header: SignedCompactHeader,
+}
+
+opaque_debug::implement!(ReproductionBundle);
+
+/// An error that can occur while anonymizing a notarized session.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum AnonymizeError {
+    /// A range of the redaction config exceeds the transcript.
+    #[error("{direction:?} range {range:?} exceeds the transcript length {len}")]
+    RangeOutOfBounds {
+        /// The direction of the transcript.
+        direction: Direction,
+        /// The range which exceeds the transcript.
+        range: Range<usize>,
+        /// The length of the transcript.
+        len: usize,
+    },
+    /// The commitments could not be recomputed.
+    #[error(transparent)]
+    Commitment(#[from] TranscriptCommitmentBuilderError),
+}
+
+/// Returns the placeholder of a byte.
+///
+/// ASCII punctuation and whitespace are preserved, so that the sanitized transcript can still be
+/// parsed the same way as the original.
+pub fn placeholder(byte: u8) -> u8 {
+    match byte {
+        b'0'..=b'9' => b'0',
+        b'a'..=b'z' => b'x',
+        b'A'..=b'Z' => b'X',
+        _ if byte.is_ascii_punctuation() || byte.is_ascii_whitespace() => byte,
+        _ => b'?',
+    }
+}
+
+/// Produces a sanitized reproduction of a notarized session.
+///
+/// The commitments are recomputed over the same ranges as in the original session, so proofs
+/// which reveal the same ranges can be built from the sanitized session. The handshake and the
+/// server name are kept, while the link to a previous session header is dropped.
+///
+/// # Arguments
+///
+/// * `session` - The notarized session to anonymize.
+/// * `config` - The ranges of the transcripts to replace with placeholders.
+/// * `encoder_seed` - The seed of the encoder used to recompute the commitments.
+/// * `signing_key` - The test key to sign the sanitized session with.
+pub fn anonymize(
+    session: &NotarizedSession,
+    config: &RedactionConfig,
+    encoder_seed: [u8; 32],
+    signing_key: &SigningKey,
+) -> Result<ReproductionBundle, AnonymizeError> {
+    let data = session.data();
+    let sent = redact(data.sent_transcript(), &config.sent, Direction::Sent)?;
+    let recv = redact(data.recv_transcript(), &config.recv, Direction::Received)?;
+
+    let mut builder = TranscriptCommitmentBuilder::new(
+        encoding_provider(encoder_seed, &sent, &recv),
+        sent.len(),
+        recv.len(),
+    );
+
+    // Commit in the order of the ids, so the commitments keep their ids
+    let mut infos = data.commitments().iter_info().collect::<Vec<_>>();
+    infos.sort_by_key(|(id, _)| **id);
+    for (idx, (id, info)) in infos.into_iter().enumerate() {
+        debug_assert_eq!(*id, CommitmentId::new(idx as u32));
+        builder.commit(info.ranges(), *info.direction())?;
+    }
+    let commitments = builder.build()?;
+
+    let original = session.header();
+    let header = SessionHeader::new(
+        encoder_seed,
+        commitments.merkle_root(),
+        sent.len(),
+        recv.len(),
+        original.handshake_summary().clone(),
+    );
+
+    let notary_public_key = NotaryPublicKey::from(*signing_key.verifying_key());
+    let signature: P256Signature = signing_key.sign(&header.to_bytes());
+    let compact_header = CompactHeader::new(&header, &notary_public_key);
+    let compact_signature: P256Signature = signing_key.sign(&compact_header.signing_bytes());
+
+    let data = SessionData::new(
+        data.session_info().server_name.clone(),
+        data.session_info().handshake_decommitment.clone(),
+        Transcript::new(sent),
+        Transcript::new(recv),
+        commitments,
+    );
+
+    Ok(ReproductionBundle {
+        notary_public_key,
+        session: NotarizedSession::new(header, Some(signature.into()), data),
+        compact_header: SignedCompactHeader {
+            header: compact_header,
+            signature: compact_signature.into(),
+        },
+    })
+}
+
+fn encoding_provider(encoder_seed: [u8; 32], sent: &[u8], recv: &[u8]) -> EncodingProvider {
+    let encoder = ChaChaEncoder::new(encoder_seed);
+    let mut encodings = HashMap::new();
+    for (prefix, transcript) in [("tx", sent), ("rx", recv)] {
+        for (idx, byte) in transcript.iter().enumerate() {
+            let id = format!("{prefix}/{idx}");
+            let encoding = encoder.encode_by_type(EncodingId::new(&id).to_inner(), &ValueType::U8);
+            encodings.insert(id, encoding.select(*byte).unwrap());
+        }
+    }
+
+    Box::new(move |ids: &[&str]| ids.iter().map(|id| encodings.get(*id).cloned()).collect())
+}
+
+fn redact(
+    transcript: &Transcript,
+    ranges: &[Range<usize>],
+    direction: Direction,
+) -> Result<Vec<u8>, AnonymizeError> {
+    let mut data = transcript.data().to_vec();
+    for range in ranges {
+        let bytes =
+            data.get_mut(range.clone())
+                .ok_or_else(|| AnonymizeError::RangeOutOfBounds {
+                    direction,
+                    range: range.clone(),
+                    len: transcript.data().len(),
+                })?;
+        bytes.iter_mut().for_each(|byte| *byte = placeholder(*byte));
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mpz_core::commit::HashCommit;
+
+    use crate::fixtures;
+
+    const SENT: &[u8] = b"GET /account HTTP/1.1\r\nAuthorization: Bearer s3cr3T\r\n\r\n";
+    const RECV: &[u8] = b"HTTP/1.1 200 OK\r\n\r\n{\"balance\": 1234}";
+
+    fn session() -> NotarizedSession {
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(SENT, RECV),
+            SENT.len(),
+            RECV.len(),
+        );
+        builder.commit_sent(&(0..SENT.len())).unwrap();
+        builder.commit_recv(&(0..17)).unwrap();
+        builder.commit_recv(&(17..RECV.len())).unwrap();
+        let commitments = builder.build().unwrap();
+
+        let (decommitment, _) = fixtures::handshake_data().hash_commit();
+        let header = fixtures::session_header(commitments.merkle_root(), SENT.len(), RECV.len());
+        let data = SessionData::new(
+            crate::ServerName::Dns("tlsnotary.org".to_string()),
+            decommitment,
+            Transcript::new(SENT),
+            Transcript::new(RECV),
+            commitments,
+        );
+
+        NotarizedSession::new(header, None, data)
+    }
+
+    #[test]
+    fn test_placeholder() {
+        assert_eq!(
+            b"Bearer s3cr3T\r\n{\"a\": [1]}\xff".map(placeholder),
+            *b"Xxxxxx x0xx0X\r\n{\"x\": [0]}?"
+        );
+    }
+
+    #[test]
+    fn test_anonymize() {
+        let config = RedactionConfig {
+            sent: vec![45..51],
+            recv: vec![31..35],
+        };
+
+        let ReproductionBundle {
+            notary_public_key,
+            session,
+            compact_header,
+        } = anonymize(
+            &session(),
+            &config,
+            fixtures::encoder_seed(),
+            &fixtures::notary_signing_key(),
+        )
+        .unwrap();
+
+        let sent = session.data().sent_transcript().data();
+        let recv = session.data().recv_transcript().data();
+        assert_eq!(&sent[..45], &SENT[..45]);
+        assert_eq!(&sent[45..], b"x0xx0X\r\n\r\n");
+        assert_eq!(&recv[..], b"HTTP/1.1 200 OK\r\n\r\n{\"balance\": 0000}");
+
+        let header = session.header();
+        session
+            .signature()
+            .as_ref()
+            .unwrap()
+            .verify(&header.to_bytes(), notary_public_key.clone())
+            .unwrap();
+        assert!(compact_header.header.is_summary_of(header));
+        compact_header.verify(&notary_public_key).unwrap();
+
+        let commitments = session.data().commitments();
+        let id = commitments
+            .get_ids_by_range(Direction::Received, &(17..RECV.len()))
+            .pop()
+            .unwrap();
+        let mut builder = session.data().build_substrings_proof();
+        builder.reveal_by_id(id).unwrap();
+        let (_, revealed) = builder.build().unwrap().verify(header).unwrap();
+
+        assert_eq!(&revealed.data()[17..], b"{\"balance\": 0000}");
+    }
+
+    #[test]
+    fn test_range_out_of_bounds() {
+        let config = RedactionConfig {
+            sent: vec![0..SENT.len() + 1],
+            recv: vec![],
+        };
+
+        assert!(matches!(
+            anonymize(
+                &session(),
+                &config,
+                fixtures::encoder_seed(),
+                &fixtures::notary_signing_key()
+            ),
+            Err(AnonymizeError::RangeOutOfBounds {
+                direction: Direction::Sent,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,256 @@
+//! Canonical JSON serialization.
+//!
+//! This module implements the JSON Canonicalization Scheme (JCS) described in
+//! [RFC 8785](https://www.rfc-editor.org/rfc/rfc8785). The canonical form of a value is byte-for-byte
+//! reproducible, so relying parties written in other languages can recompute exactly the same bytes
+//! from the same data, e.g. to hash or sign them.
+//!
+//! Additionally, [`to_json_ld`] wraps a canonical object with a JSON-LD `@context` and `@type` so that
+//! attestations can be consumed by decentralized identity tooling. TLSNotary does not publish a
+//! context, so the context is provided by the relying party.
+//!
+//! # Limitations
+//!
+//! Only integers in the range `[-(2^53 - 1), 2^53 - 1]` are supported, as these are the only numbers
+//! which are guaranteed to round-trip through an IEEE 754 double. None of the types in this crate
+//! serialize floating point values.
+
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+/// The largest integer which is exactly representable as an IEEE 754 double.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// An error that can occur during canonical JSON serialization.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum CanonicalJsonError {
+    /// The value could not be serialized to JSON.
+    #[error("failed to serialize value: {0}")]
+    Serialize(#[from] serde_json::Error),
+    /// The value contains a number which can not be represented canonically.
+    #[error("number can not be represented in canonical JSON: {0}")]
+    UnsupportedNumber(Number),
+    /// A JSON-LD document was requested for a value which is not a JSON object.
+    #[error("JSON-LD documents can only be created from JSON objects")]
+    NotAnObject,
+    /// The value contains a field which is reserved by the JSON-LD document.
+    #[error("field is reserved in JSON-LD documents: {0}")]
+    ReservedField(String),
+}
+
+/// Serializes the value into canonical JSON.
+///
+/// # Arguments
+///
+/// * `value` - The value to serialize.
+pub fn to_canonical_json<T: Serialize + ?Sized>(value: &T) -> Result<String, CanonicalJsonError> {
+    let value = serde_json::to_value(value)?;
+
+    let mut out = String::new();
+    write_value(&value, &mut out)?;
+
+    Ok(out)
+}
+
+/// Serializes the value into a canonical JSON-LD document.
+///
+/// The value must serialize into a JSON object which does not contain an `@context` or `@type`
+/// field.
+///
+/// # Arguments
+///
+/// * `value` - The value to serialize.
+/// * `context` - The JSON-LD context of the document, e.g. an IRI of a context published by the
+///   relying party.
+/// * `ty` - The JSON-LD type of the value, e.g. `SessionHeader`.
+pub fn to_json_ld<T: Serialize + ?Sized>(
+    value: &T,
+    context: &str,
+    ty: &str,
+) -> Result<String, CanonicalJsonError> {
+    let Value::Object(mut document) = serde_json::to_value(value)? else {
+        return Err(CanonicalJsonError::NotAnObject);
+    };
+
+    for (name, value) in [("@context", context), ("@type", ty)] {
+        if document.contains_key(name) {
+            return Err(CanonicalJsonError::ReservedField(name.to_string()));
+        }
+        document.insert(name.to_string(), Value::String(value.to_string()));
+    }
+
+    let mut out = String::new();
+    write_value(&Value::Object(document), &mut out)?;
+
+    Ok(out)
+}
+
+fn write_value(value: &Value, out: &mut String) -> Result<(), CanonicalJsonError> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(true) => out.push_str("true"),
+        Value::Bool(false) => out.push_str("false"),
+        Value::Number(number) => write_number(number, out)?,
+        Value::String(string) => write_string(string, out),
+        Value::Array(values) => {
+            out.push('[');
+            for (idx, value) in values.iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                write_value(value, out)?;
+            }
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            // Properties are sorted by the UTF-16 code units of their names.
+            let mut fields = fields.iter().collect::<Vec<_>>();
+            fields.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            out.push('{');
+            for (idx, (name, value)) in fields.into_iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                write_string(name, out);
+                out.push(':');
+                write_value(value, out)?;
+            }
+            out.push('}');
+        }
+    }
+
+    Ok(())
+}
+
+fn write_number(number: &Number, out: &mut String) -> Result<(), CanonicalJsonError> {
+    if let Some(n) = number.as_u64() {
+        if n <= MAX_SAFE_INTEGER {
+            out.push_str(&n.to_string());
+            return Ok(());
+        }
+    } else if let Some(n) = number.as_i64() {
+        if n.unsigned_abs() <= MAX_SAFE_INTEGER {
+            out.push_str(&n.to_string());
+            return Ok(());
+        }
+    }
+
+    Err(CanonicalJsonError::UnsupportedNumber(number.clone()))
+}
+
+fn write_string(string: &str, out: &mut String) {
+    out.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if c < '\u{20}' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::{fixtures, merkle::MerkleRoot};
+
+    // Property sorting vector from RFC 8785, section 3.2.3.
+    #[test]
+    fn test_sorting() {
+        let value = json!({
+            "\u{20ac}": "Euro Sign",
+            "\r": "Carriage Return",
+            "\u{fb33}": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\u{1f600}": "Emoji: Grinning Face",
+            "\u{80}": "Control",
+            "\u{f6}": "Latin Small Letter O With Diaeresis"
+        });
+
+        assert_eq!(
+            to_canonical_json(&value).unwrap(),
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\
+            \"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",\
+            \"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+        );
+    }
+
+    #[test]
+    fn test_string_escaping() {
+        let value = json!(["\u{1}\u{8}\t\n\u{c}\r\u{1f}", "\"\\/", "\u{7f}\u{20ac}"]);
+
+        assert_eq!(
+            to_canonical_json(&value).unwrap(),
+            "[\"\\u0001\\b\\t\\n\\f\\r\\u001f\",\"\\\"\\\\/\",\"\u{7f}\u{20ac}\"]"
+        );
+    }
+
+    #[test]
+    fn test_literals_and_whitespace() {
+        let value = json!({ "b": [true, false, null], "a": { "d": -1, "c": 0 } });
+
+        assert_eq!(
+            to_canonical_json(&value).unwrap(),
+            "{\"a\":{\"c\":0,\"d\":-1},\"b\":[true,false,null]}"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_numbers() {
+        assert!(to_canonical_json(&json!(MAX_SAFE_INTEGER)).is_ok());
+        assert!(matches!(
+            to_canonical_json(&json!(MAX_SAFE_INTEGER + 1)).unwrap_err(),
+            CanonicalJsonError::UnsupportedNumber(_)
+        ));
+        assert!(matches!(
+            to_canonical_json(&json!(1.5)).unwrap_err(),
+            CanonicalJsonError::UnsupportedNumber(_)
+        ));
+    }
+
+    const CONTEXT: &str = "https://example.org/context.jsonld";
+
+    #[test]
+    fn test_session_header_json_ld() {
+        let header = fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 20);
+
+        let document = to_json_ld(&header, CONTEXT, "SessionHeader").unwrap();
+
+        assert!(document.starts_with(&format!(
+            "{{\"@context\":\"{CONTEXT}\",\"@type\":\"SessionHeader\","
+        )));
+        // Serializing the same header twice must produce identical bytes.
+        assert_eq!(
+            document,
+            to_json_ld(&header, CONTEXT, "SessionHeader").unwrap()
+        );
+        assert!(matches!(
+            to_json_ld(&[1u8, 2, 3], CONTEXT, "Array").unwrap_err(),
+            CanonicalJsonError::NotAnObject
+        ));
+    }
+
+    #[test]
+    fn test_json_ld_reserved_fields() {
+        for name in ["@context", "@type"] {
+            let value = json!({ name: "https://attacker.example", "a": 1 });
+
+            assert!(matches!(
+                to_json_ld(&value, CONTEXT, "Value").unwrap_err(),
+                CanonicalJsonError::ReservedField(field) if field == name
+            ));
+        }
+    }
+}
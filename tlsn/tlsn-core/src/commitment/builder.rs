@@ -6,8 +6,8 @@ use utils::range::{RangeSet, ToRangeSet};
 
 use crate::{
     commitment::{
-        blake3::Blake3Commitment, Commitment, CommitmentId, CommitmentInfo, CommitmentKind,
-        TranscriptCommitments,
+        blake3::Blake3Commitment, Commitment, CommitmentId, CommitmentIndex, CommitmentInfo,
+        CommitmentKind, TranscriptCommitments,
     },
     merkle::MerkleTree,
     transcript::get_value_ids,
@@ -182,10 +182,13 @@ impl TranscriptCommitmentBuilder {
         let merkle_tree = MerkleTree::from_leaves(&merkle_leaves)
             .map_err(|_| TranscriptCommitmentBuilderError::NoCommitments)?;
 
+        let index = CommitmentIndex::new(&commitment_info);
+
         Ok(TranscriptCommitments {
             merkle_tree,
             commitments,
             commitment_info,
+            index,
         })
     }
 }
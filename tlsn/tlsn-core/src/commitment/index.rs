@@ -0,0 +1,245 @@
+use std::ops::Range;
+
+use crate::{
+    commitment::{CommitmentId, CommitmentInfo},
+    Direction,
+};
+
+/// An interval index over the ranges of transcript commitments.
+///
+/// Allows resolving which commitments cover a range of the transcript without scanning every
+/// commitment. The index is derived from the commitment info, so it is not serialized.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CommitmentIndex {
+    sent: RangeIndex,
+    received: RangeIndex,
+}
+
+impl CommitmentIndex {
+    /// Builds a new index from the given commitment info.
+    pub(crate) fn new<'a>(
+        commitment_info: impl IntoIterator<Item = (&'a CommitmentId, &'a CommitmentInfo)>,
+    ) -> Self {
+        let mut sent = Vec::new();
+        let mut received = Vec::new();
+        for (id, info) in commitment_info {
+            let entries = match info.direction() {
+                Direction::Sent => &mut sent,
+                Direction::Received => &mut received,
+            };
+
+            entries.extend(info.ranges().iter_ranges().map(|range| Entry {
+                start: range.start,
+                end: range.end,
+                id: *id,
+            }));
+        }
+
+        Self {
+            sent: RangeIndex::new(sent),
+            received: RangeIndex::new(received),
+        }
+    }
+
+    /// Returns the ids of the commitments which contain the given range in one of their
+    /// contiguous ranges, sorted in ascending order.
+    pub(crate) fn get_ids_by_range(
+        &self,
+        direction: Direction,
+        range: &Range<usize>,
+    ) -> Vec<CommitmentId> {
+        match direction {
+            Direction::Sent => self.sent.get_ids_by_range(range),
+            Direction::Received => self.received.get_ids_by_range(range),
+        }
+    }
+}
+
+/// A range of a commitment.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    start: usize,
+    end: usize,
+    id: CommitmentId,
+}
+
+/// An augmented interval tree over the ranges of one direction.
+///
+/// The tree is stored implicitly in `entries`, which are sorted by their start: the subtree
+/// spanning `entries[lo..hi]` is rooted at the middle entry, and `max_end` holds the maximum end
+/// within each subtree at the index of its root. A query descends only into subtrees which can
+/// contain the range, so it takes `O((k + 1) log n)` for `k` matching entries.
+#[derive(Debug, Clone, Default)]
+struct RangeIndex {
+    entries: Vec<Entry>,
+    max_end: Vec<usize>,
+}
+
+impl RangeIndex {
+    fn new(mut entries: Vec<Entry>) -> Self {
+        entries.sort_by_key(|entry| (entry.start, entry.id));
+
+        let mut max_end = vec![0; entries.len()];
+        build(&entries, &mut max_end, 0, entries.len());
+
+        Self { entries, max_end }
+    }
+
+    fn get_ids_by_range(&self, range: &Range<usize>) -> Vec<CommitmentId> {
+        if range.is_empty() {
+            return Vec::new();
+        }
+
+        let mut ids = Vec::new();
+        self.query(0, self.entries.len(), range, &mut ids);
+
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    /// Collects the ids of the entries in `entries[lo..hi]` which contain the range.
+    fn query(&self, lo: usize, hi: usize, range: &Range<usize>, ids: &mut Vec<CommitmentId>) {
+        if lo >= hi {
+            return;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+
+        // None of the entries in this subtree reach the end of the range.
+        if self.max_end[mid] < range.end {
+            return;
+        }
+
+        self.query(lo, mid, range, ids);
+
+        // The entries right of `mid` start at or after it, so if it starts after the range
+        // none of them contain it.
+        let entry = &self.entries[mid];
+        if entry.start <= range.start {
+            if entry.end >= range.end {
+                ids.push(entry.id);
+            }
+
+            self.query(mid + 1, hi, range, ids);
+        }
+    }
+}
+
+/// Computes `max_end` for the subtree spanning `entries[lo..hi]`, returning its maximum end.
+fn build(entries: &[Entry], max_end: &mut [usize], lo: usize, hi: usize) -> usize {
+    if lo >= hi {
+        return 0;
+    }
+
+    let mid = lo + (hi - lo) / 2;
+    let left = build(entries, max_end, lo, mid);
+    let right = build(entries, max_end, mid + 1, hi);
+
+    max_end[mid] = entries[mid].end.max(left).max(right);
+    max_end[mid]
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::range::RangeSet;
+
+    use crate::{
+        commitment::{CommitmentKind, TranscriptCommitmentBuilder, TranscriptCommitments},
+        fixtures,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_get_ids_by_range() {
+        let info = [
+            (Direction::Sent, RangeSet::from([0..4, 8..12])),
+            (Direction::Sent, RangeSet::from([2..10])),
+            (Direction::Received, RangeSet::from([0..20])),
+            (Direction::Sent, RangeSet::from([9..10])),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(id, (direction, ranges))| {
+            (
+                CommitmentId::new(id as u32),
+                CommitmentInfo::new(CommitmentKind::Blake3, ranges, direction),
+            )
+        })
+        .collect::<Vec<_>>();
+
+        let index = CommitmentIndex::new(info.iter().map(|(id, info)| (id, info)));
+        let ids = |direction, range| {
+            index
+                .get_ids_by_range(direction, &range)
+                .into_iter()
+                .map(CommitmentId::to_inner)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(Direction::Sent, 0..1), vec![0]);
+        assert_eq!(ids(Direction::Sent, 2..4), vec![0, 1]);
+        assert_eq!(ids(Direction::Sent, 3..5), vec![1]);
+        assert_eq!(ids(Direction::Sent, 9..10), vec![0, 1, 3]);
+        assert_eq!(ids(Direction::Sent, 4..9), vec![1]);
+        assert_eq!(ids(Direction::Sent, 11..13), Vec::<u32>::new());
+        assert_eq!(ids(Direction::Sent, 2..2), Vec::<u32>::new());
+        assert_eq!(ids(Direction::Received, 5..15), vec![2]);
+    }
+
+    #[test]
+    fn test_get_ids_by_range_wide_commitment() {
+        // A commitment spanning the whole transcript must not hide the narrow ones.
+        let info = std::iter::once(RangeSet::from([0..1000]))
+            .chain((0..100).map(|i| RangeSet::from([i * 10..i * 10 + 5])))
+            .enumerate()
+            .map(|(id, ranges)| {
+                (
+                    CommitmentId::new(id as u32),
+                    CommitmentInfo::new(CommitmentKind::Blake3, ranges, Direction::Sent),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let index = CommitmentIndex::new(info.iter().map(|(id, info)| (id, info)));
+
+        for i in 0..100 {
+            let ids = index.get_ids_by_range(Direction::Sent, &(i * 10 + 1..i * 10 + 4));
+            assert_eq!(
+                ids,
+                vec![CommitmentId::new(0), CommitmentId::new(i as u32 + 1)]
+            );
+
+            let ids = index.get_ids_by_range(Direction::Sent, &(i * 10 + 4..i * 10 + 6));
+            assert_eq!(ids, vec![CommitmentId::new(0)]);
+        }
+    }
+
+    #[test]
+    fn test_index_rebuilt_on_deserialize() {
+        const TX: &[u8] = b"some sent data";
+        const RX: &[u8] = b"some received data";
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+        let id_sent = builder.commit_sent(&(0..9)).unwrap();
+        let id_recv = builder.commit_recv(&(5..13)).unwrap();
+        let commitments = builder.build().unwrap();
+
+        let bytes = bincode::serialize(&commitments).unwrap();
+        let commitments: TranscriptCommitments = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(
+            commitments.get_ids_by_range(Direction::Sent, &(5..9)),
+            vec![id_sent]
+        );
+        assert_eq!(
+            commitments.get_ids_by_range(Direction::Received, &(5..9)),
+            vec![id_recv]
+        );
+    }
+}
@@ -3,8 +3,9 @@
 /// BLAKE3 commitments.
 pub mod blake3;
 mod builder;
+mod index;
 
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range};
 
 use bimap::BiMap;
 use mpz_core::hash::Hash;
@@ -19,6 +20,8 @@ use crate::{
 
 pub use builder::{TranscriptCommitmentBuilder, TranscriptCommitmentBuilderError};
 
+use index::CommitmentIndex;
+
 /// A commitment id.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct CommitmentId(u32);
@@ -148,16 +151,41 @@ impl CommitmentOpening {
 
 /// A collection of transcript commitments.
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "UnindexedTranscriptCommitments")]
 pub struct TranscriptCommitments {
     /// A Merkle tree of commitments. Each commitment's index in the tree matches its `CommitmentId`.
     merkle_tree: MerkleTree,
     commitments: HashMap<CommitmentId, Commitment>,
     /// Information about the above `commitments`.
     commitment_info: BiMap<CommitmentId, CommitmentInfo>,
+    /// An index over the ranges of the above `commitment_info`, rebuilt when deserialized.
+    #[serde(skip)]
+    index: CommitmentIndex,
 }
 
 opaque_debug::implement!(TranscriptCommitments);
 
+/// The serialized fields of [`TranscriptCommitments`].
+#[derive(Deserialize)]
+struct UnindexedTranscriptCommitments {
+    merkle_tree: MerkleTree,
+    commitments: HashMap<CommitmentId, Commitment>,
+    commitment_info: BiMap<CommitmentId, CommitmentInfo>,
+}
+
+impl From<UnindexedTranscriptCommitments> for TranscriptCommitments {
+    fn from(value: UnindexedTranscriptCommitments) -> Self {
+        let index = CommitmentIndex::new(&value.commitment_info);
+
+        Self {
+            merkle_tree: value.merkle_tree,
+            commitments: value.commitments,
+            commitment_info: value.commitment_info,
+            index,
+        }
+    }
+}
+
 impl TranscriptCommitments {
     /// Returns the merkle tree of the commitments.
     pub fn merkle_tree(&self) -> &MerkleTree {
@@ -190,4 +218,20 @@ impl TranscriptCommitments {
     pub fn get_info(&self, id: &CommitmentId) -> Option<&CommitmentInfo> {
         self.commitment_info.get_by_left(id)
     }
+
+    /// Returns an iterator over the info of all commitments, in no particular order.
+    #[cfg(any(test, feature = "fixtures"))]
+    pub(crate) fn iter_info(&self) -> impl Iterator<Item = (&CommitmentId, &CommitmentInfo)> {
+        self.commitment_info.iter()
+    }
+
+    /// Returns the ids of the commitments which contain the given range of the transcript in one
+    /// of their contiguous ranges, sorted in ascending order.
+    pub fn get_ids_by_range(
+        &self,
+        direction: Direction,
+        range: &Range<usize>,
+    ) -> Vec<CommitmentId> {
+        self.index.get_ids_by_range(direction, range)
+    }
 }
@@ -6,6 +6,9 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
+pub mod anonymize;
+#[cfg(feature = "canonical-json")]
+pub mod canonical;
 pub mod commitment;
 #[cfg(any(test, feature = "fixtures"))]
 pub mod fixtures;
@@ -17,7 +20,7 @@ mod signature;
 pub mod transcript;
 
 pub use session::{HandshakeSummary, NotarizedSession, SessionData, SessionHeader};
-pub use signature::{NotaryPublicKey, Signature};
+pub use signature::{NotaryPublicKey, Signature, SignatureAlgorithm};
 pub use transcript::{Direction, RedactedTranscript, Transcript, TranscriptSlice};
 
 use mpz_garble_core::{encoding_state, EncodedValue};
@@ -3,7 +3,20 @@
 use serde::{Deserialize, Serialize};
 use utils::range::RangeSet;
 
-use crate::{merkle::MerkleRoot, proof::SessionInfo, signature::Signature, SessionHeader};
+use crate::{
+    commitment::CommitmentKind,
+    merkle::MerkleRoot,
+    proof::SessionInfo,
+    session::{PreviousHeaderCommitment, SignedCompactHeader},
+    signature::{Signature, SignatureAlgorithm},
+    NotaryPublicKey, SessionHeader,
+};
+
+/// The version of the protocol implemented by this crate.
+///
+/// This must be incremented once in each release which breaks compatibility with the previous
+/// release, not for every breaking change in between.
+pub const PROTOCOL_VERSION: u16 = 1;
 
 /// Top-level enum for all messages
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,6 +31,12 @@ pub enum TlsnMessage {
     SessionInfo(SessionInfo),
     /// Information about the values the prover wants to prove
     ProvingInfo(ProvingInfo),
+    /// The sending party aborted the session.
+    Abort(Abort),
+    /// The protocol version and capabilities of the sending party.
+    Hello(Hello),
+    /// An optional commitment to a previous session header of the Prover.
+    PreviousHeader(Option<PreviousHeaderCommitment>),
 }
 
 /// A signed session header.
@@ -27,6 +46,10 @@ pub struct SignedSessionHeader {
     pub header: SessionHeader,
     /// The notary's signature
     pub signature: Signature,
+    /// The notary's public key, which both signatures are made with
+    pub notary_public_key: NotaryPublicKey,
+    /// A compact summary of the header for light clients, also signed by the notary
+    pub compact: SignedCompactHeader,
 }
 
 /// Information about the values the prover wants to prove
@@ -39,3 +62,166 @@ pub struct ProvingInfo {
     /// Purported cleartext values
     pub cleartext: Vec<u8>,
 }
+
+/// A message sent by a party to abort the session, informing the other party of the reason.
+#[derive(Debug, Clone, Serialize, Deserialize, thiserror::Error)]
+#[error("{code:?}: {message}")]
+pub struct Abort {
+    /// The reason for aborting.
+    pub code: AbortCode,
+    /// A human readable description of the reason.
+    pub message: String,
+}
+
+impl Abort {
+    /// Creates a new abort message.
+    pub fn new(code: AbortCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// Returns `true` if the session may succeed if it is retried.
+    pub fn is_retryable(&self) -> bool {
+        self.code.is_retryable()
+    }
+}
+
+/// The reason a session was aborted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum AbortCode {
+    /// The session violates the policy of the aborting party, e.g. a data limit.
+    PolicyViolation,
+    /// The aborting party received a message it did not expect.
+    UnexpectedMessage,
+    /// The session timed out.
+    Timeout,
+    /// An internal error occurred in the aborting party.
+    Internal,
+    /// The parties are not compatible, see [`Hello`].
+    Incompatible,
+}
+
+impl AbortCode {
+    /// Returns `true` if a session aborted with this code may succeed if it is retried.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, AbortCode::Timeout | AbortCode::Internal)
+    }
+}
+
+/// The protocol version and capabilities of a party, exchanged when the connection is set up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hello {
+    /// The protocol version.
+    pub version: u16,
+    /// The kinds of transcript commitments which are supported.
+    pub commitment_kinds: Vec<CommitmentKind>,
+    /// The algorithms of the notary signature which are supported.
+    ///
+    /// The Prover lists the algorithms it accepts, and the Verifier the algorithms it may sign
+    /// with.
+    pub signature_algorithms: Vec<SignatureAlgorithm>,
+}
+
+impl Default for Hello {
+    fn default() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            commitment_kinds: vec![CommitmentKind::Blake3],
+            signature_algorithms: vec![SignatureAlgorithm::P256, SignatureAlgorithm::K256],
+        }
+    }
+}
+
+impl Hello {
+    /// Checks that this party is compatible with the peer.
+    ///
+    /// # Arguments
+    ///
+    /// * `peer` - The hello message received from the peer.
+    pub fn check_compatible(&self, peer: &Hello) -> Result<(), IncompatibleError> {
+        if self.version != peer.version {
+            return Err(IncompatibleError::Version {
+                expected: self.version,
+                received: peer.version,
+            });
+        }
+
+        if !self
+            .commitment_kinds
+            .iter()
+            .any(|kind| peer.commitment_kinds.contains(kind))
+        {
+            return Err(IncompatibleError::CommitmentKinds);
+        }
+
+        if !self
+            .signature_algorithms
+            .iter()
+            .any(|algorithm| peer.signature_algorithms.contains(algorithm))
+        {
+            return Err(IncompatibleError::SignatureAlgorithms);
+        }
+
+        Ok(())
+    }
+}
+
+/// An error for [`Hello::check_compatible`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum IncompatibleError {
+    /// The parties use different protocol versions.
+    #[error("protocol version mismatch: expected {expected}, received {received}")]
+    Version {
+        /// The version of this party.
+        expected: u16,
+        /// The version of the peer.
+        received: u16,
+    },
+    /// The parties do not support any common commitment kind.
+    #[error("no commitment kind is supported by both parties")]
+    CommitmentKinds,
+    /// The parties do not support any common signature algorithm.
+    #[error("no signature algorithm is supported by both parties")]
+    SignatureAlgorithms,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hello_compatibility() {
+        let hello = Hello::default();
+
+        assert!(hello.check_compatible(&Hello::default()).is_ok());
+        assert!(matches!(
+            hello.check_compatible(&Hello {
+                version: PROTOCOL_VERSION + 1,
+                ..Default::default()
+            }),
+            Err(IncompatibleError::Version { .. })
+        ));
+        assert!(matches!(
+            hello.check_compatible(&Hello {
+                commitment_kinds: vec![],
+                ..Default::default()
+            }),
+            Err(IncompatibleError::CommitmentKinds)
+        ));
+        assert!(matches!(
+            Hello {
+                signature_algorithms: vec![SignatureAlgorithm::P256],
+                ..Default::default()
+            }
+            .check_compatible(&Hello {
+                signature_algorithms: vec![SignatureAlgorithm::K256],
+                ..Default::default()
+            }),
+            Err(IncompatibleError::SignatureAlgorithms)
+        ));
+    }
+}
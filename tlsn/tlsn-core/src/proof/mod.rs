@@ -1,11 +1,17 @@
 //! Different types of proofs used in the TLSNotary protocol.
 
+mod policy;
 mod session;
 mod substrings;
 
+pub use policy::{
+    CertificatePolicy, CertificatePolicyBuilder, CertificatePolicyBuilderError,
+    CertificatePolicyError,
+};
 pub use session::{default_cert_verifier, SessionInfo, SessionProof, SessionProofError};
 pub use substrings::{
-    SubstringsProof, SubstringsProofBuilder, SubstringsProofBuilderError, SubstringsProofError,
+    StreamingSubstringsProof, SubstringsProof, SubstringsProofBuilder, SubstringsProofBuilderError,
+    SubstringsProofError, DEFAULT_MAX_RANGES,
 };
 
 use serde::{Deserialize, Serialize};
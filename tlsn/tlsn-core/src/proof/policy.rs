@@ -0,0 +1,322 @@
+use tls_core::{
+    handshake::HandshakeData,
+    msgs::enums::{NamedGroup, SignatureScheme},
+};
+
+/// An error for [`CertificatePolicy`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum CertificatePolicyError {
+    /// The server signed the key exchange with a signature scheme which is not allowed.
+    #[error("signature scheme is not allowed: {0:?}")]
+    SignatureScheme(SignatureScheme),
+    /// The server's RSA key is smaller than allowed.
+    #[error("RSA key size is below the minimum: {bits} < {min}")]
+    RsaKeySize {
+        /// The size of the server's key in bits.
+        bits: usize,
+        /// The minimum allowed key size in bits.
+        min: usize,
+    },
+    /// The server's EC key is on a curve which is not allowed.
+    #[error("certificate key curve is not allowed: {0:?}")]
+    Curve(NamedGroup),
+    /// The server's EC key is on a curve which is not known, so it can not be allowed.
+    #[error("certificate key curve is unknown")]
+    UnknownCurve,
+    /// The server's certificate key could not be parsed.
+    #[error("invalid certificate key")]
+    InvalidCertificateKey,
+    /// The server's certificate chain is longer than allowed.
+    #[error("certificate chain is too long: {len} > {max}")]
+    ChainLength {
+        /// The length of the server's certificate chain.
+        len: usize,
+        /// The maximum allowed length.
+        max: usize,
+    },
+}
+
+/// A policy for the server's certificate and key exchange, checked in addition to the
+/// certificate verifier.
+///
+/// The default policy does not restrict anything.
+#[derive(Debug, Clone, Default, derive_builder::Builder)]
+pub struct CertificatePolicy {
+    /// The signature schemes which the server may use to sign the key exchange. If `None`, any
+    /// scheme supported by the certificate verifier is allowed.
+    #[builder(setter(strip_option), default)]
+    signature_schemes: Option<Vec<SignatureScheme>>,
+    /// The minimum size of the RSA key in the server's certificate in bits.
+    #[builder(default)]
+    min_rsa_key_bits: usize,
+    /// The curves which the EC key in the server's certificate may use. If `None`, any curve is
+    /// allowed.
+    #[builder(setter(strip_option), default)]
+    curves: Option<Vec<NamedGroup>>,
+    /// The maximum length of the server's certificate chain, including the end-entity certificate.
+    #[builder(setter(strip_option), default)]
+    max_chain_length: Option<usize>,
+}
+
+impl CertificatePolicy {
+    /// Creates a new builder for `CertificatePolicy`.
+    pub fn builder() -> CertificatePolicyBuilder {
+        CertificatePolicyBuilder::default()
+    }
+
+    /// Checks the handshake against the policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `handshake_data` - The handshake data.
+    pub fn check(&self, handshake_data: &HandshakeData) -> Result<(), CertificatePolicyError> {
+        let kx_sig = handshake_data.server_kx_details().kx_sig();
+
+        if let Some(schemes) = &self.signature_schemes {
+            if !schemes.contains(&kx_sig.scheme) {
+                return Err(CertificatePolicyError::SignatureScheme(kx_sig.scheme));
+            }
+        }
+
+        let cert_chain = handshake_data.server_cert_details().cert_chain();
+
+        // The key is only parsed if the policy restricts it.
+        if self.min_rsa_key_bits > 0 || self.curves.is_some() {
+            let key = cert_chain
+                .first()
+                .and_then(|cert| parse_server_key(&cert.0))
+                .ok_or(CertificatePolicyError::InvalidCertificateKey)?;
+
+            match key {
+                ServerKey::Rsa { bits } if bits < self.min_rsa_key_bits => {
+                    return Err(CertificatePolicyError::RsaKeySize {
+                        bits,
+                        min: self.min_rsa_key_bits,
+                    });
+                }
+                ServerKey::Ec(curve) => {
+                    if let Some(curves) = &self.curves {
+                        let curve = curve.ok_or(CertificatePolicyError::UnknownCurve)?;
+                        if !curves.contains(&curve) {
+                            return Err(CertificatePolicyError::Curve(curve));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let len = cert_chain.len();
+        if let Some(max) = self.max_chain_length {
+            if len > max {
+                return Err(CertificatePolicyError::ChainLength { len, max });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+const SEQUENCE: u8 = 0x30;
+const INTEGER: u8 = 0x02;
+const BIT_STRING: u8 = 0x03;
+const OID: u8 = 0x06;
+const EXPLICIT_VERSION: u8 = 0xa0;
+
+/// 1.2.840.113549.1.1.1
+const RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+/// 1.2.840.10045.2.1
+const EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+/// 1.2.840.10045.3.1.7
+const SECP256R1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+/// 1.3.132.0.34
+const SECP384R1: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+/// 1.3.132.0.35
+const SECP521R1: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x23];
+
+/// The public key of a certificate.
+#[derive(Debug, PartialEq, Eq)]
+enum ServerKey {
+    /// An RSA key with a modulus of the given size.
+    Rsa { bits: usize },
+    /// An EC key on the given curve, if it is known.
+    Ec(Option<NamedGroup>),
+    /// A key of another type, e.g. Ed25519.
+    Other,
+}
+
+/// Parses the public key from the subject public key info of a DER encoded X.509 certificate.
+fn parse_server_key(cert: &[u8]) -> Option<ServerKey> {
+    let (cert, _) = expect_der(cert, SEQUENCE)?;
+    let (mut tbs, _) = expect_der(cert, SEQUENCE)?;
+
+    // Skip the version, serial number, signature algorithm, issuer, validity and subject.
+    if tbs.first() == Some(&EXPLICIT_VERSION) {
+        tbs = read_der(tbs)?.2;
+    }
+    for _ in 0..5 {
+        tbs = read_der(tbs)?.2;
+    }
+
+    let (spki, _) = expect_der(tbs, SEQUENCE)?;
+    let (algorithm, spki) = expect_der(spki, SEQUENCE)?;
+    let (oid, params) = expect_der(algorithm, OID)?;
+
+    match oid {
+        RSA_ENCRYPTION => {
+            // The first byte of the bit string is the number of unused bits.
+            let (key, _) = expect_der(spki, BIT_STRING)?;
+            let (key, _) = expect_der(key.get(1..)?, SEQUENCE)?;
+            let (modulus, _) = expect_der(key, INTEGER)?;
+            let modulus = &modulus[modulus.iter().position(|&b| b != 0)?..];
+
+            Some(ServerKey::Rsa {
+                bits: modulus.len() * 8 - modulus[0].leading_zeros() as usize,
+            })
+        }
+        EC_PUBLIC_KEY => {
+            let (curve, _) = expect_der(params, OID)?;
+
+            Some(ServerKey::Ec(match curve {
+                SECP256R1 => Some(NamedGroup::secp256r1),
+                SECP384R1 => Some(NamedGroup::secp384r1),
+                SECP521R1 => Some(NamedGroup::secp521r1),
+                _ => None,
+            }))
+        }
+        _ => Some(ServerKey::Other),
+    }
+}
+
+/// Reads a DER encoded element, returning its tag, its contents and the remaining input.
+fn read_der(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, input) = input.split_first()?;
+    let (&first, input) = input.split_first()?;
+
+    let (len, input) = if first < 0x80 {
+        (first as usize, input)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 || input.len() < n {
+            return None;
+        }
+        let (len, input) = input.split_at(n);
+        (len.iter().fold(0, |len, &b| (len << 8) | b as usize), input)
+    };
+
+    if input.len() < len {
+        return None;
+    }
+    let (contents, rest) = input.split_at(len);
+
+    Some((tag, contents, rest))
+}
+
+/// Reads a DER encoded element with the given tag, returning its contents and the remaining input.
+fn expect_der(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (actual, contents, rest) = read_der(input)?;
+    (actual == tag).then_some((contents, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tls_core::cert::ServerCertDetails;
+
+    use crate::fixtures::{self, cert::appliedzkp};
+
+    /// Returns handshake data with the certificate chain of appliedzkp.org, which has a P-256 key.
+    fn ec_handshake_data() -> HandshakeData {
+        let data = appliedzkp();
+        HandshakeData::new(
+            ServerCertDetails::new(vec![data.ee, data.inter, data.ca], vec![], None),
+            fixtures::server_kx_details(),
+            fixtures::client_random(),
+            fixtures::server_random(),
+        )
+    }
+
+    #[test]
+    fn test_parse_server_key() {
+        assert_eq!(
+            parse_server_key(&fixtures::cert::tlsnotary().ee.0),
+            Some(ServerKey::Rsa { bits: 2048 })
+        );
+        assert_eq!(
+            parse_server_key(&appliedzkp().ee.0),
+            Some(ServerKey::Ec(Some(NamedGroup::secp256r1)))
+        );
+        assert_eq!(parse_server_key(&[0x30, 0x03, 0x02]), None);
+    }
+
+    #[test]
+    fn test_certificate_policy() {
+        let handshake_data = fixtures::handshake_data();
+        let scheme = handshake_data.server_kx_details().kx_sig().scheme;
+
+        assert!(CertificatePolicy::default().check(&handshake_data).is_ok());
+
+        let policy = CertificatePolicy::builder()
+            .signature_schemes(vec![scheme])
+            .min_rsa_key_bits(2048)
+            .curves(vec![NamedGroup::secp256r1])
+            .max_chain_length(3)
+            .build()
+            .unwrap();
+        assert!(policy.check(&handshake_data).is_ok());
+        assert!(policy.check(&ec_handshake_data()).is_ok());
+
+        let policy = CertificatePolicy::builder()
+            .signature_schemes(vec![SignatureScheme::ED25519])
+            .build()
+            .unwrap();
+        assert!(matches!(
+            policy.check(&handshake_data),
+            Err(CertificatePolicyError::SignatureScheme(_))
+        ));
+
+        let policy = CertificatePolicy::builder()
+            .max_chain_length(1)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            policy.check(&handshake_data),
+            Err(CertificatePolicyError::ChainLength { max: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_rsa_key_size() {
+        let policy = CertificatePolicy::builder()
+            .min_rsa_key_bits(3072)
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            policy.check(&fixtures::handshake_data()),
+            Err(CertificatePolicyError::RsaKeySize {
+                bits: 2048,
+                min: 3072
+            })
+        ));
+        // The minimum only applies to RSA keys.
+        assert!(policy.check(&ec_handshake_data()).is_ok());
+    }
+
+    #[test]
+    fn test_curve() {
+        let policy = CertificatePolicy::builder()
+            .curves(vec![NamedGroup::secp384r1])
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            policy.check(&ec_handshake_data()),
+            Err(CertificatePolicyError::Curve(NamedGroup::secp256r1))
+        ));
+        // The allowed curves only apply to EC keys.
+        assert!(policy.check(&fixtures::handshake_data()).is_ok());
+    }
+}
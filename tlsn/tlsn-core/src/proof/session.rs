@@ -11,6 +11,7 @@ use tls_core::{
 };
 
 use crate::{
+    proof::{CertificatePolicy, CertificatePolicyError},
     session::SessionHeader,
     signature::{Signature, SignatureVerifyError},
     HandshakeSummary, NotaryPublicKey, ServerName,
@@ -35,6 +36,9 @@ pub enum SessionProofError {
     /// Invalid server certificate
     #[error("server certificate verification failed: {0}")]
     InvalidServerCertificate(String),
+    /// The server certificate or key exchange violates the certificate policy
+    #[error("certificate policy violation: {0}")]
+    CertificatePolicy(#[from] CertificatePolicyError),
 }
 
 /// A session proof which is created from a [crate::session::NotarizedSession]
@@ -61,6 +65,27 @@ impl SessionProof {
         &self,
         notary_public_key: impl Into<NotaryPublicKey>,
         cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<(), SessionProofError> {
+        self.verify_with_policy(
+            notary_public_key,
+            cert_verifier,
+            &CertificatePolicy::default(),
+        )
+    }
+
+    /// Verify the session proof, additionally checking the server certificate and key exchange
+    /// against a certificate policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `notary_public_key` - The public key of the notary.
+    /// * `cert_verifier` - The certificate verifier.
+    /// * `policy` - The certificate policy.
+    pub fn verify_with_policy(
+        &self,
+        notary_public_key: impl Into<NotaryPublicKey>,
+        cert_verifier: &impl ServerCertVerifier,
+        policy: &CertificatePolicy,
     ) -> Result<(), SessionProofError> {
         // Verify notary signature
         let signature = self
@@ -69,8 +94,11 @@ impl SessionProof {
             .ok_or(SessionProofError::MissingNotarySignature)?;
 
         signature.verify(&self.header.to_bytes(), notary_public_key)?;
-        self.session_info
-            .verify(self.header.handshake_summary(), cert_verifier)?;
+        self.session_info.verify_with_policy(
+            self.header.handshake_summary(),
+            cert_verifier,
+            policy,
+        )?;
 
         Ok(())
     }
@@ -105,6 +133,26 @@ impl SessionInfo {
         &self,
         handshake_summary: &HandshakeSummary,
         cert_verifier: &impl ServerCertVerifier,
+    ) -> Result<(), SessionProofError> {
+        self.verify_with_policy(
+            handshake_summary,
+            cert_verifier,
+            &CertificatePolicy::default(),
+        )
+    }
+
+    /// Verify the session info, additionally checking it against a certificate policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `handshake_summary` - The handshake summary signed by the notary.
+    /// * `cert_verifier` - The certificate verifier.
+    /// * `policy` - The certificate policy.
+    pub fn verify_with_policy(
+        &self,
+        handshake_summary: &HandshakeSummary,
+        cert_verifier: &impl ServerCertVerifier,
+        policy: &CertificatePolicy,
     ) -> Result<(), SessionProofError> {
         // Verify server name
         let server_name = TlsServerName::try_from(self.server_name.as_ref())
@@ -125,6 +173,8 @@ impl SessionInfo {
             )
             .map_err(|e| SessionProofError::InvalidServerCertificate(e.to_string()))?;
 
+        policy.check(self.handshake_decommitment.data())?;
+
         Ok(())
     }
 
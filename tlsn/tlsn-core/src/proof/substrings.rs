@@ -12,10 +12,19 @@ use crate::{
 };
 use mpz_circuits::types::ValueType;
 use mpz_garble_core::Encoder;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{
+    ser::{SerializeMap, SerializeStruct, Serializer},
+    Deserialize, Serialize,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    ops::Range,
+};
 use utils::range::{RangeDisjoint, RangeSet, RangeUnion, ToRangeSet};
 
+/// The default maximum number of ranges which can be revealed by a [`SubstringsProofBuilder`].
+pub const DEFAULT_MAX_RANGES: usize = 1 << 16;
+
 /// An error for [`SubstringsProofBuilder`]
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
@@ -32,14 +41,28 @@ pub enum SubstringsProofBuilderError {
     /// Attempted to add a commitment with a duplicate id.
     #[error("commitment with id {0:?} already exists")]
     DuplicateCommitmentId(CommitmentId),
+    /// The maximum number of revealed ranges was exceeded.
+    #[error("revealed ranges exceed the maximum allowed: {count} > {limit}")]
+    MaxRangesExceeded {
+        /// The maximum number of ranges.
+        limit: usize,
+        /// The number of ranges which would have been revealed.
+        count: usize,
+    },
 }
 
 /// A builder for [`SubstringsProof`]
+///
+/// Openings are only created when the proof is built, see [`build_streaming`](Self::build_streaming)
+/// for a way to serialize a proof without holding all of its openings in memory.
 pub struct SubstringsProofBuilder<'a> {
     commitments: &'a TranscriptCommitments,
     transcript_tx: &'a Transcript,
     transcript_rx: &'a Transcript,
-    openings: HashMap<CommitmentId, (CommitmentInfo, CommitmentOpening)>,
+    /// Ids of the commitments to open, sorted by their index in the Merkle tree.
+    revealed: BTreeSet<CommitmentId>,
+    range_count: usize,
+    max_ranges: usize,
 }
 
 opaque_debug::implement!(SubstringsProofBuilder<'_>);
@@ -55,7 +78,9 @@ impl<'a> SubstringsProofBuilder<'a> {
             commitments,
             transcript_tx,
             transcript_rx,
-            openings: HashMap::default(),
+            revealed: BTreeSet::default(),
+            range_count: 0,
+            max_ranges: DEFAULT_MAX_RANGES,
         }
     }
 
@@ -64,6 +89,14 @@ impl<'a> SubstringsProofBuilder<'a> {
         self.commitments
     }
 
+    /// Sets the maximum number of ranges which can be revealed.
+    ///
+    /// Defaults to [`DEFAULT_MAX_RANGES`].
+    pub fn set_max_ranges(&mut self, max_ranges: usize) -> &mut Self {
+        self.max_ranges = max_ranges;
+        self
+    }
+
     /// Reveals data corresponding to the provided ranges in the sent direction.
     pub fn reveal_sent(
         &mut self,
@@ -113,50 +146,178 @@ impl<'a> SubstringsProofBuilder<'a> {
             .expect("info exists if commitment exists");
 
         #[allow(irrefutable_let_patterns)]
-        let Commitment::Blake3(commitment) = commitment
+        let Commitment::Blake3(_) = commitment
         else {
             return Err(SubstringsProofBuilderError::InvalidCommitmentType(id));
         };
 
-        let transcript = match info.direction() {
-            Direction::Sent => self.transcript_tx,
-            Direction::Received => self.transcript_rx,
-        };
-
-        let data = transcript.get_bytes_in_ranges(info.ranges());
+        let range_count = self.range_count + info.ranges().iter_ranges().count();
+        if range_count > self.max_ranges {
+            return Err(SubstringsProofBuilderError::MaxRangesExceeded {
+                limit: self.max_ranges,
+                count: range_count,
+            });
+        }
 
         // add commitment to openings and return an error if it is already present
-        if self
-            .openings
-            .insert(id, (info.clone(), commitment.open(data).into()))
-            .is_some()
-        {
+        if !self.revealed.insert(id) {
             return Err(SubstringsProofBuilderError::DuplicateCommitmentId(id));
         }
 
+        self.range_count = range_count;
+
         Ok(self)
     }
 
+    /// Reveals a range of the transcript with the smallest commitment which contains it.
+    ///
+    /// Unlike [`reveal`](Self::reveal), the commitment does not have to match the range exactly,
+    /// so data around the range may be revealed as well. Nothing is added if the range is
+    /// already revealed, see [`is_revealed`](Self::is_revealed).
+    pub fn reveal_covering(
+        &mut self,
+        range: &Range<usize>,
+        direction: Direction,
+    ) -> Result<&mut Self, SubstringsProofBuilderError> {
+        let ids = self.commitments.get_ids_by_range(direction, range);
+        if ids.iter().any(|id| self.revealed.contains(id)) {
+            return Ok(self);
+        }
+
+        let id = ids
+            .into_iter()
+            .min_by_key(|id| {
+                self.commitments
+                    .get_info(id)
+                    .expect("index only contains existing commitments")
+                    .ranges()
+                    .len()
+            })
+            .ok_or(SubstringsProofBuilderError::MissingCommitment)?;
+
+        self.reveal_by_id(id)
+    }
+
+    /// Returns `true` if each of the ranges is contained in a commitment which is revealed.
+    pub fn is_revealed(&self, ranges: &dyn ToRangeSet<usize>, direction: Direction) -> bool {
+        ranges.to_range_set().iter_ranges().all(|range| {
+            self.commitments
+                .get_ids_by_range(direction, &range)
+                .iter()
+                .any(|id| self.revealed.contains(id))
+        })
+    }
+
     /// Builds the [`SubstringsProof`]
     pub fn build(self) -> Result<SubstringsProof, SubstringsProofBuilderError> {
-        let Self {
-            commitments,
+        let inclusion_proof = self.inclusion_proof();
+
+        let openings = self
+            .revealed
+            .iter()
+            .map(|id| {
+                let (info, opening) = self.open(*id);
+                (*id, (info.clone(), opening))
+            })
+            .collect();
+
+        Ok(SubstringsProof {
             openings,
-            ..
-        } = self;
+            inclusion_proof,
+        })
+    }
+
+    /// Builds a [`StreamingSubstringsProof`].
+    ///
+    /// This is an alternative to [`build`](Self::build) which bounds the memory used by the prover
+    /// when revealing a large number of ranges. The returned value serializes identically to a
+    /// [`SubstringsProof`], but each opening is only created once the serializer reaches it.
+    pub fn build_streaming(
+        self,
+    ) -> Result<StreamingSubstringsProof<'a>, SubstringsProofBuilderError> {
+        let inclusion_proof = self.inclusion_proof();
+
+        Ok(StreamingSubstringsProof {
+            builder: self,
+            inclusion_proof,
+        })
+    }
 
-        let mut indices = openings
-            .keys()
+    fn inclusion_proof(&self) -> MerkleProof {
+        // Ids are sorted, so are the indices.
+        let indices = self
+            .revealed
+            .iter()
             .map(|id| id.to_inner() as usize)
             .collect::<Vec<_>>();
-        indices.sort();
 
-        let inclusion_proof = commitments.merkle_tree().proof(&indices);
+        self.commitments.merkle_tree().proof(&indices)
+    }
 
-        Ok(SubstringsProof {
-            openings,
-            inclusion_proof,
-        })
+    /// Opens the commitment with the given id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the commitment was not validated in [`reveal_by_id`](Self::reveal_by_id).
+    fn open(&self, id: CommitmentId) -> (&'a CommitmentInfo, CommitmentOpening) {
+        let commitments = self.commitments;
+
+        let Some(Commitment::Blake3(commitment)) = commitments.get(&id) else {
+            unreachable!("commitment was validated when it was revealed");
+        };
+
+        let info = commitments
+            .get_info(&id)
+            .expect("info exists if commitment exists");
+
+        let transcript = match info.direction() {
+            Direction::Sent => self.transcript_tx,
+            Direction::Received => self.transcript_rx,
+        };
+
+        let data = transcript.get_bytes_in_ranges(info.ranges());
+
+        (info, commitment.open(data).into())
+    }
+}
+
+/// A [`SubstringsProof`] which creates its openings while it is being serialized.
+///
+/// This type serializes identically to [`SubstringsProof`], so it can be deserialized as one by the
+/// verifier. See [`SubstringsProofBuilder::build_streaming`].
+pub struct StreamingSubstringsProof<'a> {
+    builder: SubstringsProofBuilder<'a>,
+    inclusion_proof: MerkleProof,
+}
+
+opaque_debug::implement!(StreamingSubstringsProof<'_>);
+
+impl Serialize for StreamingSubstringsProof<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("SubstringsProof", 2)?;
+        state.serialize_field("openings", &StreamingOpenings(&self.builder))?;
+        state.serialize_field("inclusion_proof", &self.inclusion_proof)?;
+        state.end()
+    }
+}
+
+/// Serializes the openings of a builder as a map, creating one opening at a time.
+struct StreamingOpenings<'a, 'b>(&'b SubstringsProofBuilder<'a>);
+
+impl Serialize for StreamingOpenings<'_, '_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.0.revealed.len()))?;
+        for id in &self.0.revealed {
+            let (info, opening) = self.0.open(*id);
+            map.serialize_entry(id, &(info, &opening))?;
+        }
+        map.end()
     }
 }
 
@@ -197,6 +358,21 @@ pub struct SubstringsProof {
 opaque_debug::implement!(SubstringsProof);
 
 impl SubstringsProof {
+    /// Returns the ranges of the sent and received transcripts which are opened by this proof.
+    ///
+    /// The proof is not verified, so this must only be used for informational purposes.
+    pub fn revealed_ranges(&self) -> (RangeSet<usize>, RangeSet<usize>) {
+        let mut sent = RangeSet::default();
+        let mut recv = RangeSet::default();
+        for (info, _) in self.openings.values() {
+            match info.direction() {
+                Direction::Sent => sent = sent.union(info.ranges()),
+                Direction::Received => recv = recv.union(info.ranges()),
+            }
+        }
+        (sent, recv)
+    }
+
     /// Verifies this proof and, if successful, returns the redacted sent and received transcripts.
     ///
     /// # Arguments
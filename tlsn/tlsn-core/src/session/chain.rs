@@ -0,0 +1,235 @@
+use mpz_core::serialize::CanonicalSerialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::SessionHeader;
+
+/// Domain separator for commitments to a previous session header.
+const PREVIOUS_HEADER_DOMAIN: &[u8] = b"tlsn.previous-header";
+
+/// An error that can occur while verifying a chain of session headers.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HeaderChainError {
+    /// The chain does not contain any header.
+    #[error("header chain is empty")]
+    Empty,
+    /// The number of blinders does not match the number of links in the chain.
+    #[error("expected {expected} blinders for the header chain, got {actual}")]
+    BlinderCountMismatch {
+        /// The number of links in the chain.
+        expected: usize,
+        /// The number of blinders provided.
+        actual: usize,
+    },
+    /// A header does not commit to a previous header.
+    #[error("header {0} does not commit to a previous header")]
+    MissingLink(usize),
+    /// A header commits to a different previous header.
+    #[error("header {0} does not commit to the previous header in the chain")]
+    InvalidLink(usize),
+    /// A header is older than the previous header in the chain.
+    #[error("header {0} is older than the previous header in the chain")]
+    OutOfOrder(usize),
+}
+
+/// A blinded commitment to a previous [`SessionHeader`].
+///
+/// A Prover can include this commitment in a new session header to link it to a header it was
+/// issued before, e.g. to build a chain of recurring attestations. The commitment is blinded, so it
+/// does not reveal which header it commits to until the Prover discloses the blinder. Sessions
+/// which do not include it can not be linked to each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreviousHeaderCommitment([u8; 32]);
+
+impl PreviousHeaderCommitment {
+    /// Returns the commitment as bytes.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// The opening of a [`PreviousHeaderCommitment`], which is kept by the Prover.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviousHeaderOpening {
+    header: SessionHeader,
+    blinder: [u8; 32],
+}
+
+impl PreviousHeaderOpening {
+    /// Creates a new opening for the header.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The previous session header.
+    /// * `blinder` - The blinder, which must be sampled from a cryptographically secure RNG and
+    ///   kept secret until the link is disclosed.
+    pub fn new(header: SessionHeader, blinder: [u8; 32]) -> Self {
+        Self { header, blinder }
+    }
+
+    /// Returns the commitment to the previous header.
+    pub fn commit(&self) -> PreviousHeaderCommitment {
+        commit_previous_header(&self.header, &self.blinder)
+    }
+
+    /// Returns `true` if this opens the given commitment.
+    pub fn verify(&self, commitment: &PreviousHeaderCommitment) -> bool {
+        &self.commit() == commitment
+    }
+
+    /// Returns the previous header.
+    pub fn header(&self) -> &SessionHeader {
+        &self.header
+    }
+
+    /// Returns the blinder.
+    pub fn blinder(&self) -> &[u8; 32] {
+        &self.blinder
+    }
+}
+
+fn commit_previous_header(header: &SessionHeader, blinder: &[u8; 32]) -> PreviousHeaderCommitment {
+    let mut hasher = Sha256::new();
+    hasher.update(PREVIOUS_HEADER_DOMAIN);
+    hasher.update(blinder);
+    hasher.update(header.to_bytes());
+
+    PreviousHeaderCommitment(hasher.finalize().into())
+}
+
+/// Verifies that the session headers form a chain, from the oldest to the most recent.
+///
+/// Every header except the first must commit to the header before it, which is checked with the
+/// blinder of its commitment. The headers must be in chronological order.
+///
+/// This only checks the links between the headers, the headers themselves must be verified
+/// separately, e.g. with [`SessionProof::verify`](crate::proof::SessionProof::verify).
+///
+/// # Arguments
+///
+/// * `headers` - The session headers, from the oldest to the most recent.
+/// * `blinders` - The blinders of the commitments, where `blinders[i]` is the blinder used by
+///   `headers[i + 1]` to commit to `headers[i]`.
+pub fn verify_header_chain(
+    headers: &[SessionHeader],
+    blinders: &[[u8; 32]],
+) -> Result<(), HeaderChainError> {
+    if headers.is_empty() {
+        return Err(HeaderChainError::Empty);
+    }
+
+    if blinders.len() != headers.len() - 1 {
+        return Err(HeaderChainError::BlinderCountMismatch {
+            expected: headers.len() - 1,
+            actual: blinders.len(),
+        });
+    }
+
+    for (idx, (pair, blinder)) in headers.windows(2).zip(blinders).enumerate() {
+        let (previous, header) = (&pair[0], &pair[1]);
+        let idx = idx + 1;
+
+        let commitment = header
+            .previous()
+            .ok_or(HeaderChainError::MissingLink(idx))?;
+
+        if &commit_previous_header(previous, blinder) != commitment {
+            return Err(HeaderChainError::InvalidLink(idx));
+        }
+
+        if header.time() < previous.time() {
+            return Err(HeaderChainError::OutOfOrder(idx));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{fixtures, merkle::MerkleRoot, HandshakeSummary};
+
+    fn header(previous: Option<PreviousHeaderCommitment>) -> SessionHeader {
+        fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 20).with_previous(previous)
+    }
+
+    fn header_at(time: u64, previous: Option<PreviousHeaderCommitment>) -> SessionHeader {
+        SessionHeader::new(
+            fixtures::encoder_seed(),
+            MerkleRoot::from([1u8; 32]),
+            10,
+            20,
+            HandshakeSummary::new(
+                time,
+                fixtures::server_ephemeral_key(),
+                fixtures::handshake_commitment(),
+            ),
+        )
+        .with_previous(previous)
+    }
+
+    #[test]
+    fn test_header_chain() {
+        let first = header(None);
+        let second = header(Some(
+            PreviousHeaderOpening::new(first.clone(), [1u8; 32]).commit(),
+        ));
+        let third = header(Some(
+            PreviousHeaderOpening::new(second.clone(), [2u8; 32]).commit(),
+        ));
+
+        let headers = [first, second, third];
+        verify_header_chain(&headers, &[[1u8; 32], [2u8; 32]]).unwrap();
+        verify_header_chain(&headers[..1], &[]).unwrap();
+
+        assert_eq!(
+            verify_header_chain(&headers, &[[1u8; 32], [3u8; 32]]),
+            Err(HeaderChainError::InvalidLink(2))
+        );
+        assert_eq!(
+            verify_header_chain(&headers, &[[1u8; 32]]),
+            Err(HeaderChainError::BlinderCountMismatch {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(verify_header_chain(&headers[1..], &[[2u8; 32]]), Ok(()));
+        assert_eq!(
+            verify_header_chain(&[headers[2].clone(), headers[0].clone()], &[[2u8; 32]]),
+            Err(HeaderChainError::MissingLink(1))
+        );
+    }
+
+    #[test]
+    fn test_header_chain_out_of_order() {
+        let first = header_at(2000, None);
+        let second = header_at(
+            1000,
+            Some(PreviousHeaderOpening::new(first.clone(), [1u8; 32]).commit()),
+        );
+        let third = header_at(
+            3000,
+            Some(PreviousHeaderOpening::new(second.clone(), [2u8; 32]).commit()),
+        );
+
+        assert_eq!(
+            verify_header_chain(&[first, second.clone()], &[[1u8; 32]]),
+            Err(HeaderChainError::OutOfOrder(1))
+        );
+        // Only consecutive headers are compared
+        assert_eq!(verify_header_chain(&[second, third], &[[2u8; 32]]), Ok(()));
+    }
+
+    #[test]
+    fn test_previous_header_opening() {
+        let first = header(None);
+        let opening = PreviousHeaderOpening::new(first.clone(), [1u8; 32]);
+        let commitment = opening.commit();
+
+        assert!(opening.verify(&commitment));
+        assert!(!PreviousHeaderOpening::new(first, [2u8; 32]).verify(&commitment));
+    }
+}
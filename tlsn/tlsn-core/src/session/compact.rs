@@ -0,0 +1,377 @@
+use mpz_core::serialize::CanonicalSerialize;
+use p256::ecdsa::signature::Verifier;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    signature::{Signature, SignatureAlgorithm},
+    NotaryPublicKey, SessionHeader,
+};
+
+/// The version of the [`CompactHeader`] layout.
+pub const COMPACT_HEADER_VERSION: u16 = 1;
+
+/// The length of an encoded [`CompactHeader`] in bytes.
+pub const COMPACT_HEADER_LEN: usize = 106;
+
+/// The domain separation tag which is prepended to a [`CompactHeader`] before it is signed.
+///
+/// This ensures that a signature over a compact header can not be confused with a signature over
+/// any other message the notary signs with the same key.
+pub const COMPACT_HEADER_DOMAIN: &[u8; 19] = b"tlsn.compact-header";
+
+/// The length of the message signed by the notary, see [`CompactHeader::signing_bytes`].
+pub const COMPACT_HEADER_SIGNED_LEN: usize = COMPACT_HEADER_DOMAIN.len() + COMPACT_HEADER_LEN;
+
+/// An error that can occur while verifying a [`CompactHeader`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum CompactHeaderError {
+    /// The header uses an unsupported layout version.
+    #[error("unsupported compact header version: {0}")]
+    UnsupportedVersion(u16),
+    /// The public key is not a valid SEC1 encoded key for the signature algorithm.
+    #[error("invalid notary public key")]
+    InvalidPublicKey,
+    /// The signature is malformed or was not produced by the notary.
+    #[error("invalid compact header signature")]
+    InvalidSignature,
+    /// The header was signed for a different notary key.
+    #[error("compact header key id does not match the notary public key")]
+    KeyIdMismatch,
+}
+
+/// A compact summary of a [`SessionHeader`] with a fixed layout.
+///
+/// The Notary signs this alongside the session header, so that light clients which can not parse
+/// the full header (e.g. smart contracts) can still verify the Merkle root of
+/// the transcript commitments. The notary signs [`COMPACT_HEADER_DOMAIN`] followed by the
+/// encoding, which is, with integers in big-endian:
+///
+/// | Offset | Length | Field                                     |
+/// |--------|--------|-------------------------------------------|
+/// | 0      | 2      | layout version                            |
+/// | 2      | 32     | key id, see [`NotaryPublicKey::key_id`]   |
+/// | 34     | 8      | time, in seconds since the UNIX epoch     |
+/// | 42     | 32     | Merkle root of the transcript commitments |
+/// | 74     | 32     | SHA-256 digest of the full session header |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactHeader {
+    version: u16,
+    key_id: [u8; 32],
+    time: u64,
+    merkle_root: [u8; 32],
+    header_digest: [u8; 32],
+}
+
+impl CompactHeader {
+    /// Creates a new compact header summarizing the session header.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The session header.
+    /// * `notary_public_key` - The public key of the notary which signs the header.
+    pub fn new(header: &SessionHeader, notary_public_key: &NotaryPublicKey) -> Self {
+        Self {
+            version: COMPACT_HEADER_VERSION,
+            key_id: notary_public_key.key_id(),
+            time: header.time(),
+            merkle_root: header.merkle_root().to_inner(),
+            header_digest: Sha256::digest(header.to_bytes()).into(),
+        }
+    }
+
+    /// Decodes a compact header.
+    pub fn from_bytes(bytes: &[u8; COMPACT_HEADER_LEN]) -> Result<Self, CompactHeaderError> {
+        let version = u16::from_be_bytes([bytes[0], bytes[1]]);
+        if version != COMPACT_HEADER_VERSION {
+            return Err(CompactHeaderError::UnsupportedVersion(version));
+        }
+
+        Ok(Self {
+            version,
+            key_id: bytes[2..34].try_into().unwrap(),
+            time: u64::from_be_bytes(bytes[34..42].try_into().unwrap()),
+            merkle_root: bytes[42..74].try_into().unwrap(),
+            header_digest: bytes[74..106].try_into().unwrap(),
+        })
+    }
+
+    /// Encodes the compact header.
+    pub fn to_bytes(&self) -> [u8; COMPACT_HEADER_LEN] {
+        let mut bytes = [0u8; COMPACT_HEADER_LEN];
+        bytes[0..2].copy_from_slice(&self.version.to_be_bytes());
+        bytes[2..34].copy_from_slice(&self.key_id);
+        bytes[34..42].copy_from_slice(&self.time.to_be_bytes());
+        bytes[42..74].copy_from_slice(&self.merkle_root);
+        bytes[74..106].copy_from_slice(&self.header_digest);
+        bytes
+    }
+
+    /// Returns the bytes which are signed by the notary, which is the encoded header prefixed
+    /// with [`COMPACT_HEADER_DOMAIN`].
+    pub fn signing_bytes(&self) -> [u8; COMPACT_HEADER_SIGNED_LEN] {
+        signing_bytes(&self.to_bytes())
+    }
+
+    /// Returns the layout version.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Returns the id of the notary key which signed the header.
+    pub fn key_id(&self) -> &[u8; 32] {
+        &self.key_id
+    }
+
+    /// Time of the TLS session, in seconds since the UNIX epoch.
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Returns the Merkle root of the transcript commitments.
+    pub fn merkle_root(&self) -> &[u8; 32] {
+        &self.merkle_root
+    }
+
+    /// Returns the SHA-256 digest of the full session header.
+    pub fn header_digest(&self) -> &[u8; 32] {
+        &self.header_digest
+    }
+
+    /// Returns `true` if this is a summary of the given session header.
+    pub fn is_summary_of(&self, header: &SessionHeader) -> bool {
+        self.time == header.time()
+            && self.merkle_root == header.merkle_root().to_inner()
+            && self.header_digest[..] == Sha256::digest(header.to_bytes())[..]
+    }
+}
+
+/// A [`CompactHeader`] signed by the notary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedCompactHeader {
+    /// The compact header.
+    pub header: CompactHeader,
+    /// The notary's signature over [`CompactHeader::signing_bytes`].
+    pub signature: Signature,
+}
+
+impl SignedCompactHeader {
+    /// Verifies that the header was signed by the notary and that its key id matches the key.
+    ///
+    /// # Arguments
+    ///
+    /// * `notary_public_key` - The public key of the notary.
+    pub fn verify(&self, notary_public_key: &NotaryPublicKey) -> Result<(), CompactHeaderError> {
+        self.signature
+            .verify(&self.header.signing_bytes(), notary_public_key.clone())
+            .map_err(|_| CompactHeaderError::InvalidSignature)?;
+
+        if self.header.key_id != notary_public_key.key_id() {
+            return Err(CompactHeaderError::KeyIdMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+fn signing_bytes(header: &[u8; COMPACT_HEADER_LEN]) -> [u8; COMPACT_HEADER_SIGNED_LEN] {
+    let mut bytes = [0u8; COMPACT_HEADER_SIGNED_LEN];
+    bytes[..COMPACT_HEADER_DOMAIN.len()].copy_from_slice(COMPACT_HEADER_DOMAIN);
+    bytes[COMPACT_HEADER_DOMAIN.len()..].copy_from_slice(header);
+    bytes
+}
+
+/// Verifies an encoded compact header signed by a notary, returning the decoded header.
+///
+/// Unlike [`SignedCompactHeader::verify`], this only takes the raw encodings, so that verifiers
+/// don't need to deserialize any of the types of this crate.
+///
+/// # Arguments
+///
+/// * `header` - The encoded compact header.
+/// * `signature` - The notary's signature over the header, as `r || s`.
+/// * `public_key` - The notary's SEC1 compressed public key.
+/// * `algorithm` - The signature algorithm of the notary's key.
+pub fn verify_compact_header(
+    header: &[u8; COMPACT_HEADER_LEN],
+    signature: &[u8; 64],
+    public_key: &[u8; 33],
+    algorithm: SignatureAlgorithm,
+) -> Result<CompactHeader, CompactHeaderError> {
+    let msg = signing_bytes(header);
+
+    match algorithm {
+        SignatureAlgorithm::P256 => {
+            let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+                .map_err(|_| CompactHeaderError::InvalidPublicKey)?;
+            let signature = p256::ecdsa::Signature::from_slice(signature)
+                .map_err(|_| CompactHeaderError::InvalidSignature)?;
+
+            key.verify(&msg, &signature)
+                .map_err(|_| CompactHeaderError::InvalidSignature)?;
+        }
+        SignatureAlgorithm::K256 => {
+            let key = k256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+                .map_err(|_| CompactHeaderError::InvalidPublicKey)?;
+            let signature = k256::ecdsa::Signature::from_slice(signature)
+                .map_err(|_| CompactHeaderError::InvalidSignature)?;
+
+            key.verify(&msg, &signature)
+                .map_err(|_| CompactHeaderError::InvalidSignature)?;
+        }
+    }
+
+    let header = CompactHeader::from_bytes(header)?;
+
+    let key_id: [u8; 32] = Sha256::digest(public_key).into();
+    if header.key_id != key_id {
+        return Err(CompactHeaderError::KeyIdMismatch);
+    }
+
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::ecdsa::signature::Signer;
+
+    use super::*;
+    use crate::{fixtures, merkle::MerkleRoot};
+
+    #[test]
+    fn test_verify_compact_header() {
+        let signing_key = fixtures::notary_signing_key();
+        let public_key = NotaryPublicKey::from(*signing_key.verifying_key());
+        let session_header = fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 20);
+
+        let header = CompactHeader::new(&session_header, &public_key);
+        let bytes = header.to_bytes();
+        let signature: p256::ecdsa::Signature = signing_key.sign(&header.signing_bytes());
+
+        let encoded_key: [u8; 33] = signing_key
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .try_into()
+            .unwrap();
+
+        let signed = SignedCompactHeader {
+            header,
+            signature: signature.into(),
+        };
+        signed.verify(&public_key).unwrap();
+
+        let signature: [u8; 64] = signature.to_bytes().as_slice().try_into().unwrap();
+
+        let verified =
+            verify_compact_header(&bytes, &signature, &encoded_key, SignatureAlgorithm::P256)
+                .unwrap();
+
+        assert_eq!(verified, header);
+        assert!(verified.is_summary_of(&session_header));
+        assert!(!verified.is_summary_of(&fixtures::session_header(
+            MerkleRoot::from([1u8; 32]),
+            10,
+            21
+        )));
+
+        let mut tampered = bytes;
+        tampered[42] ^= 1;
+        assert!(matches!(
+            verify_compact_header(
+                &tampered,
+                &signature,
+                &encoded_key,
+                SignatureAlgorithm::P256
+            ),
+            Err(CompactHeaderError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_verify_compact_header_k256() {
+        let signing_key = k256::ecdsa::SigningKey::from_slice(&[1; 32]).unwrap();
+        let public_key = NotaryPublicKey::from(*signing_key.verifying_key());
+        let session_header = fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 20);
+
+        let header = CompactHeader::new(&session_header, &public_key);
+        let signature: k256::ecdsa::Signature = signing_key.sign(&header.signing_bytes());
+
+        let encoded_key: [u8; 33] = signing_key
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .try_into()
+            .unwrap();
+        let encoded_signature: [u8; 64] = signature.to_bytes().as_slice().try_into().unwrap();
+
+        let verified = verify_compact_header(
+            &header.to_bytes(),
+            &encoded_signature,
+            &encoded_key,
+            SignatureAlgorithm::K256,
+        )
+        .unwrap();
+        assert_eq!(verified, header);
+
+        // The same key interpreted as a P-256 key does not verify.
+        assert!(verify_compact_header(
+            &header.to_bytes(),
+            &encoded_signature,
+            &encoded_key,
+            SignatureAlgorithm::P256,
+        )
+        .is_err());
+
+        SignedCompactHeader {
+            header,
+            signature: signature.into(),
+        }
+        .verify(&public_key)
+        .unwrap();
+    }
+
+    #[test]
+    fn test_signature_without_domain_is_rejected() {
+        let signing_key = fixtures::notary_signing_key();
+        let public_key = NotaryPublicKey::from(*signing_key.verifying_key());
+        let session_header = fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 20);
+
+        let header = CompactHeader::new(&session_header, &public_key);
+        let signature: p256::ecdsa::Signature = signing_key.sign(&header.to_bytes());
+
+        let signed = SignedCompactHeader {
+            header,
+            signature: signature.into(),
+        };
+        assert!(matches!(
+            signed.verify(&public_key),
+            Err(CompactHeaderError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_key_id_mismatch() {
+        let signing_key = fixtures::notary_signing_key();
+        let other_key = NotaryPublicKey::from(
+            *p256::ecdsa::SigningKey::from_slice(&[2; 32])
+                .unwrap()
+                .verifying_key(),
+        );
+        let session_header = fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 20);
+
+        // Signed by the fixture key, but claims to be signed by another key.
+        let header = CompactHeader::new(&session_header, &other_key);
+        let signature: p256::ecdsa::Signature = signing_key.sign(&header.signing_bytes());
+
+        let signed = SignedCompactHeader {
+            header,
+            signature: signature.into(),
+        };
+        assert!(matches!(
+            signed.verify(&NotaryPublicKey::from(*signing_key.verifying_key())),
+            Err(CompactHeaderError::KeyIdMismatch)
+        ));
+    }
+}
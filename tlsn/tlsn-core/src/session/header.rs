@@ -1,22 +1,54 @@
 use mpz_core::commit::Decommitment;
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
 use mpz_garble_core::ChaChaEncoder;
 use tls_core::{handshake::HandshakeData, key::PublicKey};
 
-use crate::{merkle::MerkleRoot, HandshakeSummary};
+use crate::{merkle::MerkleRoot, session::PreviousHeaderCommitment, HandshakeSummary};
 
 /// An error that can occur while verifying a session header
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
 pub enum SessionHeaderVerifyError {
     /// The session header is not consistent with the provided data
-    #[error("session header is not consistent with the provided data")]
-    InconsistentHeader,
+    #[error("session header is not consistent with the provided data, mismatched fields: {0:?}")]
+    InconsistentHeader(Vec<SessionHeaderField>),
 }
 
+/// A field of a [`SessionHeader`] which is checked during verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SessionHeaderField {
+    /// The time of the session differs by more than the allowed tolerance.
+    Time,
+    /// The Merkle root of the transcript commitments.
+    MerkleRoot,
+    /// The encoder seed.
+    EncoderSeed,
+    /// The commitment to the handshake data.
+    HandshakeCommitment,
+    /// The server's ephemeral public key.
+    ServerPublicKey,
+    /// The commitment to a previous session header.
+    PreviousHeader,
+}
+
+/// Prefix of the binary encoding of a [`SessionHeader`] which links to a previous header.
+///
+/// A header without a link keeps the original encoding, which starts with the encoder seed, so
+/// that the signatures over it and headers serialized by older versions remain valid. A linked
+/// header is versioned by this prefix in place of the encoder seed, which a random seed collides
+/// with only with negligible probability.
+const LINKED_HEADER_TAG: [u8; 32] = *b"tlsn.session-header.linked.v2...";
+
 /// An authentic session header from the Notary
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// The header is signed over its binary encoding, see [`LINKED_HEADER_TAG`] for how it is
+/// versioned.
+#[derive(Debug, Clone)]
 pub struct SessionHeader {
     /// A PRG seeds used to generate encodings for the plaintext
     encoder_seed: [u8; 32],
@@ -35,6 +67,10 @@ pub struct SessionHeader {
     recv_len: usize,
 
     handshake_summary: HandshakeSummary,
+
+    /// An optional commitment to a previous session header of the Prover, which links this session
+    /// to it.
+    previous: Option<PreviousHeaderCommitment>,
 }
 
 impl SessionHeader {
@@ -52,9 +88,16 @@ impl SessionHeader {
             sent_len,
             recv_len,
             handshake_summary,
+            previous: None,
         }
     }
 
+    /// Sets the commitment to a previous session header, see [`PreviousHeaderCommitment`].
+    pub fn with_previous(mut self, previous: Option<PreviousHeaderCommitment>) -> Self {
+        self.previous = previous;
+        self
+    }
+
     /// Verify the data in the header is consistent with the Prover's view
     pub fn verify(
         &self,
@@ -63,22 +106,63 @@ impl SessionHeader {
         root: &MerkleRoot,
         encoder_seed: &[u8; 32],
         handshake_data_decommitment: &Decommitment<HandshakeData>,
+        previous: Option<&PreviousHeaderCommitment>,
     ) -> Result<(), SessionHeaderVerifyError> {
-        let ok_time = self.handshake_summary.time().abs_diff(time) <= 300;
-        let ok_root = &self.merkle_root == root;
-        let ok_encoder_seed = &self.encoder_seed == encoder_seed;
-        let ok_handshake_data = handshake_data_decommitment
-            .verify(self.handshake_summary.handshake_commitment())
-            .is_ok();
-        let ok_server_public_key = self.handshake_summary.server_public_key() == server_public_key;
+        let mismatched = self.diff(
+            time,
+            server_public_key,
+            root,
+            encoder_seed,
+            handshake_data_decommitment,
+            previous,
+        );
 
-        if !(ok_time && ok_root && ok_encoder_seed && ok_handshake_data && ok_server_public_key) {
-            return Err(SessionHeaderVerifyError::InconsistentHeader);
+        if !mismatched.is_empty() {
+            return Err(SessionHeaderVerifyError::InconsistentHeader(mismatched));
         }
 
         Ok(())
     }
 
+    /// Returns the fields of the header which are not consistent with the Prover's view.
+    ///
+    /// The arguments are the same as for [`verify`](Self::verify).
+    pub fn diff(
+        &self,
+        time: u64,
+        server_public_key: &PublicKey,
+        root: &MerkleRoot,
+        encoder_seed: &[u8; 32],
+        handshake_data_decommitment: &Decommitment<HandshakeData>,
+        previous: Option<&PreviousHeaderCommitment>,
+    ) -> Vec<SessionHeaderField> {
+        let mut mismatched = Vec::new();
+
+        if self.handshake_summary.time().abs_diff(time) > 300 {
+            mismatched.push(SessionHeaderField::Time);
+        }
+        if &self.merkle_root != root {
+            mismatched.push(SessionHeaderField::MerkleRoot);
+        }
+        if &self.encoder_seed != encoder_seed {
+            mismatched.push(SessionHeaderField::EncoderSeed);
+        }
+        if handshake_data_decommitment
+            .verify(self.handshake_summary.handshake_commitment())
+            .is_err()
+        {
+            mismatched.push(SessionHeaderField::HandshakeCommitment);
+        }
+        if self.handshake_summary.server_public_key() != server_public_key {
+            mismatched.push(SessionHeaderField::ServerPublicKey);
+        }
+        if self.previous.as_ref() != previous {
+            mismatched.push(SessionHeaderField::PreviousHeader);
+        }
+
+        mismatched
+    }
+
     /// Create a new [ChaChaEncoder] from encoder_seed
     pub fn encoder(&self) -> ChaChaEncoder {
         ChaChaEncoder::new(self.encoder_seed)
@@ -117,4 +201,280 @@ impl SessionHeader {
     pub fn recv_len(&self) -> usize {
         self.recv_len
     }
+
+    /// Returns the commitment to a previous session header, if the Prover linked this session to
+    /// one
+    pub fn previous(&self) -> Option<&PreviousHeaderCommitment> {
+        self.previous.as_ref()
+    }
+}
+
+/// The fields of a [`SessionHeader`] in the order of its binary encoding.
+const FIELDS: &[&str] = &[
+    "linked_header_tag",
+    "encoder_seed",
+    "merkle_root",
+    "sent_len",
+    "recv_len",
+    "handshake_summary",
+    "previous",
+];
+
+/// The fields of a [`SessionHeader`] as serialized.
+///
+/// The fields of a linked header are prefixed with [`LINKED_HEADER_TAG`] in formats which are not
+/// human readable, so that the binary encoding is versioned. Human readable formats identify a
+/// linked header by the `previous` field instead.
+#[derive(Serialize)]
+#[serde(rename = "SessionHeader")]
+struct HeaderFieldsRef<'a> {
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_some"
+    )]
+    linked_header_tag: Option<&'a [u8; 32]>,
+    encoder_seed: &'a [u8; 32],
+    merkle_root: &'a MerkleRoot,
+    sent_len: usize,
+    recv_len: usize,
+    handshake_summary: &'a HandshakeSummary,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_some"
+    )]
+    previous: Option<&'a PreviousHeaderCommitment>,
+}
+
+/// Serializes the value of an optional field which is skipped if it is `None`, so that the field
+/// is encoded the same way as a required field.
+fn serialize_some<T: Serialize, S: Serializer>(
+    value: &Option<&T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    value
+        .expect("field is skipped if it is none")
+        .serialize(serializer)
+}
+
+impl Serialize for SessionHeader {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let linked_header_tag = (self.previous.is_some() && !serializer.is_human_readable())
+            .then_some(&LINKED_HEADER_TAG);
+
+        HeaderFieldsRef {
+            linked_header_tag,
+            encoder_seed: &self.encoder_seed,
+            merkle_root: &self.merkle_root,
+            sent_len: self.sent_len,
+            recv_len: self.recv_len,
+            handshake_summary: &self.handshake_summary,
+            previous: self.previous.as_ref(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SessionHeader {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Formats which are not self-describing, e.g. bincode, read the fields as a sequence, in
+        // which a linked header is identified by its first element. Self-describing formats may
+        // also read the fields as a map, in which it is identified by its fields.
+        deserializer.deserialize_struct("SessionHeader", FIELDS, HeaderVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum Field {
+    LinkedHeaderTag,
+    EncoderSeed,
+    MerkleRoot,
+    SentLen,
+    RecvLen,
+    HandshakeSummary,
+    Previous,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Deserializes a header, with or without a link to a previous header.
+struct HeaderVisitor;
+
+impl<'de> Visitor<'de> for HeaderVisitor {
+    type Value = SessionHeader;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a session header")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let first: [u8; 32] = next_element(&mut seq, 0)?;
+        let linked = first == LINKED_HEADER_TAG;
+        let offset = usize::from(linked);
+
+        let encoder_seed = if linked {
+            next_element(&mut seq, 1)?
+        } else {
+            first
+        };
+        let merkle_root = next_element(&mut seq, offset + 1)?;
+        let sent_len = next_element(&mut seq, offset + 2)?;
+        let recv_len = next_element(&mut seq, offset + 3)?;
+        let handshake_summary = next_element(&mut seq, offset + 4)?;
+        let previous = if linked {
+            Some(next_element(&mut seq, 6)?)
+        } else {
+            None
+        };
+
+        Ok(SessionHeader::new(
+            encoder_seed,
+            merkle_root,
+            sent_len,
+            recv_len,
+            handshake_summary,
+        )
+        .with_previous(previous))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut linked_header_tag: Option<[u8; 32]> = None;
+        let mut encoder_seed = None;
+        let mut merkle_root = None;
+        let mut sent_len = None;
+        let mut recv_len = None;
+        let mut handshake_summary = None;
+        let mut previous = None;
+
+        while let Some(field) = map.next_key()? {
+            match field {
+                Field::LinkedHeaderTag => linked_header_tag = Some(map.next_value()?),
+                Field::EncoderSeed => encoder_seed = Some(map.next_value()?),
+                Field::MerkleRoot => merkle_root = Some(map.next_value()?),
+                Field::SentLen => sent_len = Some(map.next_value()?),
+                Field::RecvLen => recv_len = Some(map.next_value()?),
+                Field::HandshakeSummary => handshake_summary = Some(map.next_value()?),
+                Field::Previous => previous = Some(map.next_value()?),
+                Field::Unknown => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        match linked_header_tag {
+            Some(tag) if tag != LINKED_HEADER_TAG => {
+                return Err(de::Error::invalid_value(
+                    de::Unexpected::Bytes(&tag),
+                    &"the linked header tag",
+                ))
+            }
+            Some(_) if previous.is_none() => return Err(de::Error::missing_field("previous")),
+            _ => {}
+        }
+
+        Ok(SessionHeader::new(
+            encoder_seed.ok_or_else(|| de::Error::missing_field("encoder_seed"))?,
+            merkle_root.ok_or_else(|| de::Error::missing_field("merkle_root"))?,
+            sent_len.ok_or_else(|| de::Error::missing_field("sent_len"))?,
+            recv_len.ok_or_else(|| de::Error::missing_field("recv_len"))?,
+            handshake_summary.ok_or_else(|| de::Error::missing_field("handshake_summary"))?,
+        )
+        .with_previous(previous))
+    }
+}
+
+fn next_element<'de, T: Deserialize<'de>, A: SeqAccess<'de>>(
+    seq: &mut A,
+    idx: usize,
+) -> Result<T, A::Error> {
+    seq.next_element()?
+        .ok_or_else(|| de::Error::invalid_length(idx, &"a session header"))
+}
+
+#[cfg(test)]
+mod tests {
+    use mpz_core::serialize::CanonicalSerialize;
+
+    use super::*;
+    use crate::{fixtures, session::PreviousHeaderOpening};
+
+    /// The layout of a session header before headers could link to a previous header.
+    #[derive(Serialize)]
+    struct LegacySessionHeader {
+        encoder_seed: [u8; 32],
+        merkle_root: MerkleRoot,
+        sent_len: usize,
+        recv_len: usize,
+        handshake_summary: HandshakeSummary,
+    }
+
+    fn legacy(header: &SessionHeader) -> LegacySessionHeader {
+        LegacySessionHeader {
+            encoder_seed: header.encoder_seed,
+            merkle_root: header.merkle_root,
+            sent_len: header.sent_len,
+            recv_len: header.recv_len,
+            handshake_summary: header.handshake_summary.clone(),
+        }
+    }
+
+    fn linked_header() -> SessionHeader {
+        let previous = fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 20);
+        fixtures::session_header(MerkleRoot::from([2u8; 32]), 30, 40).with_previous(Some(
+            PreviousHeaderOpening::new(previous, [1u8; 32]).commit(),
+        ))
+    }
+
+    #[test]
+    fn test_unlinked_header_keeps_legacy_encoding() {
+        let header = fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 20);
+
+        // The signed bytes must not change, otherwise existing signatures become invalid
+        assert_eq!(header.to_bytes(), legacy(&header).to_bytes());
+
+        let bytes = bincode::serialize(&legacy(&header)).unwrap();
+        assert_eq!(bincode::serialize(&header).unwrap(), bytes);
+        let decoded: SessionHeader = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), header.to_bytes());
+        assert!(decoded.previous().is_none());
+
+        let json = serde_json::to_string(&legacy(&header)).unwrap();
+        assert_eq!(serde_json::to_string(&header).unwrap(), json);
+        let decoded: SessionHeader = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.to_bytes(), header.to_bytes());
+    }
+
+    #[test]
+    fn test_linked_header_encoding() {
+        let header = linked_header();
+
+        let bytes = header.to_bytes();
+        assert_eq!(bytes[..32], LINKED_HEADER_TAG);
+        assert_ne!(bytes, legacy(&header).to_bytes());
+
+        let decoded: SessionHeader =
+            bincode::deserialize(&bincode::serialize(&header).unwrap()).unwrap();
+        assert_eq!(decoded.previous(), header.previous());
+        assert_eq!(decoded.to_bytes(), bytes);
+
+        let decoded: SessionHeader =
+            serde_json::from_str(&serde_json::to_string(&header).unwrap()).unwrap();
+        assert_eq!(decoded.previous(), header.previous());
+        assert_eq!(decoded.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_self_describing_binary_encoding() {
+        let unlinked = fixtures::session_header(MerkleRoot::from([1u8; 32]), 10, 20);
+
+        // CBOR is not human readable, but encodes structs as maps
+        for header in [unlinked, linked_header()] {
+            let mut bytes = Vec::new();
+            ciborium::ser::into_writer(&header, &mut bytes).unwrap();
+            let decoded: SessionHeader = ciborium::de::from_reader(bytes.as_slice()).unwrap();
+
+            assert_eq!(decoded.previous(), header.previous());
+            assert_eq!(decoded.to_bytes(), header.to_bytes());
+        }
+    }
 }
@@ -1,14 +1,23 @@
 //! TLS session types.
 
+mod chain;
+mod compact;
 mod data;
 mod handshake;
 mod header;
 
 use serde::{Deserialize, Serialize};
 
+pub use chain::{
+    verify_header_chain, HeaderChainError, PreviousHeaderCommitment, PreviousHeaderOpening,
+};
+pub use compact::{
+    verify_compact_header, CompactHeader, CompactHeaderError, SignedCompactHeader,
+    COMPACT_HEADER_DOMAIN, COMPACT_HEADER_LEN, COMPACT_HEADER_SIGNED_LEN, COMPACT_HEADER_VERSION,
+};
 pub use data::SessionData;
 pub use handshake::{HandshakeSummary, HandshakeVerifyError};
-pub use header::{SessionHeader, SessionHeaderVerifyError};
+pub use header::{SessionHeader, SessionHeaderField, SessionHeaderVerifyError};
 
 use crate::{
     proof::{SessionInfo, SessionProof},
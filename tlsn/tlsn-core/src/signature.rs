@@ -1,6 +1,21 @@
 use serde::{Deserialize, Serialize};
 
-use p256::ecdsa::{signature::Verifier, VerifyingKey};
+use p256::{
+    ecdsa::{signature::Verifier, VerifyingKey},
+    elliptic_curve::sec1::ToEncodedPoint,
+};
+use sha2::{Digest, Sha256};
+
+/// A signature algorithm supported by the Notary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[non_exhaustive]
+pub enum SignatureAlgorithm {
+    /// ECDSA over the NIST P-256 curve.
+    #[default]
+    P256,
+    /// ECDSA over the secp256k1 curve.
+    K256,
+}
 
 /// A Notary public key.
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -8,6 +23,8 @@ use p256::ecdsa::{signature::Verifier, VerifyingKey};
 pub enum NotaryPublicKey {
     /// A NIST P-256 public key.
     P256(p256::PublicKey),
+    /// A secp256k1 public key.
+    K256(k256::PublicKey),
 }
 
 impl From<p256::PublicKey> for NotaryPublicKey {
@@ -16,6 +33,42 @@ impl From<p256::PublicKey> for NotaryPublicKey {
     }
 }
 
+impl From<VerifyingKey> for NotaryPublicKey {
+    fn from(key: VerifyingKey) -> Self {
+        Self::P256(key.into())
+    }
+}
+
+impl From<k256::PublicKey> for NotaryPublicKey {
+    fn from(key: k256::PublicKey) -> Self {
+        Self::K256(key)
+    }
+}
+
+impl From<k256::ecdsa::VerifyingKey> for NotaryPublicKey {
+    fn from(key: k256::ecdsa::VerifyingKey) -> Self {
+        Self::K256(key.into())
+    }
+}
+
+impl NotaryPublicKey {
+    /// Returns the id of this key, which is the SHA-256 digest of its SEC1 compressed encoding.
+    pub fn key_id(&self) -> [u8; 32] {
+        match self {
+            Self::P256(key) => Sha256::digest(key.to_encoded_point(true).as_bytes()).into(),
+            Self::K256(key) => Sha256::digest(key.to_encoded_point(true).as_bytes()).into(),
+        }
+    }
+
+    /// Returns the signature algorithm of this key.
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        match self {
+            Self::P256(_) => SignatureAlgorithm::P256,
+            Self::K256(_) => SignatureAlgorithm::K256,
+        }
+    }
+}
+
 /// An error occurred while verifying a signature.
 #[derive(Debug, thiserror::Error)]
 #[error("signature verification failed: {0}")]
@@ -27,6 +80,8 @@ pub struct SignatureVerifyError(String);
 pub enum Signature {
     /// A secp256r1 signature.
     P256(p256::ecdsa::Signature),
+    /// A secp256k1 signature.
+    K256(k256::ecdsa::Signature),
 }
 
 impl From<p256::ecdsa::Signature> for Signature {
@@ -35,11 +90,26 @@ impl From<p256::ecdsa::Signature> for Signature {
     }
 }
 
+impl From<k256::ecdsa::Signature> for Signature {
+    fn from(sig: k256::ecdsa::Signature) -> Self {
+        Self::K256(sig)
+    }
+}
+
 impl Signature {
     /// Returns the bytes of this signature.
     pub fn to_bytes(&self) -> Vec<u8> {
         match self {
             Self::P256(sig) => sig.to_vec(),
+            Self::K256(sig) => sig.to_vec(),
+        }
+    }
+
+    /// Returns the signature algorithm of this signature.
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        match self {
+            Self::P256(_) => SignatureAlgorithm::P256,
+            Self::K256(_) => SignatureAlgorithm::K256,
         }
     }
 
@@ -58,6 +128,46 @@ impl Signature {
             (Self::P256(sig), NotaryPublicKey::P256(key)) => VerifyingKey::from(key)
                 .verify(msg, sig)
                 .map_err(|e| SignatureVerifyError(e.to_string())),
+            (Self::K256(sig), NotaryPublicKey::K256(key)) => k256::ecdsa::VerifyingKey::from(key)
+                .verify(msg, sig)
+                .map_err(|e| SignatureVerifyError(e.to_string())),
+            (sig, key) => Err(SignatureVerifyError(format!(
+                "{:?} signature can not be verified with a {:?} key",
+                sig.algorithm(),
+                key.algorithm()
+            ))),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use p256::ecdsa::signature::Signer;
+
+    #[test]
+    fn test_verify_k256() {
+        let signing_key = k256::ecdsa::SigningKey::from_slice(&[1; 32]).unwrap();
+        let public_key = NotaryPublicKey::from(*signing_key.verifying_key());
+        let signature: Signature =
+            Signer::<k256::ecdsa::Signature>::sign(&signing_key, b"msg").into();
+
+        assert_eq!(signature.algorithm(), SignatureAlgorithm::K256);
+        assert_eq!(public_key.algorithm(), SignatureAlgorithm::K256);
+        signature.verify(b"msg", public_key.clone()).unwrap();
+        assert!(signature.verify(b"other", public_key).is_err());
+    }
+
+    #[test]
+    fn test_verify_algorithm_mismatch() {
+        let signing_key = k256::ecdsa::SigningKey::from_slice(&[1; 32]).unwrap();
+        let signature: Signature =
+            Signer::<k256::ecdsa::Signature>::sign(&signing_key, b"msg").into();
+        let p256_key = p256::ecdsa::SigningKey::from_slice(&[1; 32]).unwrap();
+
+        assert!(signature
+            .verify(b"msg", NotaryPublicKey::from(*p256_key.verifying_key()))
+            .is_err());
+    }
+}
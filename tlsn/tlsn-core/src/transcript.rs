@@ -10,6 +10,9 @@ pub(crate) static TX_TRANSCRIPT_ID: &str = "tx";
 pub(crate) static RX_TRANSCRIPT_ID: &str = "rx";
 
 /// A transcript contains a subset of bytes from a TLS session
+///
+/// The data is kept in a shared, immutable buffer. Cloning a transcript or taking a
+/// [view](Transcript::view) of it does not copy the data.
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
 pub struct Transcript {
     data: Bytes,
@@ -26,6 +29,17 @@ impl Transcript {
         &self.data
     }
 
+    /// Returns a view of the data in the given range, which shares the buffer of the transcript.
+    ///
+    /// Returns `None` if the range is out of bounds.
+    pub fn view(&self, range: Range<usize>) -> Option<Bytes> {
+        if range.start > range.end || range.end > self.data.len() {
+            return None;
+        }
+
+        Some(self.data.slice(range))
+    }
+
     /// Returns a concatenated bytestring located in the given ranges of the transcript.
     ///
     /// # Panics
@@ -225,6 +239,19 @@ mod tests {
         );
     }
 
+    #[rstest]
+    fn test_view(transcripts: (Transcript, Transcript)) {
+        let (sent, _) = transcripts;
+
+        let view = sent.view(5..9).unwrap();
+        assert_eq!(view.as_ref(), b"sent");
+        // The view shares the buffer of the transcript
+        assert_eq!(view.as_ptr(), sent.data()[5..].as_ptr());
+
+        assert!(sent.view(0..sent.data().len()).is_some());
+        assert!(sent.view(0..sent.data().len() + 1).is_none());
+    }
+
     #[rstest]
     #[should_panic]
     fn test_get_bytes_in_ranges_empty(transcripts: (Transcript, Transcript)) {
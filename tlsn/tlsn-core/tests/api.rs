@@ -23,9 +23,10 @@ use tlsn_core::{
     commitment::TranscriptCommitmentBuilder,
     fixtures,
     msg::SignedSessionHeader,
-    proof::{SessionProof, SubstringsProof},
-    HandshakeSummary, NotarizedSession, ServerName, SessionData, SessionHeader, Signature,
-    Transcript,
+    proof::{SessionProof, SubstringsProof, SubstringsProofBuilder, SubstringsProofBuilderError},
+    session::{CompactHeader, SignedCompactHeader},
+    Direction, HandshakeSummary, NotarizedSession, ServerName, SessionData, SessionHeader,
+    Signature, Transcript,
 };
 
 #[test]
@@ -114,19 +115,30 @@ fn test_api() {
     );
 
     let signature: P256Signature = signer.sign(&header.to_bytes());
+    let compact_header = CompactHeader::new(&header, &notary_pubkey.into());
+    let compact_signature: P256Signature = signer.sign(&compact_header.signing_bytes());
     // Notary creates a msg and sends it to Prover
     let msg = SignedSessionHeader {
         header,
         signature: signature.into(),
+        notary_public_key: notary_pubkey.into(),
+        compact: SignedCompactHeader {
+            header: compact_header,
+            signature: compact_signature.into(),
+        },
     };
 
     //---------------------------------------
     let msg_bytes = bincode::serialize(&msg).unwrap();
-    let SignedSessionHeader { header, signature } = bincode::deserialize(&msg_bytes).unwrap();
+    let SignedSessionHeader {
+        header,
+        signature,
+        notary_public_key,
+        compact,
+    } = bincode::deserialize(&msg_bytes).unwrap();
     //---------------------------------------
 
     // Prover verifies the signature
-    #[allow(irrefutable_let_patterns)]
     if let Signature::P256(signature) = signature {
         notary_verifing_key
             .verify(&header.to_bytes(), &signature)
@@ -143,9 +155,14 @@ fn test_api() {
             &notarized_session_data.commitments().merkle_root(),
             header.encoder_seed(),
             &notarized_session_data.session_info().handshake_decommitment,
+            None,
         )
         .unwrap();
 
+    // Prover checks that the compact header summarizes the header and was signed by the notary
+    assert!(compact.header.is_summary_of(&header));
+    compact.verify(&notary_public_key).unwrap();
+
     let session = NotarizedSession::new(header, Some(signature), notarized_session_data);
 
     // Prover converts NotarizedSession into SessionProof and SubstringsProof and sends them to the Verifier
@@ -190,3 +207,98 @@ fn test_api() {
     assert_eq!(&sent.data()[range1], b"se".as_slice());
     assert_eq!(&recv.data()[range2], b"ec".as_slice());
 }
+
+#[test]
+/// Tests that a streamed substrings proof can be deserialized and verified as a regular one
+fn test_streaming_substrings_proof() {
+    let data_sent = "sent data".as_bytes();
+    let data_recv = "received data".as_bytes();
+    let transcript_tx = Transcript::new(data_sent.to_vec());
+    let transcript_rx = Transcript::new(data_recv.to_vec());
+
+    let encodings_provider = fixtures::encoding_provider(data_sent, data_recv);
+    let mut commitment_builder =
+        TranscriptCommitmentBuilder::new(encodings_provider, data_sent.len(), data_recv.len());
+
+    let ids = (0..data_recv.len() - 1)
+        .map(|idx| commitment_builder.commit_recv(&(idx..idx + 2)).unwrap())
+        .collect::<Vec<_>>();
+
+    let commitments = commitment_builder.build().unwrap();
+    let header =
+        fixtures::session_header(commitments.merkle_root(), data_sent.len(), data_recv.len());
+
+    let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+    for id in ids.iter().rev() {
+        builder.reveal_by_id(*id).unwrap();
+    }
+
+    let proof_bytes = bincode::serialize(&builder.build_streaming().unwrap()).unwrap();
+    let proof: SubstringsProof = bincode::deserialize(&proof_bytes).unwrap();
+
+    let (_, recv) = proof.verify(&header).unwrap();
+
+    assert_eq!(recv.data(), data_recv);
+
+    // Revealing more ranges than allowed fails.
+    let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+    builder.set_max_ranges(1);
+    builder.reveal_by_id(ids[0]).unwrap();
+
+    assert!(matches!(
+        builder.reveal_by_id(ids[1]).unwrap_err(),
+        SubstringsProofBuilderError::MaxRangesExceeded { limit: 1, count: 2 }
+    ));
+}
+
+#[test]
+/// Tests that ranges are revealed with the smallest commitment which contains them
+fn test_reveal_covering() {
+    let data_sent = "sent data".as_bytes();
+    let data_recv = "received data".as_bytes();
+    let transcript_tx = Transcript::new(data_sent.to_vec());
+    let transcript_rx = Transcript::new(data_recv.to_vec());
+
+    let encodings_provider = fixtures::encoding_provider(data_sent, data_recv);
+    let mut commitment_builder =
+        TranscriptCommitmentBuilder::new(encodings_provider, data_sent.len(), data_recv.len());
+
+    let all = commitment_builder
+        .commit_recv(&(0..data_recv.len()))
+        .unwrap();
+    let word = commitment_builder.commit_recv(&(0..8)).unwrap();
+
+    let commitments = commitment_builder.build().unwrap();
+    let header =
+        fixtures::session_header(commitments.merkle_root(), data_sent.len(), data_recv.len());
+
+    let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+    builder
+        .reveal_covering(&(2..5), Direction::Received)
+        .unwrap()
+        // Already revealed by the commitment above
+        .reveal_covering(&(0..8), Direction::Received)
+        .unwrap();
+
+    assert!(builder.is_revealed(&(0..8), Direction::Received));
+    assert!(!builder.is_revealed(&(7..9), Direction::Received));
+    assert!(matches!(
+        builder
+            .reveal_covering(&(0..2), Direction::Sent)
+            .unwrap_err(),
+        SubstringsProofBuilderError::MissingCommitment
+    ));
+
+    let (_, recv) = builder.build().unwrap().verify(&header).unwrap();
+    assert_eq!(recv.authed().iter_ranges().collect::<Vec<_>>(), vec![0..8]);
+
+    let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+    builder
+        .reveal_covering(&(7..9), Direction::Received)
+        .unwrap();
+    builder.reveal_by_id(word).unwrap();
+    assert!(matches!(
+        builder.reveal_by_id(all).unwrap_err(),
+        SubstringsProofBuilderError::DuplicateCommitmentId(_)
+    ));
+}
@@ -1,11 +1,15 @@
 use std::error::Error;
 
 use spansy::Spanned;
-use tlsn_core::{commitment::TranscriptCommitmentBuilder, Direction};
+use tlsn_core::{
+    commitment::{CommitmentKind, TranscriptCommitmentBuilder},
+    Direction,
+};
 
 use crate::{
     http::{Body, BodyContent, Header, HttpTranscript, MessageKind, Request, Response, Target},
     json::{DefaultJsonCommitter, JsonCommit},
+    line::line_ranges,
 };
 
 /// HTTP commitment error.
@@ -409,6 +413,76 @@ pub struct DefaultHttpCommitter {}
 
 impl HttpCommit for DefaultHttpCommitter {}
 
+/// An HTTP committer which additionally commits to each line of text bodies.
+///
+/// This is the same as [`DefaultHttpCommitter`], except that bodies which are not JSON are also
+/// committed to line by line, excluding line terminators, so that individual lines can be disclosed.
+#[derive(Debug, Default, Clone)]
+pub struct LineHttpCommitter {}
+
+impl HttpCommit for LineHttpCommitter {
+    fn commit_request_body(
+        &mut self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+        parent: &Request,
+        body: &Body,
+    ) -> Result<(), HttpCommitError> {
+        DefaultHttpCommitter::default().commit_request_body(builder, direction, parent, body)?;
+
+        if !matches!(body.content, BodyContent::Json(_)) {
+            commit_lines(builder, direction, MessageKind::Request, body)?;
+        }
+
+        Ok(())
+    }
+
+    fn commit_response_body(
+        &mut self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+        parent: &Response,
+        body: &Body,
+    ) -> Result<(), HttpCommitError> {
+        DefaultHttpCommitter::default().commit_response_body(builder, direction, parent, body)?;
+
+        if !matches!(body.content, BodyContent::Json(_)) {
+            commit_lines(builder, direction, MessageKind::Response, body)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Commits to each non-empty line of the body, skipping lines which are already committed.
+fn commit_lines(
+    builder: &mut TranscriptCommitmentBuilder,
+    direction: Direction,
+    record_kind: MessageKind,
+    body: &Body,
+) -> Result<(), HttpCommitError> {
+    let span = body.span();
+    let Some(offset) = span.indices().min() else {
+        return Ok(());
+    };
+
+    for range in line_ranges(span.as_bytes(), offset) {
+        if range.is_empty()
+            || builder
+                .get_id(CommitmentKind::Blake3, range.clone(), direction)
+                .is_some()
+        {
+            continue;
+        }
+
+        builder.commit(&range, direction).map_err(|e| {
+            HttpCommitError::new_with_source(record_kind, "failed to commit to body line", e)
+        })?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -451,4 +525,28 @@ mod tests {
 
         builder.build().unwrap();
     }
+
+    #[test]
+    fn test_http_line_commit_response() {
+        let src: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 21\r\n\r\nfirst\r\n\r\nsecond\nthird";
+        let response = parse_response(src).unwrap();
+        let mut committer = LineHttpCommitter::default();
+        let mut builder =
+            TranscriptCommitmentBuilder::new(fixtures::encoding_provider(&[], src), 0, src.len());
+
+        committer
+            .commit_response(&mut builder, Direction::Received, &response)
+            .unwrap();
+
+        let body_start = src.len() - 21;
+        for (range, line) in [(0..5, "first"), (9..15, "second"), (16..21, "third")] {
+            let range = body_start + range.start..body_start + range.end;
+            assert_eq!(&src[range.clone()], line.as_bytes());
+            assert!(builder
+                .get_id(CommitmentKind::Blake3, range, Direction::Received)
+                .is_some());
+        }
+
+        builder.build().unwrap();
+    }
 }
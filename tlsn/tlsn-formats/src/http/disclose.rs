@@ -0,0 +1,113 @@
+use tlsn_core::{
+    commitment::CommitmentKind,
+    proof::{SubstringsProofBuilder, SubstringsProofBuilderError},
+    Direction,
+};
+use utils::range::ToRangeSet;
+
+use crate::http::{HttpTranscript, MessageKind};
+
+/// Minimal disclosure error.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum MinimalDisclosureError {
+    /// A message is missing a header which is required by the preset.
+    #[error("{kind:?} {idx} is missing the \"{name}\" header")]
+    MissingHeader {
+        /// The kind of the message.
+        kind: MessageKind,
+        /// The index of the message in the transcript.
+        idx: usize,
+        /// The name of the missing header.
+        name: &'static str,
+    },
+    /// The data could not be revealed.
+    #[error(transparent)]
+    Reveal(#[from] SubstringsProofBuilderError),
+}
+
+/// Reveals the minimal parts of an HTTP transcript which show that each request was sent to a host
+/// and answered with a body of a given length.
+///
+/// For each request, the request line and the `Host` header are revealed. For each response, the
+/// status line and the `Content-Length` header are revealed. All other headers and the bodies stay
+/// redacted, while the verifier can still check the status code and body length.
+///
+/// The transcript must have been committed with
+/// [`DefaultHttpCommitter`](crate::http::DefaultHttpCommitter) or a committer which commits to the
+/// same ranges. Parts which are already revealed by the builder, e.g. as part of a larger
+/// commitment, are skipped.
+///
+/// # Arguments
+///
+/// * `builder` - The substrings proof builder.
+/// * `transcript` - The HTTP transcript.
+/// * `commitment_kind` - The kind of commitment to reveal.
+pub fn reveal_minimal(
+    builder: &mut SubstringsProofBuilder,
+    transcript: &HttpTranscript,
+    commitment_kind: CommitmentKind,
+) -> Result<(), MinimalDisclosureError> {
+    for (idx, request) in transcript.requests.iter().enumerate() {
+        let host = request.headers_with_name("host").next().ok_or(
+            MinimalDisclosureError::MissingHeader {
+                kind: MessageKind::Request,
+                idx,
+                name: "host",
+            },
+        )?;
+
+        reveal(
+            builder,
+            &request.without_data(),
+            Direction::Sent,
+            commitment_kind,
+        )?;
+        reveal(
+            builder,
+            &request.request.target,
+            Direction::Sent,
+            commitment_kind,
+        )?;
+        reveal(builder, host, Direction::Sent, commitment_kind)?;
+    }
+
+    for (idx, response) in transcript.responses.iter().enumerate() {
+        let content_length = response.headers_with_name("content-length").next().ok_or(
+            MinimalDisclosureError::MissingHeader {
+                kind: MessageKind::Response,
+                idx,
+                name: "content-length",
+            },
+        )?;
+
+        reveal(
+            builder,
+            &response.without_data(),
+            Direction::Received,
+            commitment_kind,
+        )?;
+        reveal(
+            builder,
+            content_length,
+            Direction::Received,
+            commitment_kind,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Reveals the ranges with the commitment which matches them, unless they are revealed already.
+fn reveal(
+    builder: &mut SubstringsProofBuilder,
+    ranges: &dyn ToRangeSet<usize>,
+    direction: Direction,
+    commitment_kind: CommitmentKind,
+) -> Result<(), SubstringsProofBuilderError> {
+    if !builder.is_revealed(ranges, direction) {
+        builder.reveal(ranges, direction, commitment_kind)?;
+    }
+
+    Ok(())
+}
@@ -1,9 +1,11 @@
 //! Tooling for working with HTTP data.
 
 mod commit;
+mod disclose;
 mod session;
 
-pub use commit::{DefaultHttpCommitter, HttpCommit, HttpCommitError};
+pub use commit::{DefaultHttpCommitter, HttpCommit, HttpCommitError, LineHttpCommitter};
+pub use disclose::{reveal_minimal, MinimalDisclosureError};
 pub use session::NotarizedHttpSession;
 
 #[doc(hidden)]
@@ -199,4 +201,52 @@ mod tests {
         assert_eq!(&recv.data()[25..43], b"very-secret-cookie");
         assert_eq!(&recv.data()[180..194], b"Hello World!!!");
     }
+
+    #[test]
+    fn test_http_reveal_minimal() {
+        let transcript_tx = Transcript::new(TX);
+        let transcript_rx = Transcript::new(RX);
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+
+        let transcript = HttpTranscript::parse(&transcript_tx, &transcript_rx).unwrap();
+
+        DefaultHttpCommitter::default()
+            .commit_transcript(&mut builder, &transcript)
+            .unwrap();
+
+        let commitments = builder.build().unwrap();
+
+        let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+        reveal_minimal(&mut builder, &transcript, CommitmentKind::Blake3).unwrap();
+        // Parts which are revealed already are skipped
+        reveal_minimal(&mut builder, &transcript, CommitmentKind::Blake3).unwrap();
+
+        let proof = builder.build().unwrap();
+        let header = fixtures::session_header(commitments.merkle_root(), TX.len(), RX.len());
+        let (sent, recv) = proof.verify(&header).unwrap();
+
+        assert_eq!(&sent.data()[..14], b"GET / HTTP/1.1");
+        assert_eq!(&sent.data()[16..33], b"Host: localhost\r\n");
+        // The request body stays redacted
+        assert!(sent
+            .redacted()
+            .iter_ranges()
+            .any(|range| range.contains(&137)));
+
+        assert_eq!(&recv.data()[..15], b"HTTP/1.1 200 OK");
+        // The cookie header and the body stay redacted
+        assert!(recv
+            .redacted()
+            .iter_ranges()
+            .any(|range| range.contains(&25)));
+        assert!(recv
+            .redacted()
+            .iter_ranges()
+            .any(|range| range.contains(&180)));
+    }
 }
@@ -13,6 +13,7 @@
 
 pub mod http;
 pub mod json;
+pub mod line;
 
 #[doc(hidden)]
 pub use spansy;
@@ -0,0 +1,116 @@
+use std::error::Error;
+
+use tlsn_core::{
+    commitment::{CommitmentKind, TranscriptCommitmentBuilder},
+    Direction,
+};
+
+use crate::line::{Line, LineTranscript};
+
+/// Line commitment error.
+#[derive(Debug, thiserror::Error)]
+#[error("line commitment error: {msg}")]
+pub struct LineCommitError {
+    msg: String,
+    #[source]
+    source: Option<Box<dyn Error + Send + Sync>>,
+}
+
+impl LineCommitError {
+    /// Creates a new line commitment error.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The error message.
+    pub fn new(msg: impl Into<String>) -> Self {
+        Self {
+            msg: msg.into(),
+            source: None,
+        }
+    }
+
+    /// Creates a new line commitment error with a source.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The error message.
+    /// * `source` - The source error.
+    pub fn new_with_source<E>(msg: impl Into<String>, source: E) -> Self
+    where
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        Self {
+            msg: msg.into(),
+            source: Some(source.into()),
+        }
+    }
+
+    /// Returns the error message.
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+}
+
+/// A line committer.
+pub trait LineCommit {
+    /// Commits to a line transcript.
+    ///
+    /// The default implementation commits to each line in both directions separately.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The transcript commitment builder.
+    /// * `transcript` - The transcript to commit.
+    fn commit_transcript(
+        &mut self,
+        builder: &mut TranscriptCommitmentBuilder,
+        transcript: &LineTranscript,
+    ) -> Result<(), LineCommitError> {
+        for line in &transcript.sent {
+            self.commit_line(builder, Direction::Sent, line)?;
+        }
+
+        for line in &transcript.received {
+            self.commit_line(builder, Direction::Received, line)?;
+        }
+
+        Ok(())
+    }
+
+    /// Commits to a line.
+    ///
+    /// The default implementation commits to the line excluding its terminator, skipping empty
+    /// lines and lines which are already committed.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The transcript commitment builder.
+    /// * `direction` - The direction of the line (sent or received).
+    /// * `line` - The line to commit to.
+    fn commit_line(
+        &mut self,
+        builder: &mut TranscriptCommitmentBuilder,
+        direction: Direction,
+        line: &Line,
+    ) -> Result<(), LineCommitError> {
+        if line.is_empty()
+            || builder
+                .get_id(CommitmentKind::Blake3, line.range(), direction)
+                .is_some()
+        {
+            return Ok(());
+        }
+
+        builder
+            .commit(line, direction)
+            .map_err(|e| LineCommitError::new_with_source("failed to commit to line", e))?;
+
+        Ok(())
+    }
+}
+
+/// The default line committer.
+#[derive(Debug, Default, Clone)]
+pub struct DefaultLineCommitter {}
+
+impl LineCommit for DefaultLineCommitter {}
@@ -0,0 +1,182 @@
+//! Tooling for working with line-oriented protocols.
+//!
+//! Many protocols other than HTTP exchange lines of text, e.g. the commands and replies of SMTP
+//! and IMAP, or newline-delimited JSON-RPC. A [`LineTranscript`] splits each direction of a
+//! transcript into lines, which can be committed to and disclosed individually.
+
+mod commit;
+
+use std::ops::Range;
+
+use bytes::Bytes;
+use tlsn_core::Transcript;
+use utils::range::{RangeSet, ToRangeSet};
+
+pub use commit::{DefaultLineCommitter, LineCommit, LineCommitError};
+
+/// A line of a transcript, excluding its line terminator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    range: Range<usize>,
+    data: Bytes,
+}
+
+impl Line {
+    /// Returns the range of the line in the transcript.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns the content of the line.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the content of the line as a string, if it is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.data).ok()
+    }
+
+    /// Returns `true` if the line has no content.
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+}
+
+impl ToRangeSet<usize> for Line {
+    fn to_range_set(&self) -> RangeSet<usize> {
+        self.range.clone().into()
+    }
+}
+
+/// A transcript of a line-oriented protocol.
+#[derive(Debug)]
+pub struct LineTranscript {
+    /// The lines sent to the server.
+    pub sent: Vec<Line>,
+    /// The lines received from the server.
+    pub received: Vec<Line>,
+}
+
+impl LineTranscript {
+    /// Splits the provided transcripts into lines.
+    ///
+    /// Lines are terminated by `\n` or `\r\n`. The last line does not need to be terminated.
+    pub fn parse(tx: &Transcript, rx: &Transcript) -> Self {
+        Self {
+            sent: split_lines(tx),
+            received: split_lines(rx),
+        }
+    }
+}
+
+/// Splits the transcript into lines, see [`LineTranscript::parse`].
+fn split_lines(transcript: &Transcript) -> Vec<Line> {
+    line_ranges(transcript.data(), 0)
+        .map(|range| Line {
+            data: transcript.view(range.clone()).expect("line is in bounds"),
+            range,
+        })
+        .collect()
+}
+
+/// Returns the ranges of the lines in `data`, excluding line terminators, offset by `offset`.
+pub(crate) fn line_ranges(data: &[u8], offset: usize) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = offset;
+    data.split_inclusive(|b| *b == b'\n').map(move |line| {
+        let content = line.strip_suffix(b"\n").unwrap_or(line);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+        let range = start..start + content.len();
+        start += line.len();
+        range
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tlsn_core::{
+        commitment::{CommitmentKind, TranscriptCommitmentBuilder},
+        fixtures,
+        proof::SubstringsProofBuilder,
+        Direction,
+    };
+
+    static TX: &[u8] = b"EHLO client.example\r\nAUTH PLAIN c2VjcmV0\r\n\r\nQUIT\r\n";
+    static RX: &[u8] = b"220 smtp.example ESMTP\r\n250-smtp.example\r\n250 AUTH PLAIN\r\n\
+    235 2.7.0 Authentication successful\r\n221 Bye";
+
+    #[test]
+    fn test_line_parse() {
+        let transcript = LineTranscript::parse(&Transcript::new(TX), &Transcript::new(RX));
+
+        let sent: Vec<_> = transcript
+            .sent
+            .iter()
+            .map(|line| line.as_str().unwrap())
+            .collect();
+        assert_eq!(
+            sent,
+            ["EHLO client.example", "AUTH PLAIN c2VjcmV0", "", "QUIT"]
+        );
+
+        let last = transcript.received.last().unwrap();
+        assert_eq!(last.as_str(), Some("221 Bye"));
+        assert_eq!(&RX[last.range()], b"221 Bye");
+    }
+
+    #[test]
+    fn test_line_prove() {
+        let transcript_tx = Transcript::new(TX);
+        let transcript_rx = Transcript::new(RX);
+
+        let mut builder = TranscriptCommitmentBuilder::new(
+            fixtures::encoding_provider(TX, RX),
+            TX.len(),
+            RX.len(),
+        );
+
+        let transcript = LineTranscript::parse(&transcript_tx, &transcript_rx);
+
+        DefaultLineCommitter::default()
+            .commit_transcript(&mut builder, &transcript)
+            .unwrap();
+
+        let commitments = builder.build().unwrap();
+
+        // Empty lines are not committed to
+        assert!(commitments
+            .get_id_by_info(
+                CommitmentKind::Blake3,
+                &transcript.sent[2].to_range_set(),
+                Direction::Sent
+            )
+            .is_none());
+
+        let mut builder = SubstringsProofBuilder::new(&commitments, &transcript_tx, &transcript_rx);
+
+        // Reveal the greeting of the client and the result of the authentication, but not the
+        // credentials
+        builder
+            .reveal_sent(&transcript.sent[0], CommitmentKind::Blake3)
+            .unwrap()
+            .reveal_recv(&transcript.received[3], CommitmentKind::Blake3)
+            .unwrap();
+
+        let proof = builder.build().unwrap();
+
+        let header = fixtures::session_header(commitments.merkle_root(), TX.len(), RX.len());
+
+        let (sent, recv) = proof.verify(&header).unwrap();
+
+        assert_eq!(
+            &sent.data()[transcript.sent[0].range()],
+            b"EHLO client.example"
+        );
+        assert_eq!(
+            &recv.data()[transcript.received[3].range()],
+            b"235 2.7.0 Authentication successful"
+        );
+    }
+}
@@ -0,0 +1,40 @@
+//! Deviations from the protocol, for testing that the verifier detects a malicious prover.
+//!
+//! These are only reachable with the `adversary` feature and must never be used outside of tests.
+
+use tlsn_core::msg::ProvingInfo;
+use utils::range::{RangeSet, RangeUnion};
+
+/// A deviation from the protocol by the prover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProverDeviation {
+    /// Sends purported cleartext which is inconsistent with the proven transcript.
+    WrongCleartext,
+    /// Reveals a range which exceeds the length of the received transcript.
+    RangeExceedsTranscript,
+}
+
+impl ProverDeviation {
+    /// Applies the deviation to the proving info before it is sent to the verifier.
+    ///
+    /// # Arguments
+    ///
+    /// * `proving_info` - The proving info.
+    /// * `recv_len` - The length of the received transcript.
+    pub(crate) fn apply(self, proving_info: &mut ProvingInfo, recv_len: usize) {
+        match self {
+            ProverDeviation::WrongCleartext => {
+                if let Some(byte) = proving_info.cleartext.first_mut() {
+                    *byte ^= 1;
+                }
+            }
+            ProverDeviation::RangeExceedsTranscript => {
+                proving_info.recv_ids = proving_info
+                    .recv_ids
+                    .union(&RangeSet::from(recv_len..recv_len + 1));
+                proving_info.cleartext.push(0);
+            }
+        }
+    }
+}
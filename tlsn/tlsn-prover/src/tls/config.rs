@@ -3,9 +3,16 @@ use mpz_share_conversion::{ReceiverConfig, SenderConfig};
 use tls_client::RootCertStore;
 use tls_mpc::{MpcTlsCommonConfig, MpcTlsLeaderConfig, TranscriptConfig};
 use tlsn_common::{
-    config::{ot_recv_estimate, ot_send_estimate, DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT},
+    config::{
+        ot_recv_estimate, ot_send_estimate, ByteCount, SessionProfile, DEFAULT_MAX_RECV_LIMIT,
+        DEFAULT_MAX_SENT_LIMIT,
+    },
+    mux::MuxConfig,
     Role,
 };
+use tlsn_core::{
+    msg::Hello, session::PreviousHeaderCommitment, NotaryPublicKey, SignatureAlgorithm,
+};
 
 /// Configuration for the prover
 #[derive(Debug, Clone, derive_builder::Builder)]
@@ -20,11 +27,21 @@ pub struct ProverConfig {
     #[builder(setter(strip_option), default = "default_root_store()")]
     pub(crate) root_cert_store: RootCertStore,
     /// Maximum number of bytes that can be sent.
-    #[builder(default = "DEFAULT_MAX_SENT_LIMIT")]
-    max_sent_data: usize,
+    #[builder(setter(into), default = "DEFAULT_MAX_SENT_LIMIT")]
+    max_sent_data: ByteCount,
     /// Maximum number of bytes that can be received.
-    #[builder(default = "DEFAULT_MAX_RECV_LIMIT")]
-    max_recv_data: usize,
+    #[builder(setter(into), default = "DEFAULT_MAX_RECV_LIMIT")]
+    max_recv_data: ByteCount,
+    /// Multiplexer configuration, which defaults to the configuration of the session profile.
+    #[builder(setter(strip_option), default)]
+    mux_config: Option<MuxConfig>,
+    /// Commitment to a previous session header, which links the notarized session to it.
+    #[builder(setter(strip_option), default)]
+    previous_header: Option<PreviousHeaderCommitment>,
+    /// The expected public key of the notary. If set, the prover rejects session headers signed
+    /// with any other key.
+    #[builder(setter(into, strip_option), default)]
+    notary_public_key: Option<NotaryPublicKey>,
 }
 
 impl ProverConfig {
@@ -33,13 +50,18 @@ impl ProverConfig {
         ProverConfigBuilder::default()
     }
 
+    /// Returns the ID of the notarization session.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
     /// Returns the maximum number of bytes that can be sent.
-    pub fn max_sent_data(&self) -> usize {
+    pub fn max_sent_data(&self) -> ByteCount {
         self.max_sent_data
     }
 
     /// Returns the maximum number of bytes that can be received.
-    pub fn max_recv_data(&self) -> usize {
+    pub fn max_recv_data(&self) -> ByteCount {
         self.max_recv_data
     }
 
@@ -48,20 +70,52 @@ impl ProverConfig {
         &self.server_dns
     }
 
+    /// Returns the session profile, which is selected from the transcript limits.
+    pub fn profile(&self) -> SessionProfile {
+        SessionProfile::from_limits(self.max_sent_data, self.max_recv_data)
+    }
+
+    /// Returns the multiplexer configuration.
+    pub fn mux_config(&self) -> MuxConfig {
+        self.mux_config
+            .clone()
+            .unwrap_or_else(|| self.profile().mux_config())
+    }
+
+    /// Returns the commitment to a previous session header, if one is set.
+    pub fn previous_header(&self) -> Option<&PreviousHeaderCommitment> {
+        self.previous_header.as_ref()
+    }
+
+    /// Returns the expected public key of the notary, if one is set.
+    pub fn notary_public_key(&self) -> Option<&NotaryPublicKey> {
+        self.notary_public_key.as_ref()
+    }
+
+    /// Returns the algorithms of the notary signature which are accepted, which is only the
+    /// algorithm of the expected notary key if one is set.
+    pub fn signature_algorithms(&self) -> Vec<SignatureAlgorithm> {
+        match &self.notary_public_key {
+            Some(key) => vec![key.algorithm()],
+            None => Hello::default().signature_algorithms,
+        }
+    }
+
     pub(crate) fn build_mpc_tls_config(&self) -> MpcTlsLeaderConfig {
         MpcTlsLeaderConfig::builder()
             .common(
                 MpcTlsCommonConfig::builder()
                     .id(format!("{}/mpc_tls", &self.id))
+                    .num_threads(self.profile().mpc_threads())
                     .tx_config(
                         TranscriptConfig::default_tx()
-                            .max_size(self.max_sent_data)
+                            .max_size(self.max_sent_data.get())
                             .build()
                             .unwrap(),
                     )
                     .rx_config(
                         TranscriptConfig::default_rx()
-                            .max_size(self.max_recv_data)
+                            .max_size(self.max_recv_data.get())
                             .build()
                             .unwrap(),
                     )
@@ -28,6 +28,12 @@ pub enum ProverError {
     CommitmentError(#[from] CommitmentError),
     #[error("Range exceeds transcript length")]
     InvalidRange,
+    #[error("session aborted by the verifier: {0}")]
+    Aborted(#[from] tlsn_core::msg::Abort),
+    #[error(transparent)]
+    Incompatible(#[from] tlsn_core::msg::IncompatibleError),
+    #[error("notary signed an inconsistent session header: {0}")]
+    InconsistentHeader(#[from] tlsn_core::session::SessionHeaderVerifyError),
 }
 
 impl From<MpcTlsError> for ProverError {
@@ -1,7 +1,7 @@
 //! This module collects futures which are used by the [Prover].
 
-use super::{state, Prover, ProverControl, ProverError};
-use futures::{future::FusedFuture, Future};
+use super::{error::OTShutdownError, state, Prover, ProverControl, ProverError};
+use futures::{future::FusedFuture, Future, FutureExt};
 use std::pin::Pin;
 
 /// Prover future which must be polled for the TLS connection to make progress.
@@ -73,3 +73,127 @@ impl FusedFuture for OTFuture {
         self.fut.is_terminated()
     }
 }
+
+/// Drives `fut` to completion while polling the background futures of the prover, which must make
+/// progress for `fut` to complete.
+///
+/// The background futures are owned by the prover instead of being spawned, so no work outlives a
+/// failure: once the error is returned and the prover is dropped, all of them are dropped too.
+///
+/// If several futures fail, the error is chosen in the following order:
+///
+/// 1. The error returned by `fut`.
+/// 2. The error returned by the muxer, or an [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof)
+///    IO error if the connection was closed before `fut` completed. The muxer takes precedence over
+///    OT, as OT failures are usually a consequence of the connection failing.
+/// 3. The error returned by OT, or [`OTShutdownError`] if OT stopped before `fut` completed.
+///
+/// If the connection is closed, `fut` is polled once more before failing, as it may only now be
+/// able to handle the last messages of the verifier, e.g. an abort.
+pub(crate) async fn supervise<T>(
+    fut: impl Future<Output = Result<T, ProverError>>,
+    mux_fut: &mut MuxFuture,
+    ot_fut: &mut OTFuture,
+) -> Result<T, ProverError> {
+    let fut = fut.fuse();
+    futures::pin_mut!(fut);
+
+    futures::select_biased! {
+        res = fut => res,
+        res = mux_fut => match fut.now_or_never() {
+            Some(res) => res,
+            None => Err(res.err().unwrap_or_else(|| {
+                std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()
+            })),
+        },
+        res = ot_fut => Err(res.err().unwrap_or_else(|| OTShutdownError.into())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{
+        executor::block_on,
+        future::{pending, poll_fn, ready},
+    };
+    use std::task::Poll;
+
+    type Fut = Pin<Box<dyn FusedFuture<Output = Result<(), ProverError>> + Send + 'static>>;
+
+    fn pending_fut() -> Fut {
+        Box::pin(pending::<Result<(), ProverError>>().fuse())
+    }
+
+    fn ok_fut() -> Fut {
+        Box::pin(ready(Ok::<_, ProverError>(())).fuse())
+    }
+
+    fn ready_on_second_poll_fut() -> Fut {
+        let mut polled = false;
+        Box::pin(
+            poll_fn(move |_| {
+                if std::mem::replace(&mut polled, true) {
+                    Poll::Ready(Ok(()))
+                } else {
+                    Poll::Pending
+                }
+            })
+            .fuse(),
+        )
+    }
+
+    fn err_fut(msg: &str) -> Fut {
+        Box::pin(
+            ready(Err::<(), _>(ProverError::NotarizationError(
+                msg.to_string(),
+            )))
+            .fuse(),
+        )
+    }
+
+    fn run(fut: Fut, mux_fut: Fut, ot_fut: Fut) -> Result<(), ProverError> {
+        block_on(supervise(
+            fut,
+            &mut MuxFuture { fut: mux_fut },
+            &mut OTFuture { fut: ot_fut },
+        ))
+    }
+
+    fn assert_msg(res: Result<(), ProverError>, expected: &str) {
+        match res {
+            Err(ProverError::NotarizationError(msg)) => assert_eq!(msg, expected),
+            res => panic!("unexpected result: {res:?}"),
+        }
+    }
+
+    #[test]
+    fn test_supervise_ok() {
+        assert!(run(ok_fut(), pending_fut(), pending_fut()).is_ok());
+    }
+
+    #[test]
+    fn test_supervise_error_precedence() {
+        assert_msg(run(err_fut("fut"), err_fut("mux"), err_fut("ot")), "fut");
+        assert_msg(run(pending_fut(), err_fut("mux"), err_fut("ot")), "mux");
+        assert_msg(run(pending_fut(), pending_fut(), err_fut("ot")), "ot");
+    }
+
+    #[test]
+    fn test_supervise_early_shutdown() {
+        assert!(matches!(
+            run(pending_fut(), ok_fut(), pending_fut()),
+            Err(ProverError::IOError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+        assert!(matches!(
+            run(pending_fut(), pending_fut(), ok_fut()),
+            Err(ProverError::MpcError(err)) if err.is::<OTShutdownError>()
+        ));
+    }
+
+    #[test]
+    fn test_supervise_polls_after_shutdown() {
+        // `fut` can handle the last messages of the verifier once the connection is closed
+        assert!(run(ready_on_second_poll_fut(), ok_fut(), pending_fut()).is_ok());
+    }
+}
@@ -5,6 +5,9 @@
 //! The TLS prover provides a low-level API, see the [`HTTP prover`](crate::http) which provides abstractions for working
 //! with HTTP sessions.
 
+#[cfg(feature = "adversary")]
+#[doc(hidden)]
+pub mod adversary;
 mod config;
 mod error;
 mod future;
@@ -15,14 +18,15 @@ pub mod state;
 pub use config::{ProverConfig, ProverConfigBuilder, ProverConfigBuilderError};
 pub use error::ProverError;
 pub use future::ProverFuture;
+pub use tlsn_common::config::ByteCount;
 use tlsn_common::{
+    expect_msg_or_abort,
     mux::{attach_mux, MuxControl},
     Role,
 };
 
-use error::OTShutdownError;
-use future::{MuxFuture, OTFuture};
-use futures::{AsyncRead, AsyncWrite, FutureExt, StreamExt, TryFutureExt};
+use future::{supervise, MuxFuture, OTFuture};
+use futures::{AsyncRead, AsyncWrite, FutureExt, SinkExt, StreamExt, TryFutureExt};
 use mpz_garble::{config::Role as DEAPRole, protocol::deap::DEAPVm};
 use mpz_ot::{
     actor::kos::{ReceiverActor, SenderActor, SharedReceiver, SharedSender},
@@ -35,12 +39,17 @@ use std::sync::Arc;
 use tls_client::{ClientConnection, ServerName as TlsServerName};
 use tls_client_async::{bind_client, ClosedConnection, TlsConnection};
 use tls_mpc::{setup_components, LeaderCtrl, MpcTlsLeader, TlsRole};
-use tlsn_core::transcript::Transcript;
+use tlsn_core::{
+    msg::{Hello, TlsnMessage},
+    transcript::Transcript,
+};
 use utils_aio::mux::MuxChannel;
 
 #[cfg(feature = "formats")]
 use crate::http::{state as http_state, HttpProver, HttpProverError};
 
+#[cfg(feature = "tracing")]
+use tlsn_common::span::{self, SETUP, TLS_CONNECTION};
 #[cfg(feature = "tracing")]
 use tracing::{debug, debug_span, instrument, Instrument};
 
@@ -72,16 +81,35 @@ impl Prover<state::Initialized> {
     /// # Arguments
     ///
     /// * `socket` - The socket to the notary.
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = SETUP,
+            level = "debug",
+            skip_all,
+            fields(
+                { span::ATTR_PHASE } = "setup",
+                { span::ATTR_PEER_ROLE } = "prover",
+                { span::ATTR_SESSION_ID_HASH } = %span::session_id_hash(self.config.id()),
+            ),
+            err
+        )
+    )]
     pub async fn setup<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
         self,
         socket: S,
     ) -> Result<Prover<state::Setup>, ProverError> {
-        let (mut mux, mux_ctrl) = attach_mux(socket, Role::Prover);
+        let (mut mux, mux_ctrl) = attach_mux(socket, Role::Prover, &self.config.mux_config());
 
         let mut mux_fut = MuxFuture {
             fut: Box::pin(async move { mux.run().await.map_err(ProverError::from) }.fuse()),
         };
 
+        futures::select! {
+            res = handshake(mux_ctrl.clone(), &self.config).fuse() => res?,
+            _ = (&mut mux_fut).fuse() => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
+        };
+
         let mpc_setup_fut = setup_mpc_backend(&self.config, mux_ctrl.clone());
         let (mpc_tls, vm, _, gf2, ot_fut) = futures::select! {
             res = mpc_setup_fut.fuse() => res?,
@@ -141,20 +169,28 @@ impl Prover<state::Setup> {
         let (conn, conn_fut) = bind_client(socket, client);
 
         let start_time = web_time::UNIX_EPOCH.elapsed().unwrap().as_secs();
+        #[cfg(feature = "tracing")]
+        let session_id_hash = span::session_id_hash(self.config.id());
 
         let fut = Box::pin({
             let mpc_ctrl = mpc_ctrl.clone();
             #[allow(clippy::let_and_return)]
             let fut = async move {
                 let conn_fut = async {
-                    let ClosedConnection { sent, recv, .. } = futures::select! {
-                        res = conn_fut.fuse() => res?,
-                        _ = ot_fut => return Err(OTShutdownError)?,
-                        _ = mux_fut => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
-                    };
+                    let ClosedConnection { sent, recv, .. } = supervise(
+                        conn_fut.map_err(ProverError::from),
+                        &mut mux_fut,
+                        &mut ot_fut,
+                    )
+                    .await?;
 
                     mpc_ctrl.close_connection().await?;
 
+                    #[cfg(feature = "tracing")]
+                    tracing::Span::current()
+                        .record(span::ATTR_BYTES_SENT, sent.len())
+                        .record(span::ATTR_BYTES_RECV, recv.len());
+
                     Ok::<_, ProverError>((sent, recv))
                 };
 
@@ -180,7 +216,14 @@ impl Prover<state::Setup> {
                 })
             };
             #[cfg(feature = "tracing")]
-            let fut = fut.instrument(debug_span!("prover_tls_connection"));
+            let fut = fut.instrument(debug_span!(
+                TLS_CONNECTION,
+                { span::ATTR_PHASE } = "tls_connection",
+                { span::ATTR_PEER_ROLE } = "prover",
+                { span::ATTR_SESSION_ID_HASH } = %session_id_hash,
+                { span::ATTR_BYTES_SENT } = tracing::field::Empty,
+                { span::ATTR_BYTES_RECV } = tracing::field::Empty,
+            ));
             fut
         });
 
@@ -234,6 +277,25 @@ impl Prover<state::Closed> {
     }
 }
 
+/// Exchanges protocol versions and capabilities with the verifier.
+#[cfg_attr(feature = "tracing", instrument(level = "debug", skip_all, err))]
+async fn handshake(mut mux: MuxControl, config: &ProverConfig) -> Result<(), ProverError> {
+    // Unlike the other protocol channels, the handshake is not sequenced, so that the messages of
+    // peers using another protocol version can still be decoded
+    let mut channel = mux.get_channel("handshake").await?;
+
+    let hello = Hello {
+        signature_algorithms: config.signature_algorithms(),
+        ..Default::default()
+    };
+    channel.send(TlsnMessage::Hello(hello.clone())).await?;
+
+    let peer_hello = expect_msg_or_abort!(channel, TlsnMessage::Hello, ProverError)?;
+    hello.check_compatible(&peer_hello)?;
+
+    Ok(())
+}
+
 /// Performs a setup of the various MPC subprotocols.
 #[cfg_attr(feature = "tracing", instrument(level = "debug", skip_all, err))]
 #[allow(clippy::type_complexity)]
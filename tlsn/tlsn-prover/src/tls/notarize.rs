@@ -2,19 +2,22 @@
 //!
 //! The prover deals with a TLS verifier that is only a notary.
 
-use crate::tls::error::OTShutdownError;
+use crate::tls::future::supervise;
 
 use super::{ff::ShareConversionReveal, state::Notarize, Prover, ProverError};
-use futures::{FutureExt, SinkExt, StreamExt};
+use futures::{SinkExt, TryFutureExt};
+#[cfg(feature = "tracing")]
+use tlsn_common::span::{self, NOTARIZE};
+use tlsn_common::{channel::open_sequenced_channel, expect_msg_or_abort};
 use tlsn_core::{
     commitment::TranscriptCommitmentBuilder,
-    msg::{SignedSessionHeader, TlsnMessage},
+    msg::{Abort, AbortCode, SignedSessionHeader, TlsnMessage},
+    session::SignedCompactHeader,
     transcript::Transcript,
     NotarizedSession, ServerName, SessionData,
 };
 #[cfg(feature = "tracing")]
 use tracing::instrument;
-use utils_aio::{expect_msg_or_err, mux::MuxChannel};
 
 impl Prover<Notarize> {
     /// Returns the transcript of the sent requests
@@ -33,8 +36,31 @@ impl Prover<Notarize> {
     }
 
     /// Finalize the notarization returning a [`NotarizedSession`]
-    #[cfg_attr(feature = "tracing", instrument(level = "info", skip(self), err))]
     pub async fn finalize(self) -> Result<NotarizedSession, ProverError> {
+        self.finalize_with_compact_header()
+            .await
+            .map(|(session, _)| session)
+    }
+
+    /// Finalize the notarization returning a [`NotarizedSession`] and the compact header signed
+    /// by the notary, for light clients which can not verify the full session header.
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = NOTARIZE,
+            level = "info",
+            skip_all,
+            fields(
+                { span::ATTR_PHASE } = "notarize",
+                { span::ATTR_PEER_ROLE } = "prover",
+                { span::ATTR_SESSION_ID_HASH } = %span::session_id_hash(self.config.id()),
+            ),
+            err
+        )
+    )]
+    pub async fn finalize_with_compact_header(
+        self,
+    ) -> Result<(NotarizedSession, SignedCompactHeader), ProverError> {
         let Notarize {
             mut mux_ctrl,
             mut mux_fut,
@@ -49,7 +75,39 @@ impl Prover<Notarize> {
             builder,
         } = self.state;
 
-        let commitments = builder.build()?;
+        let commitments = match builder.build() {
+            Ok(commitments) => commitments,
+            Err(e) => {
+                // Let the notary know why the session ends, instead of it waiting for the
+                // commitments until the connection is closed.
+                let abort_fut = async {
+                    let mut channel = open_sequenced_channel(&mut mux_ctrl, "notarize").await?;
+                    channel
+                        .send(TlsnMessage::Abort(Abort::new(
+                            AbortCode::Internal,
+                            e.to_string(),
+                        )))
+                        .await?;
+
+                    Ok::<_, ProverError>(())
+                };
+
+                // Close the connection gracefully so that the abort is written out. The session
+                // failed already, so errors doing so are not reported.
+                if supervise(abort_fut, &mut mux_fut, &mut ot_fut)
+                    .await
+                    .is_ok()
+                {
+                    let mut mux_ctrl = mux_ctrl.into_inner();
+                    _ = futures::try_join!(
+                        mux_ctrl.close().map_err(ProverError::from),
+                        &mut mux_fut
+                    );
+                }
+
+                return Err(e.into());
+            }
+        };
 
         let session_data = SessionData::new(
             ServerName::Dns(self.config.server_dns().to_string()),
@@ -60,13 +118,17 @@ impl Prover<Notarize> {
         );
 
         let merkle_root = session_data.commitments().merkle_root();
+        let previous_header = self.config.previous_header().copied();
 
-        let mut notarize_fut = Box::pin(async move {
-            let mut channel = mux_ctrl.get_channel("notarize").await?;
+        let notarize_fut = Box::pin(async move {
+            let mut channel = open_sequenced_channel(&mut mux_ctrl, "notarize").await?;
 
             channel
                 .send(TlsnMessage::TranscriptCommitmentRoot(merkle_root))
                 .await?;
+            channel
+                .send(TlsnMessage::PreviousHeader(previous_header))
+                .await?;
 
             let notary_encoder_seed = vm
                 .finalize()
@@ -81,35 +143,70 @@ impl Prover<Notarize> {
                 .await
                 .map_err(|e| ProverError::MpcError(Box::new(e)))?;
 
-            let signed_header = expect_msg_or_err!(channel, TlsnMessage::SignedSessionHeader)?;
+            let signed_header =
+                expect_msg_or_abort!(channel, TlsnMessage::SignedSessionHeader, ProverError)?;
 
             Ok::<_, ProverError>((notary_encoder_seed, signed_header))
-        })
-        .fuse();
-
-        let (notary_encoder_seed, SignedSessionHeader { header, signature }) = futures::select_biased! {
-            res = notarize_fut => res?,
-            _ = ot_fut => return Err(OTShutdownError)?,
-            _ = &mut mux_fut => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
-        };
+        });
+
+        let (
+            notary_encoder_seed,
+            SignedSessionHeader {
+                header,
+                signature,
+                notary_public_key,
+                compact,
+            },
+        ) = supervise(notarize_fut, &mut mux_fut, &mut ot_fut).await?;
         // Wait for the notary to correctly close the connection
         mux_fut.await?;
 
         // Check the header is consistent with the Prover's view
-        header
-            .verify(
-                start_time,
-                &server_public_key,
-                &session_data.commitments().merkle_root(),
-                &notary_encoder_seed,
-                &session_data.session_info().handshake_decommitment,
-            )
-            .map_err(|_| {
-                ProverError::NotarizationError(
-                    "notary signed an inconsistent session header".to_string(),
-                )
-            })?;
-
-        Ok(NotarizedSession::new(header, Some(signature), session_data))
+        header.verify(
+            start_time,
+            &server_public_key,
+            &session_data.commitments().merkle_root(),
+            &notary_encoder_seed,
+            &session_data.session_info().handshake_decommitment,
+            previous_header.as_ref(),
+        )?;
+
+        if let Some(expected) = self.config.notary_public_key() {
+            if expected.key_id() != notary_public_key.key_id() {
+                return Err(ProverError::NotarizationError(
+                    "session header was signed with an unexpected notary key".to_string(),
+                ));
+            }
+        }
+
+        if !self
+            .config
+            .signature_algorithms()
+            .contains(&notary_public_key.algorithm())
+        {
+            return Err(ProverError::NotarizationError(format!(
+                "session header was signed with an unsupported algorithm: {:?}",
+                notary_public_key.algorithm()
+            )));
+        }
+
+        signature
+            .verify(&header.to_bytes(), notary_public_key.clone())
+            .map_err(|e| ProverError::NotarizationError(e.to_string()))?;
+
+        if !compact.header.is_summary_of(&header) {
+            return Err(ProverError::NotarizationError(
+                "notary signed an inconsistent compact header".to_string(),
+            ));
+        }
+
+        compact
+            .verify(&notary_public_key)
+            .map_err(|e| ProverError::NotarizationError(e.to_string()))?;
+
+        Ok((
+            NotarizedSession::new(header, Some(signature), session_data),
+            compact,
+        ))
     }
 }
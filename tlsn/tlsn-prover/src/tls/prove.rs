@@ -3,20 +3,24 @@
 //! Here the prover deals with a verifier directly, so there is no notary involved. Instead
 //! the verifier directly verifies parts of the transcript.
 
+#[cfg(feature = "adversary")]
+use super::adversary::ProverDeviation;
 use super::{state::Prove as ProveState, Prover, ProverError};
-use crate::tls::error::OTShutdownError;
-use futures::{FutureExt, SinkExt};
+use crate::tls::future::supervise;
+use futures::{FutureExt, SinkExt, StreamExt};
 use mpz_garble::{Memory, Prove, Vm};
 use mpz_share_conversion::ShareConversionReveal;
+use tlsn_common::{channel::open_sequenced_channel, msg::unexpected_msg};
 use tlsn_core::{
     msg::TlsnMessage, proof::SessionInfo, transcript::get_value_ids, Direction, ServerName,
     Transcript,
 };
 use utils::range::{RangeSet, RangeUnion};
-use utils_aio::mux::MuxChannel;
 
 #[cfg(feature = "tracing")]
-use tracing::info;
+use tlsn_common::span::{self, PROVE};
+#[cfg(feature = "tracing")]
+use tracing::{info, instrument};
 
 impl Prover<ProveState> {
     /// Returns the transcript of the sent requests
@@ -66,15 +70,52 @@ impl Prover<ProveState> {
     }
 
     /// Prove transcript values
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = PROVE,
+            level = "debug",
+            skip_all,
+            fields(
+                { span::ATTR_PHASE } = "prove",
+                { span::ATTR_PEER_ROLE } = "prover",
+                { span::ATTR_SESSION_ID_HASH } = %span::session_id_hash(self.config.id()),
+            ),
+            err
+        )
+    )]
     pub async fn prove(&mut self) -> Result<(), ProverError> {
+        self.prove_inner(
+            #[cfg(feature = "adversary")]
+            None,
+        )
+        .await
+    }
+
+    /// Prove transcript values, deviating from the protocol as specified.
+    ///
+    /// This is only meant for testing that the verifier detects a malicious prover.
+    #[cfg(feature = "adversary")]
+    pub async fn prove_with_deviation(
+        &mut self,
+        deviation: ProverDeviation,
+    ) -> Result<(), ProverError> {
+        self.prove_inner(Some(deviation)).await
+    }
+
+    async fn prove_inner(
+        &mut self,
+        #[cfg(feature = "adversary")] deviation: Option<ProverDeviation>,
+    ) -> Result<(), ProverError> {
         let mut proving_info = std::mem::take(&mut self.state.proving_info);
 
-        let mut prove_fut = Box::pin(async {
+        let prove_fut = Box::pin(async {
             // Create a new channel and vm thread if not already present
             let channel = if let Some(ref mut channel) = self.state.channel {
                 channel
             } else {
-                self.state.channel = Some(self.state.mux_ctrl.get_channel("prove-verify").await?);
+                self.state.channel =
+                    Some(open_sequenced_channel(&mut self.state.mux_ctrl, "prove-verify").await?);
                 self.state.channel.as_mut().unwrap()
             };
 
@@ -126,29 +167,31 @@ impl Prover<ProveState> {
                 .for_each(|r| cleartext.extend_from_slice(&self.state.transcript_rx.data()[r]));
             proving_info.cleartext = cleartext;
 
+            #[cfg(feature = "adversary")]
+            if let Some(deviation) = deviation {
+                deviation.apply(&mut proving_info, self.state.transcript_rx.data().len());
+            }
+
             // Send the proving info to the verifier
             channel.send(TlsnMessage::ProvingInfo(proving_info)).await?;
 
             #[cfg(feature = "tracing")]
             info!("Sent proving info to verifier");
 
-            // Prove the revealed transcript parts
-            prove_thread.prove(value_refs.as_slice()).await?;
+            // Prove the revealed transcript parts. The verifier aborts instead of taking part in
+            // the proof if it rejects the proving info.
+            futures::select_biased! {
+                msg = channel.next().fuse() => return Err(unexpected_msg::<ProverError>(msg)),
+                res = prove_thread.prove(value_refs.as_slice()).fuse() => res?,
+            }
 
             #[cfg(feature = "tracing")]
             info!("Successfully proved cleartext");
 
             Ok::<_, ProverError>(())
-        })
-        .fuse();
-
-        futures::select_biased! {
-            res = prove_fut => res?,
-            _ = &mut self.state.ot_fut => return Err(OTShutdownError)?,
-            _ = &mut self.state.mux_fut => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
-        };
+        });
 
-        Ok(())
+        supervise(prove_fut, &mut self.state.mux_fut, &mut self.state.ot_fut).await
     }
 
     /// Finalize the proving
@@ -169,8 +212,8 @@ impl Prover<ProveState> {
             handshake_decommitment,
         };
 
-        let mut finalize_fut = Box::pin(async move {
-            let mut channel = mux_ctrl.get_channel("finalize").await?;
+        let finalize_fut = Box::pin(async move {
+            let mut channel = open_sequenced_channel(&mut mux_ctrl, "finalize").await?;
 
             _ = vm
                 .finalize()
@@ -188,19 +231,20 @@ impl Prover<ProveState> {
             // Send session_info to the verifier
             channel.send(TlsnMessage::SessionInfo(session_info)).await?;
 
-            Ok::<_, ProverError>(())
-        })
-        .fuse();
+            Ok::<_, ProverError>(channel)
+        });
 
-        futures::select_biased! {
-            res = finalize_fut => res?,
-            _ = ot_fut => return Err(OTShutdownError)?,
-            _ = &mut mux_fut => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
-        };
+        let mut channel = supervise(finalize_fut, &mut mux_fut, &mut ot_fut).await?;
 
         // We need to wait for the verifier to correctly close the connection. Otherwise the prover
         // would rush ahead and close the connection before the verifier has finished.
-        mux_fut.await?;
-        Ok(())
+        let res = mux_fut.await;
+
+        // The verifier sends an abort before closing the connection if it rejects the session.
+        if let Some(Some(Ok(TlsnMessage::Abort(abort)))) = channel.next().now_or_never() {
+            return Err(ProverError::Aborted(abort));
+        }
+
+        res
     }
 }
@@ -9,13 +9,12 @@ use mpz_share_conversion::{ConverterSender, Gf2_128};
 use std::collections::HashMap;
 use tls_core::{handshake::HandshakeData, key::PublicKey};
 use tls_mpc::MpcTlsLeader;
-use tlsn_common::mux::MuxControl;
+use tlsn_common::{channel::SequencedChannel, mux::MuxControl};
 use tlsn_core::{
     commitment::TranscriptCommitmentBuilder,
     msg::{ProvingInfo, TlsnMessage},
     Transcript,
 };
-use utils_aio::duplex::Duplex;
 
 /// Entry state
 pub struct Initialized;
@@ -122,7 +121,7 @@ pub struct Prove {
     pub(crate) transcript_rx: Transcript,
 
     pub(crate) proving_info: ProvingInfo,
-    pub(crate) channel: Option<Box<dyn Duplex<TlsnMessage>>>,
+    pub(crate) channel: Option<SequencedChannel<TlsnMessage>>,
     pub(crate) prove_thread: Option<DEAPThread<SharedSender, SharedReceiver>>,
 }
 
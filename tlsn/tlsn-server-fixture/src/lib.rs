@@ -1,15 +1,20 @@
 use std::{
     collections::HashMap,
+    convert::Infallible,
+    io::Write,
     sync::{Arc, Mutex},
 };
 
 use async_rustls::TlsAcceptor;
 use axum::{
-    extract::{Query, State},
-    response::{Html, Json},
+    body::StreamBody,
+    extract::{Path, Query, State},
+    http::header,
+    response::{Html, IntoResponse, Json, Response},
     routing::get,
     Router,
 };
+use flate2::{write::GzEncoder, Compression};
 use futures::{channel::oneshot, AsyncRead, AsyncWrite};
 use hyper::{body::Bytes, server::conn::Http, StatusCode};
 use rustls::{Certificate, PrivateKey, ServerConfig};
@@ -35,6 +40,7 @@ fn app(state: AppState) -> Router {
         .route("/bytes", get(bytes))
         .route("/formats/json", get(json))
         .route("/formats/html", get(html))
+        .route("/providers/:provider", get(provider))
         .with_state(Arc::new(Mutex::new(state)))
 }
 
@@ -118,3 +124,55 @@ async fn html(
 
     Html(include_str!("data/4kb.html"))
 }
+
+/// Returns a canned response mimicking the payload of a data provider.
+fn canned_response(provider: &str) -> Option<&'static str> {
+    match provider {
+        "dummyjson" => Some(include_str!("data/providers/dummyjson.json")),
+        "github" => Some(include_str!("data/providers/github.json")),
+        "kaggle" => Some(include_str!("data/providers/kaggle.json")),
+        _ => None,
+    }
+}
+
+async fn provider(
+    State(state): State<Arc<Mutex<AppState>>>,
+    Path(provider): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    if params.get("shutdown").is_some() {
+        _ = state.lock().unwrap().shutdown.take().unwrap().send(());
+    }
+
+    let Some(body) = canned_response(&provider) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let content_type = [(header::CONTENT_TYPE, "application/json")];
+
+    match params.get("encoding").map(String::as_str) {
+        None => (content_type, body).into_response(),
+        Some("chunked") => {
+            // The body is sent with chunked transfer encoding as its length is not known upfront
+            let chunks = body
+                .as_bytes()
+                .chunks(128)
+                .map(|chunk| Ok::<_, Infallible>(Bytes::from_static(chunk)));
+
+            (content_type, StreamBody::new(futures::stream::iter(chunks))).into_response()
+        }
+        Some("gzip") => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body.as_bytes()).unwrap();
+
+            (
+                [
+                    (header::CONTENT_TYPE, "application/json"),
+                    (header::CONTENT_ENCODING, "gzip"),
+                ],
+                encoder.finish().unwrap(),
+            )
+                .into_response()
+        }
+        Some(_) => StatusCode::BAD_REQUEST.into_response(),
+    }
+}
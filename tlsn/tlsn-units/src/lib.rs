@@ -0,0 +1,115 @@
+//! Units of transcript sizes and limits.
+//!
+//! The counts can only be created from a bare `usize` with [`ByteCount::new`] or
+//! [`BitCount::new`], so that the unit is always spelled out.
+
+#![deny(missing_docs, unreachable_pub, unused_must_use)]
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A number of bytes.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct ByteCount(usize);
+
+impl ByteCount {
+    /// Creates a new byte count.
+    pub const fn new(bytes: usize) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the number of bytes.
+    pub const fn get(self) -> usize {
+        self.0
+    }
+
+    /// Returns the number of bits, or `None` if it overflows.
+    pub fn to_bits(self) -> Option<BitCount> {
+        self.0.checked_mul(8).map(BitCount)
+    }
+
+    /// Adds two byte counts, returning `None` on overflow.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+}
+
+impl From<ByteCount> for usize {
+    fn from(bytes: ByteCount) -> Self {
+        bytes.0
+    }
+}
+
+impl fmt::Display for ByteCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes", self.0)
+    }
+}
+
+/// A number of bits.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct BitCount(usize);
+
+impl BitCount {
+    /// Creates a new bit count.
+    pub const fn new(bits: usize) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the number of bits.
+    pub const fn get(self) -> usize {
+        self.0
+    }
+
+    /// Returns the number of whole bytes needed to hold the bits.
+    pub fn to_bytes_ceil(self) -> ByteCount {
+        ByteCount(self.0.div_ceil(8))
+    }
+}
+
+impl From<BitCount> for usize {
+    fn from(bits: BitCount) -> Self {
+        bits.0
+    }
+}
+
+impl fmt::Display for BitCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bits", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_count_conversions() {
+        let bytes = ByteCount::new(3);
+        assert_eq!(bytes.to_bits(), Some(BitCount::new(24)));
+        assert_eq!(BitCount::new(25).to_bytes_ceil(), ByteCount::new(4));
+        assert_eq!(ByteCount::new(usize::MAX).to_bits(), None);
+        assert_eq!(bytes.checked_add(ByteCount::new(usize::MAX)), None);
+    }
+
+    #[test]
+    fn test_serde_transparent() {
+        assert_eq!(
+            serde_json::to_string(&ByteCount::new(4096)).unwrap(),
+            "4096"
+        );
+        assert_eq!(
+            serde_json::from_str::<ByteCount>("4096").unwrap(),
+            ByteCount::new(4096)
+        );
+    }
+}
@@ -0,0 +1,47 @@
+//! Deviations from the protocol, for testing that the prover detects a malicious verifier.
+//!
+//! These are only reachable with the `adversary` feature and must never be used outside of tests.
+
+use tlsn_core::merkle::MerkleRoot;
+
+use super::VerifierError;
+
+/// A deviation from the protocol by the verifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VerifierDeviation {
+    /// Signs a session header with a different encoder seed than the one used in the MPC.
+    WrongEncoderSeed,
+    /// Signs a session header with a different Merkle root than the one sent by the prover.
+    WrongMerkleRoot,
+    /// Closes the connection after finalizing the MPC, without signing the session header.
+    CloseBeforeSigning,
+}
+
+impl VerifierDeviation {
+    /// Applies the deviation to the values signed in the session header, returning the values to
+    /// sign instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoder_seed` - The encoder seed used in the MPC.
+    /// * `merkle_root` - The Merkle root sent by the prover.
+    pub(crate) fn apply(
+        self,
+        mut encoder_seed: [u8; 32],
+        mut merkle_root: MerkleRoot,
+    ) -> Result<([u8; 32], MerkleRoot), VerifierError> {
+        match self {
+            VerifierDeviation::WrongEncoderSeed => encoder_seed[0] ^= 1,
+            VerifierDeviation::WrongMerkleRoot => merkle_root = MerkleRoot::from([0u8; 32]),
+            VerifierDeviation::CloseBeforeSigning => {
+                return Err(VerifierError::from(std::io::Error::new(
+                    std::io::ErrorKind::ConnectionAborted,
+                    "closed the connection before signing",
+                )));
+            }
+        }
+
+        Ok((encoder_seed, merkle_root))
+    }
+}
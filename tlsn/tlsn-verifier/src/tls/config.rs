@@ -4,10 +4,18 @@ use std::fmt::{Debug, Formatter, Result};
 use tls_core::verify::{ServerCertVerifier, WebPkiVerifier};
 use tls_mpc::{MpcTlsCommonConfig, MpcTlsFollowerConfig, TranscriptConfig};
 use tlsn_common::{
-    config::{ot_recv_estimate, ot_send_estimate, DEFAULT_MAX_RECV_LIMIT, DEFAULT_MAX_SENT_LIMIT},
+    config::{
+        ot_recv_estimate, ot_send_estimate, ByteCount, SessionProfile, DEFAULT_MAX_RECV_LIMIT,
+        DEFAULT_MAX_SENT_LIMIT,
+    },
+    mux::MuxConfig,
     Role,
 };
-use tlsn_core::proof::default_cert_verifier;
+use tlsn_core::{
+    msg::Hello,
+    proof::{default_cert_verifier, CertificatePolicy},
+    SignatureAlgorithm,
+};
 
 /// Configuration for the [`Verifier`](crate::tls::Verifier)
 #[allow(missing_docs)]
@@ -17,11 +25,21 @@ pub struct VerifierConfig {
     #[builder(setter(into))]
     id: String,
     /// Maximum number of bytes that can be sent.
-    #[builder(default = "DEFAULT_MAX_SENT_LIMIT")]
-    max_sent_data: usize,
+    #[builder(setter(into), default = "DEFAULT_MAX_SENT_LIMIT")]
+    max_sent_data: ByteCount,
     /// Maximum number of bytes that can be received.
-    #[builder(default = "DEFAULT_MAX_RECV_LIMIT")]
-    max_recv_data: usize,
+    #[builder(setter(into), default = "DEFAULT_MAX_RECV_LIMIT")]
+    max_recv_data: ByteCount,
+    /// Multiplexer configuration, which defaults to the configuration of the session profile.
+    #[builder(setter(strip_option), default)]
+    mux_config: Option<MuxConfig>,
+    /// Policy for the server certificate and key exchange.
+    #[builder(default)]
+    cert_policy: CertificatePolicy,
+    /// The algorithm of the key the session header is signed with, which is announced to the
+    /// prover. If not set, all supported algorithms are announced.
+    #[builder(setter(strip_option), default)]
+    signature_algorithm: Option<SignatureAlgorithm>,
     #[builder(
         pattern = "owned",
         setter(strip_option),
@@ -36,6 +54,9 @@ impl Debug for VerifierConfig {
             .field("id", &self.id)
             .field("max_sent_data", &self.max_sent_data)
             .field("max_recv_data", &self.max_recv_data)
+            .field("mux_config", &self.mux_config)
+            .field("cert_policy", &self.cert_policy)
+            .field("signature_algorithm", &self.signature_algorithm)
             .field("cert_verifier", &"_")
             .finish()
     }
@@ -53,15 +74,40 @@ impl VerifierConfig {
     }
 
     /// Returns the maximum number of bytes that can be sent.
-    pub fn max_sent_data(&self) -> usize {
+    pub fn max_sent_data(&self) -> ByteCount {
         self.max_sent_data
     }
 
     /// Returns the maximum number of bytes that can be received.
-    pub fn max_recv_data(&self) -> usize {
+    pub fn max_recv_data(&self) -> ByteCount {
         self.max_recv_data
     }
 
+    /// Returns the session profile, which is selected from the transcript limits.
+    pub fn profile(&self) -> SessionProfile {
+        SessionProfile::from_limits(self.max_sent_data, self.max_recv_data)
+    }
+
+    /// Returns the multiplexer configuration.
+    pub fn mux_config(&self) -> MuxConfig {
+        self.mux_config
+            .clone()
+            .unwrap_or_else(|| self.profile().mux_config())
+    }
+
+    /// Returns the policy for the server certificate and key exchange.
+    pub fn cert_policy(&self) -> &CertificatePolicy {
+        &self.cert_policy
+    }
+
+    /// Returns the algorithms of the notary signature which are announced to the prover.
+    pub fn signature_algorithms(&self) -> Vec<SignatureAlgorithm> {
+        match self.signature_algorithm {
+            Some(algorithm) => vec![algorithm],
+            None => Hello::default().signature_algorithms,
+        }
+    }
+
     /// Get the certificate verifier.
     pub fn cert_verifier(&self) -> &impl ServerCertVerifier {
         self.cert_verifier
@@ -96,15 +142,16 @@ impl VerifierConfig {
             .common(
                 MpcTlsCommonConfig::builder()
                     .id(format!("{}/mpc_tls", &self.id))
+                    .num_threads(self.profile().mpc_threads())
                     .tx_config(
                         TranscriptConfig::default_tx()
-                            .max_size(self.max_sent_data)
+                            .max_size(self.max_sent_data.get())
                             .build()
                             .unwrap(),
                     )
                     .rx_config(
                         TranscriptConfig::default_rx()
-                            .max_size(self.max_recv_data)
+                            .max_size(self.max_recv_data.get())
                             .build()
                             .unwrap(),
                     )
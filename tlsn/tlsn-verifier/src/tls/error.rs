@@ -13,6 +13,10 @@ pub enum VerifierError {
     MpcError(Box<dyn Error + Send + Sync + 'static>),
     #[error("Range exceeds transcript length")]
     InvalidRange,
+    #[error("session aborted by the prover: {0}")]
+    Aborted(#[from] tlsn_core::msg::Abort),
+    #[error(transparent)]
+    Incompatible(#[from] tlsn_core::msg::IncompatibleError),
 }
 
 impl From<MpcTlsError> for VerifierError {
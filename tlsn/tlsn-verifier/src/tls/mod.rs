@@ -1,5 +1,8 @@
 //! TLS Verifier
 
+#[cfg(feature = "adversary")]
+#[doc(hidden)]
+pub mod adversary;
 pub(crate) mod config;
 mod error;
 mod future;
@@ -9,6 +12,7 @@ mod verify;
 
 pub use config::{VerifierConfig, VerifierConfigBuilder, VerifierConfigBuilderError};
 pub use error::VerifierError;
+pub use tlsn_common::config::ByteCount;
 
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -16,7 +20,7 @@ use crate::tls::future::OTFuture;
 use future::MuxFuture;
 use futures::{
     stream::{SplitSink, SplitStream},
-    AsyncRead, AsyncWrite, FutureExt, StreamExt, TryFutureExt,
+    AsyncRead, AsyncWrite, FutureExt, SinkExt, StreamExt, TryFutureExt,
 };
 use mpz_garble::{config::Role as GarbleRole, protocol::deap::DEAPVm};
 use mpz_ot::{
@@ -27,16 +31,23 @@ use mpz_ot::{
 };
 use mpz_share_conversion as ff;
 use rand::Rng;
-use signature::Signer;
+use signature::{Keypair, Signer};
 use state::{Notarize, Verify};
 use tls_mpc::{setup_components, MpcTlsFollower, MpcTlsFollowerData, TlsRole};
 use tlsn_common::{
+    expect_msg_or_abort,
     mux::{attach_mux, MuxControl},
     Role,
 };
-use tlsn_core::{proof::SessionInfo, RedactedTranscript, SessionHeader, Signature};
+use tlsn_core::{
+    msg::{Abort, AbortCode, Hello, TlsnMessage},
+    proof::SessionInfo,
+    NotaryPublicKey, RedactedTranscript, SessionHeader, Signature,
+};
 use utils_aio::{duplex::Duplex, mux::MuxChannel};
 
+#[cfg(feature = "tracing")]
+use tlsn_common::span::{self, SETUP, TLS_CONNECTION};
 #[cfg(feature = "tracing")]
 use tracing::{debug, info, instrument};
 
@@ -71,16 +82,35 @@ impl Verifier<state::Initialized> {
     /// # Arguments
     ///
     /// * `socket` - The socket to the prover.
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = SETUP,
+            level = "debug",
+            skip_all,
+            fields(
+                { span::ATTR_PHASE } = "setup",
+                { span::ATTR_PEER_ROLE } = "verifier",
+                { span::ATTR_SESSION_ID_HASH } = %span::session_id_hash(self.config.id()),
+            ),
+            err
+        )
+    )]
     pub async fn setup<S: AsyncWrite + AsyncRead + Send + Unpin + 'static>(
         self,
         socket: S,
     ) -> Result<Verifier<state::Setup>, VerifierError> {
-        let (mut mux, mux_ctrl) = attach_mux(socket, Role::Verifier);
+        let (mut mux, mux_ctrl) = attach_mux(socket, Role::Verifier, &self.config.mux_config());
 
         let mut mux_fut = MuxFuture {
             fut: Box::pin(async move { mux.run().await.map_err(VerifierError::from) }.fuse()),
         };
 
+        futures::select! {
+            res = handshake(mux_ctrl.clone(), &self.config).fuse() => res?,
+            _ = &mut mux_fut => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
+        };
+
         let encoder_seed: [u8; 32] = rand::rngs::OsRng.gen();
         let mpc_setup_fut = setup_mpc_backend(&self.config, mux_ctrl.clone(), encoder_seed);
         let (mpc_tls, vm, ot_send, ot_recv, gf2, ot_fut) = futures::select! {
@@ -110,7 +140,7 @@ impl Verifier<state::Initialized> {
     pub async fn notarize<S: AsyncWrite + AsyncRead + Send + Unpin + 'static, T>(
         self,
         socket: S,
-        signer: &impl Signer<T>,
+        signer: &(impl Signer<T> + Keypair<VerifyingKey = impl Into<NotaryPublicKey>>),
     ) -> Result<SessionHeader, VerifierError>
     where
         T: Into<Signature>,
@@ -141,6 +171,22 @@ impl Verifier<state::Initialized> {
 
 impl Verifier<state::Setup> {
     /// Runs the verifier until the TLS connection is closed.
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = TLS_CONNECTION,
+            level = "debug",
+            skip_all,
+            fields(
+                { span::ATTR_PHASE } = "tls_connection",
+                { span::ATTR_PEER_ROLE } = "verifier",
+                { span::ATTR_SESSION_ID_HASH } = %span::session_id_hash(self.config.id()),
+                { span::ATTR_BYTES_SENT } = tracing::field::Empty,
+                { span::ATTR_BYTES_RECV } = tracing::field::Empty,
+            ),
+            err
+        )
+    )]
     pub async fn run(self) -> Result<Verifier<state::Closed>, VerifierError> {
         let state::Setup {
             mux_ctrl,
@@ -173,7 +219,12 @@ impl Verifier<state::Setup> {
         };
 
         #[cfg(feature = "tracing")]
-        info!("Finished TLS session");
+        {
+            tracing::Span::current()
+                .record(span::ATTR_BYTES_SENT, sent_len)
+                .record(span::ATTR_BYTES_RECV, recv_len);
+            info!("Finished TLS session");
+        }
 
         // TODO: We should be able to skip this commitment and verify the handshake directly.
         let handshake_commitment = handshake_commitment.expect("handshake commitment is set");
@@ -223,6 +274,37 @@ impl Verifier<state::Closed> {
     }
 }
 
+/// Exchanges protocol versions and capabilities with the prover.
+///
+/// The prover is sent an [`Abort`] if it is not compatible.
+#[cfg_attr(feature = "tracing", instrument(level = "debug", skip_all, err))]
+async fn handshake(mut mux_ctrl: MuxControl, config: &VerifierConfig) -> Result<(), VerifierError> {
+    // Unlike the other protocol channels, the handshake is not sequenced, so that the messages of
+    // peers using another protocol version can still be decoded
+    let mut channel = mux_ctrl.get_channel("handshake").await?;
+
+    let hello = Hello {
+        signature_algorithms: config.signature_algorithms(),
+        ..Default::default()
+    };
+    let peer_hello = expect_msg_or_abort!(channel, TlsnMessage::Hello, VerifierError)?;
+
+    if let Err(e) = hello.check_compatible(&peer_hello) {
+        channel
+            .send(TlsnMessage::Abort(Abort::new(
+                AbortCode::Incompatible,
+                e.to_string(),
+            )))
+            .await?;
+
+        return Err(e.into());
+    }
+
+    channel.send(TlsnMessage::Hello(hello)).await?;
+
+    Ok(())
+}
+
 /// Performs a setup of the various MPC subprotocols.
 #[cfg_attr(feature = "tracing", instrument(level = "debug", skip_all, err))]
 #[allow(clippy::type_complexity)]
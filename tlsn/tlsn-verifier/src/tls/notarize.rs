@@ -2,23 +2,78 @@
 //!
 //! The TLS verifier is only a notary.
 
+#[cfg(feature = "adversary")]
+use super::adversary::VerifierDeviation;
 use super::{state::Notarize, Verifier, VerifierError};
-use futures::{FutureExt, SinkExt, StreamExt, TryFutureExt};
+use futures::{FutureExt, SinkExt, TryFutureExt};
 use mpz_core::serialize::CanonicalSerialize;
 use mpz_share_conversion::ShareConversionVerify;
-use signature::Signer;
+use signature::{Keypair, Signer};
+use tlsn_common::{channel::open_sequenced_channel, expect_msg_or_abort};
 use tlsn_core::{
     msg::{SignedSessionHeader, TlsnMessage},
-    HandshakeSummary, SessionHeader, Signature,
+    session::{CompactHeader, SignedCompactHeader},
+    HandshakeSummary, NotaryPublicKey, SessionHeader, Signature,
 };
-use utils_aio::{expect_msg_or_err, mux::MuxChannel};
 
 #[cfg(feature = "tracing")]
-use tracing::info;
+use tlsn_common::span::{self, NOTARIZE};
+#[cfg(feature = "tracing")]
+use tracing::{info, instrument};
 
 impl Verifier<Notarize> {
     /// Notarizes the TLS session.
-    pub async fn finalize<T>(self, signer: &impl Signer<T>) -> Result<SessionHeader, VerifierError>
+    ///
+    /// The notary signs both the session header and its [`CompactHeader`].
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = NOTARIZE,
+            level = "info",
+            skip_all,
+            fields(
+                { span::ATTR_PHASE } = "notarize",
+                { span::ATTR_PEER_ROLE } = "verifier",
+                { span::ATTR_SESSION_ID_HASH } = %span::session_id_hash(self.config.id()),
+            ),
+            err
+        )
+    )]
+    pub async fn finalize<T>(
+        self,
+        signer: &(impl Signer<T> + Keypair<VerifyingKey = impl Into<NotaryPublicKey>>),
+    ) -> Result<SessionHeader, VerifierError>
+    where
+        T: Into<Signature>,
+    {
+        self.finalize_inner(
+            signer,
+            #[cfg(feature = "adversary")]
+            None,
+        )
+        .await
+    }
+
+    /// Notarizes the TLS session, deviating from the protocol as specified.
+    ///
+    /// This is only meant for testing that the prover detects a malicious notary.
+    #[cfg(feature = "adversary")]
+    pub async fn finalize_with_deviation<T>(
+        self,
+        signer: &(impl Signer<T> + Keypair<VerifyingKey = impl Into<NotaryPublicKey>>),
+        deviation: VerifierDeviation,
+    ) -> Result<SessionHeader, VerifierError>
+    where
+        T: Into<Signature>,
+    {
+        self.finalize_inner(signer, Some(deviation)).await
+    }
+
+    async fn finalize_inner<T>(
+        self,
+        signer: &(impl Signer<T> + Keypair<VerifyingKey = impl Into<NotaryPublicKey>>),
+        #[cfg(feature = "adversary")] deviation: Option<VerifierDeviation>,
+    ) -> Result<SessionHeader, VerifierError>
     where
         T: Into<Signature>,
     {
@@ -39,10 +94,15 @@ impl Verifier<Notarize> {
         } = self.state;
 
         let notarize_fut = async {
-            let mut notarize_channel = mux_ctrl.get_channel("notarize").await?;
+            let mut notarize_channel = open_sequenced_channel(&mut mux_ctrl, "notarize").await?;
 
-            let merkle_root =
-                expect_msg_or_err!(notarize_channel, TlsnMessage::TranscriptCommitmentRoot)?;
+            let merkle_root = expect_msg_or_abort!(
+                notarize_channel,
+                TlsnMessage::TranscriptCommitmentRoot,
+                VerifierError
+            )?;
+            let previous_header =
+                expect_msg_or_abort!(notarize_channel, TlsnMessage::PreviousHeader, VerifierError)?;
 
             // Finalize all MPC before signing the session header
             let (mut ot_sender_actor, _, _) = futures::try_join!(
@@ -64,19 +124,31 @@ impl Verifier<Notarize> {
             #[cfg(feature = "tracing")]
             info!("Finalized all MPC");
 
+            #[cfg(feature = "adversary")]
+            let (encoder_seed, merkle_root) = match deviation {
+                Some(deviation) => deviation.apply(encoder_seed, merkle_root)?,
+                None => (encoder_seed, merkle_root),
+            };
+
             let handshake_summary =
                 HandshakeSummary::new(start_time, server_ephemeral_key, handshake_commitment);
 
+            let notary_public_key: NotaryPublicKey = signer.verifying_key().into();
+
             let session_header = SessionHeader::new(
                 encoder_seed,
                 merkle_root,
                 sent_len,
                 recv_len,
                 handshake_summary,
-            );
+            )
+            .with_previous(previous_header);
 
             let signature = signer.sign(&session_header.to_bytes());
 
+            let compact_header = CompactHeader::new(&session_header, &notary_public_key);
+            let compact_signature = signer.sign(&compact_header.signing_bytes());
+
             #[cfg(feature = "tracing")]
             info!("Signed session header");
 
@@ -84,6 +156,11 @@ impl Verifier<Notarize> {
                 .send(TlsnMessage::SignedSessionHeader(SignedSessionHeader {
                     header: session_header.clone(),
                     signature: signature.into(),
+                    notary_public_key,
+                    compact: SignedCompactHeader {
+                        header: compact_header,
+                        signature: compact_signature.into(),
+                    },
                 }))
                 .await?;
 
@@ -6,9 +6,8 @@ use mpz_ot::actor::kos::{SharedReceiver, SharedSender};
 use mpz_share_conversion::{ConverterReceiver, Gf2_128};
 use tls_core::key::PublicKey;
 use tls_mpc::MpcTlsFollower;
-use tlsn_common::mux::MuxControl;
+use tlsn_common::{channel::SequencedChannel, mux::MuxControl};
 use tlsn_core::msg::TlsnMessage;
-use utils_aio::duplex::Duplex;
 
 use crate::tls::future::{MuxFuture, OTFuture};
 
@@ -114,7 +113,7 @@ pub struct Verify {
     pub(crate) sent_len: usize,
     pub(crate) recv_len: usize,
 
-    pub(crate) channel: Option<Box<dyn Duplex<TlsnMessage>>>,
+    pub(crate) channel: Option<SequencedChannel<TlsnMessage>>,
     pub(crate) verify_thread: Option<DEAPThread<SharedSender, SharedReceiver>>,
 }
 
@@ -2,19 +2,23 @@
 //!
 //! The TLS verifier is an application-specific verifier.
 
-use super::{state::Verify as VerifyState, Verifier, VerifierError};
-use futures::{FutureExt, StreamExt, TryFutureExt};
+use super::{future::MuxFuture, state::Verify as VerifyState, Verifier, VerifierError};
+use futures::{future::FusedFuture, FutureExt, SinkExt, TryFutureExt};
 use mpz_circuits::types::Value;
 use mpz_garble::{Memory, Verify, Vm};
 use mpz_share_conversion::ShareConversionVerify;
+use tlsn_common::{channel::open_sequenced_channel, expect_msg_or_abort, mux::MuxControl};
 use tlsn_core::{
-    msg::TlsnMessage, proof::SessionInfo, transcript::get_value_ids, Direction, HandshakeSummary,
-    RedactedTranscript, TranscriptSlice,
+    msg::{Abort, AbortCode, TlsnMessage},
+    proof::{SessionInfo, SessionProofError},
+    transcript::get_value_ids,
+    Direction, HandshakeSummary, RedactedTranscript, TranscriptSlice,
 };
-use utils_aio::{expect_msg_or_err, mux::MuxChannel};
 
 #[cfg(feature = "tracing")]
-use tracing::info;
+use tlsn_common::span::{self, PROVE};
+#[cfg(feature = "tracing")]
+use tracing::{info, instrument};
 
 impl Verifier<VerifyState> {
     /// Receives the **purported** transcript from the Prover.
@@ -22,15 +26,31 @@ impl Verifier<VerifyState> {
     /// # Warning
     ///
     /// The content of the received transcripts can not be considered authentic until after finalization.
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            name = PROVE,
+            level = "debug",
+            skip_all,
+            fields(
+                { span::ATTR_PHASE } = "prove",
+                { span::ATTR_PEER_ROLE } = "verifier",
+                { span::ATTR_SESSION_ID_HASH } = %span::session_id_hash(self.config.id()),
+            ),
+            err
+        )
+    )]
     pub async fn receive(
         &mut self,
     ) -> Result<(RedactedTranscript, RedactedTranscript), VerifierError> {
+        let mut aborted = false;
         let verify_fut = async {
             // Create a new channel and vm thread if not already present
             let channel = if let Some(ref mut channel) = self.state.channel {
                 channel
             } else {
-                self.state.channel = Some(self.state.mux_ctrl.get_channel("prove-verify").await?);
+                self.state.channel =
+                    Some(open_sequenced_channel(&mut self.state.mux_ctrl, "prove-verify").await?);
                 self.state.channel.as_mut().unwrap()
             };
 
@@ -42,7 +62,8 @@ impl Verifier<VerifyState> {
             };
 
             // Receive the proving info from the prover
-            let mut proving_info = expect_msg_or_err!(channel, TlsnMessage::ProvingInfo)?;
+            let mut proving_info =
+                expect_msg_or_abort!(channel, TlsnMessage::ProvingInfo, VerifierError)?;
             let mut cleartext = proving_info.cleartext.clone();
 
             #[cfg(feature = "tracing")]
@@ -52,6 +73,14 @@ impl Verifier<VerifyState> {
             if proving_info.sent_ids.max().unwrap_or_default() > self.state.sent_len
                 || proving_info.recv_ids.max().unwrap_or_default() > self.state.recv_len
             {
+                channel
+                    .send(TlsnMessage::Abort(Abort::new(
+                        AbortCode::PolicyViolation,
+                        "range exceeds transcript length",
+                    )))
+                    .await?;
+                aborted = true;
+
                 return Err(VerifierError::InvalidRange);
             }
 
@@ -124,10 +153,16 @@ impl Verifier<VerifyState> {
             Ok::<_, VerifierError>((sent_redacted, recv_redacted))
         };
 
-        futures::select! {
+        let res = futures::select! {
             res = verify_fut.fuse() => res,
             _ = &mut self.state.mux_fut => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
+        };
+
+        if aborted {
+            close_after_abort(&self.state.mux_ctrl, &mut self.state.mux_fut).await;
         }
+
+        res
     }
 
     /// Verify the TLS session.
@@ -146,8 +181,9 @@ impl Verifier<VerifyState> {
             ..
         } = self.state;
 
+        let mut aborted = false;
         let finalize_fut = async {
-            let mut channel = mux_ctrl.get_channel("finalize").await?;
+            let mut channel = open_sequenced_channel(&mut mux_ctrl, "finalize").await?;
 
             // Finalize all MPC
             let (mut ot_sender_actor, _, _) = futures::try_join!(
@@ -166,27 +202,50 @@ impl Verifier<VerifyState> {
                 .await
                 .map_err(|e| VerifierError::MpcError(Box::new(e)))?;
 
-            let session_info = expect_msg_or_err!(channel, TlsnMessage::SessionInfo)?;
+            let session_info =
+                expect_msg_or_abort!(channel, TlsnMessage::SessionInfo, VerifierError)?;
 
             #[cfg(feature = "tracing")]
             info!("Finalized all MPC");
 
+            let handshake_summary =
+                HandshakeSummary::new(start_time, server_ephemeral_key, handshake_commitment);
+
+            // Verify the TLS session
+            if let Err(e) = session_info.verify_with_policy(
+                &handshake_summary,
+                self.config.cert_verifier(),
+                self.config.cert_policy(),
+            ) {
+                if let SessionProofError::CertificatePolicy(e) = &e {
+                    channel
+                        .send(TlsnMessage::Abort(Abort::new(
+                            AbortCode::PolicyViolation,
+                            e.to_string(),
+                        )))
+                        .await?;
+                    aborted = true;
+                }
+
+                return Err(e.into());
+            }
+
+            #[cfg(feature = "tracing")]
+            info!("Successfully verified session");
+
             Ok::<_, VerifierError>(session_info)
         };
 
-        let session_info = futures::select! {
-            res = finalize_fut.fuse() => res?,
+        let res = futures::select! {
+            res = finalize_fut.fuse() => res,
             _ = &mut mux_fut => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
         };
 
-        let handshake_summary =
-            HandshakeSummary::new(start_time, server_ephemeral_key, handshake_commitment);
-
-        // Verify the TLS session
-        session_info.verify(&handshake_summary, self.config.cert_verifier())?;
+        if aborted {
+            close_after_abort(&mux_ctrl, &mut mux_fut).await;
+        }
 
-        #[cfg(feature = "tracing")]
-        info!("Successfully verified session");
+        let session_info = res?;
 
         let mut mux_ctrl = mux_ctrl.into_inner();
 
@@ -195,3 +254,19 @@ impl Verifier<VerifyState> {
         Ok(session_info)
     }
 }
+
+/// Closes the connection after an abort was sent to the prover.
+///
+/// The muxer is dropped together with the verifier, which can discard the abort before it was
+/// written out. Closing the connection gracefully flushes it, so the prover learns why the
+/// session failed.
+async fn close_after_abort(mux_ctrl: &MuxControl, mux_fut: &mut MuxFuture) {
+    if mux_fut.is_terminated() {
+        return;
+    }
+
+    let mut mux_ctrl = mux_ctrl.clone().into_inner();
+
+    // The session failed already, so an error closing the connection is not reported.
+    _ = futures::try_join!(mux_ctrl.close().map_err(VerifierError::from), mux_fut);
+}